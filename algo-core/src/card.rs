@@ -1,37 +1,46 @@
+use crate::settings::{GameSettings, SettingsError};
 use anyhow::{bail, Context as _};
 use itertools::Itertools as _;
-use rand::seq::SliceRandom as _;
+use rand::{rngs::StdRng, seq::SliceRandom as _, SeedableRng as _};
 use serde::{Deserialize, Serialize};
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeMap, fmt, ops::RangeInclusive, str::FromStr};
 
 /// Possible card colors
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum CardColor {
     Black,
     White,
+    Red,
+    Green,
 }
 
 impl CardColor {
-    /// Returns a background color in RGB format.
-    pub fn bg_color_rgb(&self) -> [u8; 3] {
-        match self {
-            Self::Black => [0; 3],
-            Self::White => [u8::MAX; 3],
-        }
-    }
+    /// All the colors, any of which can be included in the deck by [`GameSettings`].
+    ///
+    /// [`GameSettings`]: `crate::settings::GameSettings`
+    pub const ALL: [Self; 4] = [Self::Black, Self::White, Self::Red, Self::Green];
 
-    /// Returns a text color in RGB format.
-    pub fn text_color_rgb(&self) -> [u8; 3] {
-        match self {
-            Self::Black => [u8::MAX; 3],
-            Self::White => [0; 3],
-        }
+    /// Returns how the cards of the color are painted,
+    /// unless [`GameSettings::palette`] says otherwise.
+    ///
+    /// [`GameSettings::palette`]: `crate::settings::GameSettings::palette`
+    pub fn default_rgb(&self) -> CardRgb {
+        let (bg, text) = match self {
+            Self::Black => ([0; 3], [u8::MAX; 3]),
+            Self::White => ([u8::MAX; 3], [0; 3]),
+            Self::Red => ([190, 30, 45], [u8::MAX; 3]),
+            Self::Green => ([20, 120, 60], [u8::MAX; 3]),
+        };
+        CardRgb { bg, text }
     }
 
-    fn symbol(&self) -> u8 {
+    /// Returns a character that identifies the color.
+    pub fn symbol(&self) -> char {
         match self {
-            CardColor::Black => b'B',
-            CardColor::White => b'W',
+            Self::Black => 'B',
+            Self::White => 'W',
+            Self::Red => 'R',
+            Self::Green => 'G',
         }
     }
 }
@@ -43,6 +52,8 @@ impl FromStr for CardColor {
         let color = match &*s.trim().to_lowercase() {
             "black" => Self::Black,
             "white" => Self::White,
+            "red" => Self::Red,
+            "green" => Self::Green,
             unknown => bail!("unknown CardColor: {}", unknown),
         };
 
@@ -50,6 +61,15 @@ impl FromStr for CardColor {
     }
 }
 
+/// The colors a card is painted with, in RGB format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct CardRgb {
+    /// The background color.
+    pub bg: [u8; 3],
+    /// The color of the number.
+    pub text: [u8; 3],
+}
+
 pub type CardNumberType = u8;
 
 /// A number assigned to the card.
@@ -57,6 +77,17 @@ pub type CardNumberType = u8;
 #[serde(transparent)]
 pub struct CardNumber(pub CardNumberType);
 
+impl CardNumber {
+    /// Returns the card numbers in the range, in ascending order.
+    ///
+    /// Pass [`GameSettings::card_numbers`] to get the numbers used in a game.
+    ///
+    /// [`GameSettings::card_numbers`]: `crate::settings::GameSettings::card_numbers`
+    pub fn all(range: RangeInclusive<CardNumberType>) -> impl Iterator<Item = Self> + Clone {
+        range.map(Self)
+    }
+}
+
 impl From<CardNumberType> for CardNumber {
     fn from(value: CardNumberType) -> Self {
         Self(value)
@@ -92,6 +123,31 @@ impl Ord for Card {
     }
 }
 
+/// Returns the indices of the cards on a field that can be attacked, from the left.
+///
+/// [`Game::legal_attack_targets`] is built on this, so that the clients offer the same targets.
+///
+/// [`Game::legal_attack_targets`]: `crate::Game::legal_attack_targets`
+pub fn field_attack_targets(
+    field: impl IntoIterator<Item = CardPubInfo>,
+) -> impl Iterator<Item = u32> {
+    field
+        .into_iter()
+        .enumerate()
+        .filter(|(_, card)| card.is_attackable())
+        .map(|(idx, _)| idx as u32)
+}
+
+/// Returns the index to insert the card at to keep the field sorted.
+///
+/// An identical card, which the deck has if `GameSettings::copies_per_card` is more than 1,
+/// is placed after the existing ones.
+///
+/// The server and the clients must agree on this, as the cards on a field are addressed by index.
+pub fn field_insert_index(field: &[Card], card: &Card) -> usize {
+    field.partition_point(|v| v <= card)
+}
+
 impl Card {
     pub fn new(number: CardNumber, color: CardColor) -> Self {
         Self {
@@ -138,6 +194,11 @@ impl CardPubInfo {
     pub fn revealed(&self) -> bool {
         self.revealed
     }
+
+    /// Returns `true` if the card can be chosen as an attack target.
+    pub fn is_attackable(&self) -> bool {
+        !self.revealed
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -157,12 +218,42 @@ impl CardPrivInfo {
 
 // TODO: Refactor the struct into an enum to eliminate an invalid state
 //       where a card is revealed but its number is missing.
+//
+// Deserialization rejects the invalid state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "RawCardView")]
 pub struct CardView {
     pub pub_info: CardPubInfo,
     pub priv_info: Option<CardPrivInfo>,
 }
 
+/// A `CardView` that has not been validated yet.
+#[derive(Deserialize)]
+struct RawCardView {
+    pub_info: CardPubInfo,
+    priv_info: Option<CardPrivInfo>,
+}
+
+impl TryFrom<RawCardView> for CardView {
+    type Error = InconsistentCardView;
+
+    fn try_from(raw: RawCardView) -> Result<Self, Self::Error> {
+        let ret = Self {
+            pub_info: raw.pub_info,
+            priv_info: raw.priv_info,
+        };
+
+        if !ret.is_consistent() {
+            return Err(InconsistentCardView(ret));
+        }
+        Ok(ret)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("inconsistent CardView: the card is revealed but its number is missing: {0:?}")]
+pub struct InconsistentCardView(pub CardView);
+
 impl CardView {
     /// Constructs a new `CardView`.
     ///
@@ -179,6 +270,16 @@ impl CardView {
         }
     }
 
+    /// Returns `false` if the card is revealed but its number is missing.
+    pub fn is_consistent(&self) -> bool {
+        !(self.pub_info.revealed && self.priv_info.is_none())
+    }
+
+    /// Returns the number of the card if it is revealed or privately known.
+    pub fn number_known(&self) -> Option<CardNumber> {
+        self.priv_info.map(|v| v.number)
+    }
+
     fn full(card: Card) -> Self {
         Self {
             pub_info: card.pub_info,
@@ -207,16 +308,12 @@ impl CardView {
 
 impl std::fmt::Display for CardView {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let revealed = self.pub_info.revealed;
-        write!(
-            f,
-            "{:?}-{}{}{}",
-            self.pub_info.color,
-            if revealed { "" } else { "(" },
-            self.priv_info
-                .map_or("?".into(), |v| v.number.0.to_string()),
-            if revealed { "" } else { ")" },
-        )
+        let color = self.pub_info.color;
+        match (self.priv_info, self.pub_info.revealed) {
+            (None, _) => write!(f, "{:?}-?", color),
+            (Some(v), true) => write!(f, "{:?}-{}", color, v.number.0),
+            (Some(v), false) => write!(f, "{:?}-({})", color, v.number.0),
+        }
     }
 }
 
@@ -224,10 +321,11 @@ impl FromStr for CardView {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split('-');
-        let color = iter.next().context("CardColor is missing")?.parse()?;
+        // Splitting only once keeps the sign of a negative number, to be rejected with the range.
+        let (color, number) = s.split_once('-').context("CardNumber is missing")?;
+        let color = color.parse()?;
 
-        let ret = match iter.next().context("CardNumber is missing")?.trim() {
+        let ret = match number.trim() {
             "?" => Self {
                 pub_info: CardPubInfo {
                     color,
@@ -240,14 +338,14 @@ impl FromStr for CardView {
                     color,
                     revealed: false,
                 },
-                priv_info: Some(CardPrivInfo::new(CardNumber(n[1..n.len() - 1].parse()?))),
+                priv_info: Some(CardPrivInfo::new(parse_card_number(&n[1..n.len() - 1])?)),
             },
             n => Self {
                 pub_info: CardPubInfo {
                     color,
                     revealed: true,
                 },
-                priv_info: Some(CardPrivInfo::new(CardNumber(n.parse()?))),
+                priv_info: Some(CardPrivInfo::new(parse_card_number(n)?)),
             },
         };
 
@@ -255,9 +353,20 @@ impl FromStr for CardView {
     }
 }
 
+fn parse_card_number(s: &str) -> anyhow::Result<CardNumber> {
+    let n: i64 = s
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid card number: {:?}", s))?;
+
+    let n = CardNumberType::try_from(n)
+        .with_context(|| format!("card number must be 0..={}: {}", CardNumberType::MAX, n))?;
+    Ok(CardNumber(n))
+}
+
 /// Returns a list of card instances to add to the talon.
 pub(crate) fn create_cards<J>(
-    numbers: impl IntoIterator<Item = CardNumberType>,
+    numbers: impl IntoIterator<Item = CardNumber>,
     colors: J,
 ) -> impl Iterator<Item = Card>
 where
@@ -267,12 +376,12 @@ where
     numbers
         .into_iter()
         .cartesian_product(colors)
-        .map(|(n, c)| Card::new(n.into(), c))
+        .map(|(n, c)| Card::new(n, c))
 }
 
 /// A stack of cards that players can draw from during the game.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct Talon {
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Talon {
     cards: Vec<Card>,
 }
 
@@ -284,10 +393,37 @@ impl FromIterator<Card> for Talon {
 }
 
 impl Talon {
+    /// Rebuilds the talon dealt by a game created with the settings and the seed,
+    /// in the same order.
+    ///
+    /// See [`Game::for_2_players_seeded`].
+    ///
+    /// [`Game::for_2_players_seeded`]: `crate::Game::for_2_players_seeded`
+    pub fn from_settings_and_seed(
+        settings: GameSettings,
+        seed: u64,
+    ) -> Result<Self, SettingsError> {
+        Self::from_settings_and_rng(settings, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Builds the cards of the settings and shuffles them with the RNG.
+    pub(crate) fn from_settings_and_rng(
+        settings: GameSettings,
+        rng: &mut impl rand::Rng,
+    ) -> Result<Self, SettingsError> {
+        let mut talon: Self = settings.build_cards()?.into_iter().collect();
+        talon.shuffle(rng);
+        Ok(talon)
+    }
+
     pub fn len(&self) -> usize {
         self.cards.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
     pub fn shuffle(&mut self, mut rng: impl rand::Rng) {
         self.cards.shuffle(&mut rng);
     }
@@ -296,24 +432,360 @@ impl Talon {
         self.cards.pop()
     }
 
+    /// Same as [`draw`], but returns `Err` if the talon is empty.
+    ///
+    /// [`draw`]: `Talon::draw`
+    pub fn draw_checked(&mut self) -> Result<Card, TalonEmpty> {
+        self.draw().ok_or(TalonEmpty)
+    }
+
+    /// Returns up to `n` cards from the top without drawing them.
+    ///
+    /// The first element is the card to be drawn next.
+    pub fn peek_n(&self, n: usize) -> Vec<CardView> {
+        self.cards
+            .iter()
+            .rev()
+            .take(n)
+            .map(|v| v.full_view())
+            .collect()
+    }
+
+    /// Puts the card back into the talon.
+    ///
+    /// `idx` is counted from the top, so returning a drawn card with `idx: 0`
+    /// restores the draw order exactly.
+    ///
+    /// Returns `Err` if `idx` is greater than the number of cards in the talon.
+    pub fn return_card(&mut self, card: Card, idx: usize) -> anyhow::Result<()> {
+        let Some(pos) = self.cards.len().checked_sub(idx) else {
+            bail!(
+                "index out of range: {} (talon size: {})",
+                idx,
+                self.cards.len()
+            );
+        };
+
+        self.cards.insert(pos, card);
+        Ok(())
+    }
+
     pub fn view(&self) -> TalonView {
         TalonView {
             cards: self.cards.iter().map(|v| v.pub_info.color).collect(),
+            ordered: true,
         }
     }
 }
 
+/// An error indicating that there are no cards left to draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("no cards left in the talon")]
+pub struct TalonEmpty;
+
 #[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct TalonView {
     pub cards: Vec<CardColor>,
+    /// Whether `cards` are in the order they are drawn.
+    ///
+    /// Otherwise they are sorted by color, revealing only how many cards of each color remain.
+    pub ordered: bool,
+}
+
+impl TalonView {
+    /// Returns the view without the drawing order.
+    pub fn shuffled_public(&self) -> Self {
+        let mut cards = self.cards.clone();
+        cards.sort();
+
+        Self {
+            cards,
+            ordered: false,
+        }
+    }
+
+    /// Returns the number of cards of each color.
+    pub fn color_counts(&self) -> BTreeMap<CardColor, usize> {
+        self.cards.iter().copied().counts().into_iter().collect()
+    }
 }
 
 impl fmt::Debug for TalonView {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let cards = self.cards.iter().map(|v| v.symbol()).collect::<Vec<_>>();
-        f.debug_tuple("TalonView")
-            .field(&unsafe { String::from_utf8_unchecked(cards) })
+        let cards = self.cards.iter().map(|v| v.symbol()).collect::<String>();
+        f.debug_struct("TalonView")
+            .field("cards", &cards)
+            .field("ordered", &self.ordered)
             .finish()
-        // SAFETY: CardColor::symbol returns only valid UTF-8.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &[(CardNumberType, CardColor)]) -> Vec<Card> {
+        cards
+            .iter()
+            .map(|(number, color)| Card::new(CardNumber(*number), *color))
+            .collect()
+    }
+
+    #[test]
+    fn card_number_all_covers_range() {
+        let numbers = |range| CardNumber::all(range).map(|n| n.0).collect::<Vec<_>>();
+
+        assert_eq!(numbers(0..=11), (0..12).collect::<Vec<_>>());
+        assert_eq!(numbers(0..=0), [0]);
+
+        // The largest range ends without overflowing.
+        let all = numbers(0..=CardNumberType::MAX);
+        assert_eq!(all.len(), 256);
+        assert_eq!((all[0], all[255]), (0, CardNumberType::MAX));
+    }
+
+    #[test]
+    fn deck_is_built_from_card_number_all() {
+        for max_card_number in [0, 11, CardNumberType::MAX] {
+            let settings = GameSettings {
+                max_card_number,
+                copies_per_card: 2,
+                ..Default::default()
+            };
+            let colors = settings.card_colors.clone();
+
+            let deck = settings.clone().build_cards().unwrap();
+            let expected = CardNumber::all(settings.card_numbers())
+                .cartesian_product(colors)
+                .flat_map(|(n, c)| [Card::new(n, c); 2])
+                .collect::<Vec<_>>();
+            assert_eq!(deck, expected);
+        }
+    }
+
+    #[test]
+    fn palette_paints_cards() {
+        let blue = CardRgb {
+            bg: [0, 0, 200],
+            text: [u8::MAX; 3],
+        };
+        let mut settings = GameSettings {
+            card_colors: vec![CardColor::Black, CardColor::White, CardColor::Red],
+            palette: BTreeMap::from([(CardColor::Red, blue)]),
+            ..Default::default()
+        };
+        settings.validate().unwrap();
+        assert_eq!(settings.card_rgb(CardColor::Red), blue);
+        assert_eq!(
+            settings.card_rgb(CardColor::White),
+            CardColor::White.default_rgb()
+        );
+
+        settings.palette.insert(
+            CardColor::Red,
+            CardRgb {
+                text: blue.bg,
+                ..blue
+            },
+        );
+        assert_eq!(
+            settings.validate(),
+            Err(SettingsError::IllegibleColor(CardColor::Red))
+        );
+    }
+
+    #[test]
+    fn field_insert_index_keeps_field_sorted() {
+        use CardColor::*;
+        let field = cards(&[(1, Black), (3, White), (5, Black), (5, White)]);
+
+        let idx = |number, color| field_insert_index(&field, &Card::new(CardNumber(number), color));
+
+        // Both ends.
+        assert_eq!(idx(0, White), 0);
+        assert_eq!(idx(7, Black), 4);
+        assert_eq!(idx(11, White), 4);
+
+        // Between the numbers, and between the colors of the same number.
+        assert_eq!(idx(2, Black), 1);
+        assert_eq!(idx(3, Black), 1);
+        assert_eq!(idx(4, White), 2);
+
+        assert_eq!(field_insert_index(&[], &Card::new(CardNumber(5), Black)), 0);
+    }
+
+    #[test]
+    fn field_insert_index_places_duplicates_after_existing_ones() {
+        use CardColor::*;
+        // A deck with `copies_per_card: 2` has 2 of each card.
+        let field = cards(&[(2, Black), (5, Black), (5, Black), (5, White)]);
+
+        let idx = |number, color| field_insert_index(&field, &Card::new(CardNumber(number), color));
+
+        assert_eq!(idx(2, Black), 1);
+        assert_eq!(idx(5, Black), 3);
+        assert_eq!(idx(5, White), 4);
+
+        // Inserting every card in turn keeps the field sorted.
+        let mut field = Vec::new();
+        for card in cards(&[
+            (5, White),
+            (5, Black),
+            (0, Black),
+            (5, Black),
+            (9, White),
+            (0, Black),
+        ]) {
+            field.insert(field_insert_index(&field, &card), card);
+        }
+        assert_eq!(
+            field,
+            cards(&[
+                (0, Black),
+                (0, Black),
+                (5, Black),
+                (5, Black),
+                (5, White),
+                (9, White)
+            ])
+        );
+    }
+
+    #[test]
+    fn card_view_round_trips_through_string() {
+        for color in CardColor::ALL {
+            for number in CardNumber::all(0..=CardNumberType::MAX) {
+                let views = [
+                    CardView::from_props(color, None, false),
+                    CardView::from_props(color, Some(number), false),
+                    CardView::from_props(color, Some(number), true),
+                ];
+
+                for view in views {
+                    let s = view.to_string();
+                    assert_eq!(s.parse::<CardView>().unwrap(), view, "{}", s);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn card_view_rejects_out_of_range_numbers() {
+        for s in [
+            "Black--1",
+            "Black-(-1)",
+            "Black-256",
+            "White-(256)",
+            "Black-(300)",
+        ] {
+            let err = s.parse::<CardView>().unwrap_err();
+            assert!(
+                err.to_string().contains("card number must be 0..=255"),
+                "{}: {}",
+                s,
+                err
+            );
+        }
+
+        for s in [
+            "Black",
+            "Black-",
+            "Black-x",
+            "Black-(5",
+            "Blue-5",
+            "Black-5-6",
+        ] {
+            assert!(s.parse::<CardView>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn card_view_deserialization_rejects_revealed_card_without_number() {
+        let invalid = CardView::from_props(CardColor::White, None, true);
+        let bytes = bincode::serialize(&invalid).unwrap();
+        let err = bincode::deserialize::<CardView>(&bytes).unwrap_err();
+        assert!(err.to_string().contains("inconsistent CardView"), "{}", err);
+
+        for valid in [
+            CardView::from_props(CardColor::White, None, false),
+            CardView::from_props(CardColor::White, Some(CardNumber(3)), true),
+        ] {
+            let bytes = bincode::serialize(&valid).unwrap();
+            assert_eq!(bincode::deserialize::<CardView>(&bytes).unwrap(), valid);
+        }
+    }
+
+    /// A talon that draws black 0, 1 and 2 in order.
+    fn talon_012() -> Talon {
+        (0..3)
+            .rev()
+            .map(|n| Card::new(CardNumber(n), CardColor::Black))
+            .collect()
+    }
+
+    #[test]
+    fn peek_n_does_not_consume() {
+        let talon = talon_012();
+
+        let peeked = talon.peek_n(2);
+        assert_eq!(
+            peeked,
+            [0, 1].map(|n| Card::new(CardNumber(n), CardColor::Black).full_view())
+        );
+        assert_eq!(talon.len(), 3);
+
+        assert_eq!(talon.peek_n(0), []);
+        assert_eq!(talon.peek_n(10).len(), 3);
+    }
+
+    #[test]
+    fn return_card_restores_draw_order() {
+        let original = talon_012();
+
+        let mut talon = original.clone();
+        let card = talon.draw().unwrap();
+        talon.return_card(card, 0).unwrap();
+        assert_eq!(talon, original);
+
+        // Returning to the bottom.
+        let mut talon = original.clone();
+        let card = talon.draw().unwrap();
+        talon.return_card(card, 2).unwrap();
+        assert_eq!(talon.peek_n(3), {
+            let mut expected = original.peek_n(3);
+            expected.rotate_left(1);
+            expected
+        });
+    }
+
+    #[test]
+    fn return_card_rejects_index_out_of_range() {
+        let mut talon = talon_012();
+        let card = Card::new(CardNumber(3), CardColor::White);
+
+        assert!(talon.return_card(card, 4).is_err());
+        assert_eq!(talon, talon_012());
+
+        talon.return_card(card, 3).unwrap();
+        assert_eq!(talon.peek_n(4)[3], card.full_view());
+
+        let mut empty = Talon::from_iter([]);
+        assert!(empty.return_card(card, 1).is_err());
+        empty.return_card(card, 0).unwrap();
+        assert_eq!(empty.draw(), Some(card));
+    }
+
+    #[test]
+    fn draw_checked_fails_on_drained_talon() {
+        let mut talon = talon_012();
+        for n in 0..3 {
+            assert_eq!(
+                talon.draw_checked(),
+                Ok(Card::new(CardNumber(n), CardColor::Black))
+            );
+        }
+
+        assert_eq!(talon.draw_checked(), Err(TalonEmpty));
+        assert!(talon.is_empty());
     }
 }
@@ -1,6 +1,7 @@
 use crate::{
     card::{Card, CardNumber, CardView, TalonView},
     player::PlayerId,
+    settings::GameSettings,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -11,7 +12,10 @@ pub enum GameEvent {
     /// The board has been changed.
     BoardChanged(BoardChange),
     /// The game has started.
-    GameStarted(TalonView),
+    GameStarted {
+        talon: TalonView,
+        settings: Box<GameSettings>,
+    },
     /// The turn order has been determined.
     TurnOrderDetermined(Vec<PlayerId>),
     /// The card is distributed to the player.
@@ -38,8 +42,12 @@ pub enum GameEvent {
     AttackFailed,
     /// The player's field has no more face down cards.
     AttackedPlayerLost,
+    /// The player conceded the game.
+    PlayerSurrendered(PlayerId),
     /// The game is ended.
     GameEnded,
+    /// The game is ended in a draw, as no cards are left to draw.
+    GameDrawn,
     /// The turn player must select where to attack or stay.
     AttackOrStayDecisionRequired,
     /// The turn player has decided whether to attack or stay.
@@ -53,29 +61,39 @@ pub enum GameEvent {
 impl GameEvent {
     /// Returns `true` if the event represents a player's decision.
     pub fn is_decision(&self) -> bool {
-        matches!(
-            self,
-            Self::AttackTargetSelected { .. }
-                | Self::NumberGuessed(_)
-                | Self::AttackOrStayDecided { .. }
-        )
+        self.kind().is_decision()
     }
 
     /// Returns `true` if a turn player is required to respond with their decision.
     pub fn is_decision_required(&self) -> bool {
-        matches!(
-            self,
-            Self::AttackTargetSelectionRequired { .. }
-                | Self::NumberGuessRequired { .. }
-                | Self::AttackOrStayDecisionRequired { .. }
-        )
+        self.kind().is_decision_required()
+    }
+
+    /// Returns `true` if the game is decided or drawn by the event.
+    pub fn is_terminal(&self) -> bool {
+        self.kind().is_terminal()
     }
 
     /// Returns a new instance of `Self`
     /// with information hidden from the specified viewer removed.
-    pub(crate) fn view(&self, viewer: PlayerId) -> Self {
+    pub fn view(&self, viewer: PlayerId) -> Self {
         match self {
-            Self::BoardChanged(change) => Self::BoardChanged(change.view(viewer)),
+            Self::BoardChanged(change) => Self::BoardChanged(change.view(Some(viewer))),
+            other => other.clone(),
+        }
+    }
+
+    /// Returns a new instance of `Self`
+    /// with all private information removed, as seen by a spectator.
+    ///
+    /// The talon is shown without the drawing order.
+    pub fn public_view(&self) -> Self {
+        match self {
+            Self::BoardChanged(change) => Self::BoardChanged(change.view(None)),
+            Self::GameStarted { talon, settings } => Self::GameStarted {
+                talon: talon.shuffled_public(),
+                settings: settings.clone(),
+            },
             other => other.clone(),
         }
     }
@@ -83,7 +101,7 @@ impl GameEvent {
     pub fn kind(&self) -> GameEventKind {
         match self {
             Self::BoardChanged(_) => GameEventKind::BoardChanged,
-            Self::GameStarted(_) => GameEventKind::GameStarted,
+            Self::GameStarted { .. } => GameEventKind::GameStarted,
             Self::TurnOrderDetermined(_) => GameEventKind::TurnOrderDetermined,
             Self::CardDistributed(_) => GameEventKind::CardDistributed,
             Self::TurnStarted(_) => GameEventKind::TurnStarted,
@@ -98,7 +116,9 @@ impl GameEvent {
             Self::AttackSucceeded => GameEventKind::AttackSucceeded,
             Self::AttackFailed => GameEventKind::AttackFailed,
             Self::AttackedPlayerLost => GameEventKind::AttackedPlayerLost,
+            Self::PlayerSurrendered(_) => GameEventKind::PlayerSurrendered,
             Self::GameEnded => GameEventKind::GameEnded,
+            Self::GameDrawn => GameEventKind::GameDrawn,
             Self::AttackOrStayDecisionRequired => GameEventKind::AttackOrStayDecisionRequired,
             Self::AttackOrStayDecided { .. } => GameEventKind::AttackOrStayDecided,
             Self::TurnEnded => GameEventKind::TurnEnded,
@@ -123,15 +143,51 @@ pub enum GameEventKind {
     AttackSucceeded,
     AttackFailed,
     AttackedPlayerLost,
+    PlayerSurrendered,
     GameEnded,
+    GameDrawn,
     AttackOrStayDecisionRequired,
     AttackOrStayDecided,
     TurnEnded,
     RespOk,
 }
 
+impl GameEventKind {
+    /// Returns `true` if the event represents a player's decision.
+    pub fn is_decision(self) -> bool {
+        matches!(
+            self,
+            Self::AttackTargetSelected | Self::NumberGuessed | Self::AttackOrStayDecided
+        )
+    }
+
+    /// Returns `true` if a turn player is required to respond with their decision.
+    pub fn is_decision_required(self) -> bool {
+        matches!(
+            self,
+            Self::AttackTargetSelectionRequired
+                | Self::NumberGuessRequired
+                | Self::AttackOrStayDecisionRequired
+        )
+    }
+
+    /// Returns `true` if the game is decided or drawn by the event.
+    ///
+    /// No more decisions are required once such an event occurs.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            Self::NoCardsLeft
+                | Self::AttackedPlayerLost
+                | Self::PlayerSurrendered
+                | Self::GameEnded
+                | Self::GameDrawn
+        )
+    }
+}
+
 /// All possible board changes.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum BoardChange {
     /// A card is moved.
     CardMoved {
@@ -145,13 +201,25 @@ pub enum BoardChange {
         location: CardLocation,
         card: Card,
     },
+    /// The cards on a field are laid out in a new order.
+    ///
+    /// Follows every insertion into a field, so that the clients arrange the field
+    /// exactly as the server does, instead of deriving the order themselves.
+    FieldReordered {
+        player: PlayerId,
+        /// For each index from the left, the index the card had before the insertion.
+        ///
+        /// The inserted card is counted as if it had been appended to the end.
+        new_order: Vec<u32>,
+    },
 }
 
 impl BoardChange {
     /// Returns a new instance of `Self`
     /// with information hidden from the specified viewer removed.
-    #[allow(clippy::clone_on_copy)]
-    fn view(&self, viewer: PlayerId) -> Self {
+    ///
+    /// `None` represents a viewer who does not own any cards.
+    pub fn view(&self, viewer: Option<PlayerId>) -> Self {
         let mut ret = self.clone();
 
         match ret {
@@ -159,7 +227,7 @@ impl BoardChange {
                 player,
                 movement: _,
                 ref mut card,
-            } if player != viewer => {
+            } if viewer != Some(player) => {
                 *card = card.public_view();
             }
             _ => (),
@@ -167,6 +235,18 @@ impl BoardChange {
 
         ret
     }
+
+    /// Returns the `FieldReordered` change following the insertion of a card at `insert_at`
+    /// into a field that had `len_before` cards.
+    pub fn field_insertion(player: PlayerId, insert_at: u32, len_before: usize) -> Self {
+        let len_before = len_before as u32;
+        let new_order = (0..insert_at)
+            .chain([len_before])
+            .chain(insert_at..len_before)
+            .collect();
+
+        Self::FieldReordered { player, new_order }
+    }
 }
 
 /// Represents locations of cards.
@@ -213,7 +293,7 @@ pub enum GameEvResponseKind {
     Decision,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub(crate) struct EventQueue<T> {
     pub main_queue: VecDeque<T>,
     pub sub_queue: VecDeque<T>,
@@ -240,6 +320,24 @@ impl<T> EventQueue<T> {
         self.main_queue.pop_front()
     }
 
+    /// Returns the event that [`pop_next`] would return, without consuming it.
+    ///
+    /// [`pop_next`]: `EventQueue::pop_next`
+    pub fn peek_next(&self) -> Option<&T> {
+        self.sub_queue.front().or_else(|| self.main_queue.front())
+    }
+
+    /// Returns the number of events scheduled in both queues.
+    #[allow(unused)]
+    pub fn len(&self) -> usize {
+        self.main_queue.len() + self.sub_queue.len()
+    }
+
+    #[allow(unused)]
+    pub fn is_empty(&self) -> bool {
+        self.main_queue.is_empty() && self.sub_queue.is_empty()
+    }
+
     pub fn push_main(&mut self, event: T) {
         self.main_queue.push_back(event);
     }
@@ -247,4 +345,222 @@ impl<T> EventQueue<T> {
     pub fn push_sub(&mut self, event: T) {
         self.sub_queue.push_back(event);
     }
+
+    /// Discards the events scheduled in the main queue.
+    pub fn clear_main(&mut self) {
+        self.main_queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardColor;
+
+    #[test]
+    fn event_kinds_are_classified() {
+        use GameEventKind::*;
+
+        // Returns (is_decision, is_decision_required, is_terminal).
+        // No wildcard, so that a new kind cannot be added without classifying it here.
+        fn expected(kind: GameEventKind) -> (bool, bool, bool) {
+            match kind {
+                AttackTargetSelected | NumberGuessed | AttackOrStayDecided => (true, false, false),
+                AttackTargetSelectionRequired
+                | NumberGuessRequired
+                | AttackOrStayDecisionRequired => (false, true, false),
+                NoCardsLeft | AttackedPlayerLost | PlayerSurrendered | GameEnded | GameDrawn => {
+                    (false, false, true)
+                }
+                BoardChanged | GameStarted | TurnOrderDetermined | CardDistributed
+                | TurnStarted | TurnPlayerDrewCard | AttackSucceeded | AttackFailed | TurnEnded
+                | RespOk => (false, false, false),
+            }
+        }
+
+        let all = [
+            BoardChanged,
+            GameStarted,
+            TurnOrderDetermined,
+            CardDistributed,
+            TurnStarted,
+            TurnPlayerDrewCard,
+            NoCardsLeft,
+            AttackTargetSelectionRequired,
+            AttackTargetSelected,
+            NumberGuessRequired,
+            NumberGuessed,
+            AttackSucceeded,
+            AttackFailed,
+            AttackedPlayerLost,
+            PlayerSurrendered,
+            GameEnded,
+            GameDrawn,
+            AttackOrStayDecisionRequired,
+            AttackOrStayDecided,
+            TurnEnded,
+            RespOk,
+        ];
+
+        for kind in all {
+            assert_eq!(
+                (
+                    kind.is_decision(),
+                    kind.is_decision_required(),
+                    kind.is_terminal()
+                ),
+                expected(kind),
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    fn card_moved(owner: PlayerId, card: Card) -> GameEvent {
+        GameEvent::BoardChanged(BoardChange::CardMoved {
+            player: owner,
+            movement: CardMovement::TalonToField { insert_at: 0 },
+            card: card.full_view(),
+        })
+    }
+
+    #[test]
+    fn card_moved_is_masked_for_others() {
+        let (owner, opponent) = PlayerId::dummy_pair();
+        let card = Card::new(CardNumber(7), CardColor::White);
+        let event = card_moved(owner, card);
+
+        assert_eq!(event.view(owner), event);
+
+        let masked = card_moved(owner, card).public_view();
+        let GameEvent::BoardChanged(BoardChange::CardMoved {
+            card: masked_card, ..
+        }) = masked
+        else {
+            panic!("unexpected event: {:?}", masked);
+        };
+        assert_eq!(masked_card.pub_info.color, CardColor::White);
+        assert_eq!(masked_card.priv_info, None);
+
+        assert_eq!(event.view(opponent), masked);
+        assert_eq!(event.public_view(), masked);
+    }
+
+    #[test]
+    fn revealed_card_moved_is_not_masked() {
+        let (owner, opponent) = PlayerId::dummy_pair();
+        let mut card = Card::new(CardNumber(7), CardColor::White);
+        card.pub_info.revealed = true;
+        let event = card_moved(owner, card);
+
+        assert_eq!(event.view(opponent), event);
+        assert_eq!(event.public_view(), event);
+    }
+
+    #[test]
+    fn card_revealed_passes_through() {
+        let (owner, opponent) = PlayerId::dummy_pair();
+        let event = GameEvent::BoardChanged(BoardChange::CardRevealed {
+            player: owner,
+            location: CardLocation::Field { idx: 2 },
+            card: Card::new(CardNumber(3), CardColor::Black),
+        });
+
+        assert_eq!(event.view(owner), event);
+        assert_eq!(event.view(opponent), event);
+        assert_eq!(event.public_view(), event);
+    }
+
+    #[test]
+    fn field_reordered_passes_through() {
+        let (owner, opponent) = PlayerId::dummy_pair();
+        let change = BoardChange::field_insertion(owner, 1, 3);
+        assert_eq!(
+            change,
+            BoardChange::FieldReordered {
+                player: owner,
+                new_order: vec![0, 3, 1, 2],
+            }
+        );
+
+        // Everyone sees the order of the cards, but not their numbers.
+        assert_eq!(change.view(Some(owner)), change);
+        assert_eq!(change.view(Some(opponent)), change);
+        assert_eq!(change.view(None), change);
+
+        let event = GameEvent::BoardChanged(change);
+        assert_eq!(event.view(opponent), event);
+        assert_eq!(event.public_view(), event);
+    }
+
+    #[test]
+    fn public_view_hides_talon_order() {
+        use CardColor::*;
+        let settings = Box::new(GameSettings::default());
+        let event = GameEvent::GameStarted {
+            talon: TalonView {
+                cards: vec![White, Black, White, Black, Black],
+                ordered: true,
+            },
+            settings: settings.clone(),
+        };
+
+        // The players see the same talon as the server.
+        assert_eq!(event.view(PlayerId::dummy_pair().0), event);
+
+        assert_eq!(
+            event.public_view(),
+            GameEvent::GameStarted {
+                talon: TalonView {
+                    cards: vec![Black, Black, Black, White, White],
+                    ordered: false,
+                },
+                settings,
+            }
+        );
+    }
+
+    #[test]
+    fn peek_next_respects_sub_queue_priority() {
+        let mut queue = EventQueue::default();
+        queue.push_main(1);
+        queue.push_main(2);
+        queue.push_sub(10);
+
+        assert_eq!(queue.peek_next(), Some(&10));
+        assert_eq!(queue.pop_next(), Some(10));
+
+        assert_eq!(queue.peek_next(), Some(&1));
+        assert_eq!(queue.pop_next(), Some(1));
+        assert_eq!(queue.pop_next(), Some(2));
+
+        assert_eq!(queue.peek_next(), None);
+        assert_eq!(queue.pop_next(), None);
+    }
+
+    #[test]
+    fn len_counts_both_queues() {
+        let mut queue = EventQueue::default();
+        assert_eq!(queue.len(), 0);
+        assert!(queue.is_empty());
+
+        queue.push_sub(10);
+        assert_eq!(queue.len(), 1);
+        assert!(!queue.is_empty(), "an event in the sub queue is counted");
+
+        queue.pop_next();
+        queue.push_main(1);
+        assert_eq!(queue.len(), 1);
+        assert!(!queue.is_empty(), "an event in the main queue is counted");
+
+        queue.push_main(2);
+        queue.push_sub(11);
+        assert_eq!(queue.len(), 3);
+
+        queue.pop_next();
+        queue.pop_next();
+        queue.pop_next();
+        assert_eq!(queue.len(), 0);
+        assert!(queue.is_empty());
+    }
 }
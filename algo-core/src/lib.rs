@@ -1,22 +1,29 @@
-use anyhow::{bail, Context as _};
-use rand::seq::SliceRandom as _;
+use anyhow::ensure;
+use rand::{rngs::StdRng, seq::SliceRandom as _, SeedableRng as _};
+use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt};
 use tracing::debug;
 
 // TODO: fix visibility
 
 pub mod settings;
-use settings::GameSettings;
+use settings::{GameSettings, SettingsError};
 
 pub mod card;
-use card::{CardNumber, Talon};
+use card::{field_attack_targets, CardNumber, Talon, TalonEmpty};
 
 pub mod player;
-use player::{Player, PlayerId, TurnPlayer};
+use player::{Player, PlayerId, PlayerView, TurnPlayer};
 
 pub mod event;
 use event::{BoardChange, CardLocation, CardMovement, EventQueue, GameEvent, GameEventKind};
 
+pub mod replay;
+
+pub mod sim;
+
+pub mod snapshot;
+
 type ProcessEventResult = Result<(), ProcessEventError>;
 
 /// A structure controlling the game sequences.
@@ -24,6 +31,7 @@ type ProcessEventResult = Result<(), ProcessEventError>;
 pub struct Game {
     // static
     settings: GameSettings,
+    seed: u64,
 
     // board state
     board: Board,
@@ -46,23 +54,39 @@ impl Game {
     /// - the provided 2 PlayerIds are the same
     /// - the settings are invalid
     ///
-    /// This function decides the turn order and shuffles the deck randomly
-    /// using `ThreadRng`.
+    /// This function decides the turn order and shuffles the deck randomly.
+    /// See [`for_2_players_seeded`] for reproducible games.
+    ///
+    /// [`for_2_players_seeded`]: `Game::for_2_players_seeded`
     pub fn for_2_players(
         player_ids: (PlayerId, PlayerId),
         settings: GameSettings,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self, GameCreateError> {
+        Self::for_2_players_seeded(player_ids, settings, rand::random())
+    }
+
+    /// Creates a game with 2 players, using the seed to decide the turn order
+    /// and shuffle the deck.
+    ///
+    /// The same seed and settings always produce the same initial state.
+    ///
+    /// Returns `Err` under the same conditions as [`for_2_players`].
+    ///
+    /// [`for_2_players`]: `Game::for_2_players`
+    pub fn for_2_players_seeded(
+        player_ids: (PlayerId, PlayerId),
+        settings: GameSettings,
+        seed: u64,
+    ) -> Result<Self, GameCreateError> {
         if player_ids.0 == player_ids.1 {
-            bail!("duplicated PlayerId: {:?}", player_ids.0);
+            return Err(GameCreateError::DuplicatePlayerId(player_ids.0));
         }
 
-        let mut rng = rand::rng();
+        settings.validate()?;
 
-        let mut talon: Talon = settings.clone().build_cards()?.into_iter().collect();
-        if talon.len() <= settings.initial_draw_num as usize * 2 {
-            bail!("invalid game settings: not enough cards to start the game");
-        }
-        talon.shuffle(&mut rng);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let talon = Talon::from_settings_and_rng(settings.clone(), &mut rng)?;
 
         let players = BTreeMap::from([
             (player_ids.0, Player::default()),
@@ -76,7 +100,11 @@ impl Game {
         };
 
         let event_queue = EventQueue {
-            main_queue: [GameEvent::GameStarted(talon.view())].into(),
+            main_queue: [GameEvent::GameStarted {
+                talon: talon.view(),
+                settings: settings.clone().into(),
+            }]
+            .into(),
             ..Default::default()
         };
 
@@ -84,6 +112,7 @@ impl Game {
 
         let ret = Self {
             settings,
+            seed,
             board: Board::new(talon, players),
             turn_player: turn_order.clone(),
             attack: AttackContext::default(),
@@ -123,6 +152,7 @@ impl Game {
     ///
     /// Returns `Err` if:
     /// - The specified PlayerId is invalid
+    /// - The player has already responded to the staged event
     /// - `process_event` returns an error
     ///
     /// [`process_event`]: `Game::process_event`
@@ -130,17 +160,150 @@ impl Game {
         &mut self,
         player: PlayerId,
         response: GameEvent,
-    ) -> anyhow::Result<bool> {
+    ) -> Result<bool, StoreResponseError> {
         let storage = self
             .event_responses
             .get_mut(&player)
-            .context("unknown PlayerId")?;
+            .ok_or(StoreResponseError::UnknownPlayer(player))?;
+
+        if storage.is_some() {
+            return Err(StoreResponseError::AlreadyResponded(player));
+        }
 
         *storage = Some(response);
 
         Ok(self.has_all_players_responded())
     }
 
+    /// Makes the player lose the game immediately.
+    ///
+    /// The staged event is discarded along with the scheduled ones,
+    /// and [`GameEvent::PlayerSurrendered`] is scheduled instead.
+    ///
+    /// Returns `Err` if:
+    /// - The specified PlayerId is invalid
+    /// - The game has already ended
+    pub fn surrender(&mut self, player: PlayerId) -> anyhow::Result<()> {
+        ensure!(
+            self.event_responses.contains_key(&player),
+            "unknown PlayerId"
+        );
+        ensure!(
+            self.phase() != GamePhase::Ended,
+            "the game has already ended"
+        );
+
+        self.staged_event = None;
+        self.event_responses.values_mut().for_each(|v| *v = None);
+        self.event_queue = EventQueue::default();
+        self.event_queue
+            .push_main(GameEvent::PlayerSurrendered(player));
+
+        Ok(())
+    }
+
+    /// Returns the board information that the specified player is allowed to see.
+    pub fn board_view(&self, viewer: PlayerId) -> BoardView {
+        self.board.view(viewer)
+    }
+
+    /// Returns the settings the game was created with.
+    pub fn settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    /// Returns the seed the game was created with.
+    ///
+    /// The seed reveals the whole deal, so it must not be shown to the players during the game.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the staged event as seen by a spectator.
+    pub fn spectator_event(&self) -> Option<GameEvent> {
+        self.staged_event.as_ref().map(GameEvent::public_view)
+    }
+
+    /// Returns all events that have been started so far, as seen by a spectator.
+    ///
+    /// This is used to catch up a spectator who joined in the middle of the game.
+    pub fn spectator_history(&self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.history
+            .iter()
+            .chain(&self.staged_event)
+            .map(GameEvent::public_view)
+    }
+
+    /// Returns the player whose turn it is.
+    ///
+    /// Before the first turn, this is the player who takes the first turn.
+    pub fn current_turn_player(&self) -> PlayerId {
+        self.turn_player()
+    }
+
+    /// Returns the cards the player can attack,
+    /// as pairs of the owner and the index on the owner's field.
+    pub fn legal_attack_targets(&self, attacker: PlayerId) -> Vec<(PlayerId, u32)> {
+        self.board.attack_targets(attacker)
+    }
+
+    /// Returns what the game is waiting for,
+    /// judging from the staged event or the next one if none is staged.
+    pub fn phase(&self) -> GamePhase {
+        let event = self
+            .staged_event
+            .as_ref()
+            .or_else(|| self.event_queue.peek_next());
+
+        match event {
+            Some(GameEvent::AttackTargetSelectionRequired { .. }) => {
+                GamePhase::AwaitingAttackTarget
+            }
+            Some(GameEvent::NumberGuessRequired) => GamePhase::AwaitingGuess,
+            Some(GameEvent::AttackOrStayDecisionRequired) => GamePhase::AwaitingAttackOrStay,
+            Some(GameEvent::PlayerSurrendered(_) | GameEvent::GameEnded | GameEvent::GameDrawn)
+            | None => GamePhase::Ended,
+            Some(_) => GamePhase::Idle,
+        }
+    }
+
+    /// Returns `true` once the game has been decided or drawn,
+    /// even if the events announcing it have not been processed yet.
+    pub fn is_over(&self) -> bool {
+        self.phase() == GamePhase::Ended || self.loser().is_some()
+    }
+
+    /// Returns the player who won the game.
+    ///
+    /// Returns `None` while the game goes on, or if it was drawn.
+    pub fn winner(&self) -> Option<PlayerId> {
+        let loser = self.loser()?;
+        self.event_responses.keys().copied().find(|id| *id != loser)
+    }
+
+    /// Returns the player who surrendered or had all their cards revealed.
+    fn loser(&self) -> Option<PlayerId> {
+        let surrendered = self
+            .history
+            .iter()
+            .chain(&self.staged_event)
+            .chain(self.event_queue.peek_next())
+            .find_map(|ev| match ev {
+                GameEvent::PlayerSurrendered(player) => Some(*player),
+                _ => None,
+            });
+
+        surrendered.or_else(|| self.board.fully_revealed_player())
+    }
+
+    /// Returns the players who have not responded to the staged event yet.
+    pub fn unresponsive_players(&self) -> impl Iterator<Item = PlayerId> + '_ {
+        self.event_responses
+            .iter()
+            .filter(|(_, resp)| resp.is_none())
+            .map(|(id, _)| *id)
+    }
+
     pub fn process_event(&mut self) -> ProcessEventResult {
         if !self.has_all_players_responded() {
             return Err(ProcessEventError::NotReady);
@@ -159,14 +322,22 @@ impl Game {
         let event = self.staged_event.take().unwrap();
         match event {
             GameEvent::BoardChanged(_) => (),
-            GameEvent::GameStarted(_) => {
+            GameEvent::GameStarted { .. } => {
                 self.start_game();
             }
             GameEvent::TurnOrderDetermined(_) => (),
-            GameEvent::CardDistributed(pid) => {
-                let change = self.board.draw_direct(pid);
-                self.event_queue.push_sub(GameEvent::BoardChanged(change));
-            }
+            GameEvent::CardDistributed(pid) => match self.board.draw_direct(pid) {
+                Ok(changes) => {
+                    for change in changes {
+                        self.event_queue.push_sub(GameEvent::BoardChanged(change));
+                    }
+                }
+                Err(TalonEmpty) => {
+                    // The remaining distribution and the first turn can no longer happen.
+                    self.event_queue.clear_main();
+                    self.event_queue.push_main(GameEvent::NoCardsLeft);
+                }
+            },
             GameEvent::TurnStarted(_) => {
                 self.start_turn();
                 self.event_queue.push_main(GameEvent::TurnPlayerDrewCard);
@@ -175,7 +346,7 @@ impl Game {
                 self.resolve_turn_player_draw();
             }
             GameEvent::NoCardsLeft => {
-                self.event_queue.push_main(GameEvent::GameEnded);
+                self.event_queue.push_main(GameEvent::GameDrawn);
             }
             GameEvent::AttackTargetSelectionRequired { .. } => {
                 self.resolve_resp_attack_target_selection()?;
@@ -187,20 +358,20 @@ impl Game {
                 self.resolve_resp_number_guess()?;
             }
             GameEvent::NumberGuessed(_) => {
-                self.resolve_attack();
+                self.resolve_attack()?;
             }
             GameEvent::AttackSucceeded => {
-                self.resolve_succeeded_attack();
+                self.resolve_succeeded_attack()?;
             }
             GameEvent::AttackFailed => {
                 self.resolve_failed_attack();
             }
-            GameEvent::AttackedPlayerLost => {
+            GameEvent::AttackedPlayerLost | GameEvent::PlayerSurrendered(_) => {
                 // Currently, there are only 2 players
                 // If one loses, the game should end immediately.
                 self.event_queue.push_main(GameEvent::GameEnded);
             }
-            GameEvent::GameEnded => (),
+            GameEvent::GameEnded | GameEvent::GameDrawn => (),
             GameEvent::AttackOrStayDecisionRequired => {
                 self.resolve_resp_attack_or_stay_decision()?;
             }
@@ -236,6 +407,7 @@ impl Game {
                         },
                         player: *pid,
                         response: unexpected,
+                        processing: event.kind(),
                     }
                     .into());
                 }
@@ -309,7 +481,7 @@ impl Game {
         let draw_res = self.board.draw(self.turn_player());
 
         match draw_res {
-            Some(change) => {
+            Ok(change) => {
                 self.event_queue.push_sub(GameEvent::BoardChanged(change));
 
                 self.event_queue
@@ -317,24 +489,32 @@ impl Game {
                         target_player: self.attack_target_player(),
                     });
             }
-            None => self.event_queue.push_main(GameEvent::NoCardsLeft),
+            Err(TalonEmpty) => self.event_queue.push_main(GameEvent::NoCardsLeft),
         }
     }
 
     fn resolve_resp_attack_target_selection(&mut self) -> ProcessEventResult {
         let resp = self.take_turn_player_resp();
         let GameEvent::AttackTargetSelected { target_idx } = resp else {
-            return Err(self.invalid_resp_kind(GameEventKind::AttackTargetSelected, resp));
+            return Err(self.invalid_resp_kind(
+                GameEventKind::AttackTargetSelectionRequired,
+                GameEventKind::AttackTargetSelected,
+                resp,
+            ));
         };
 
         if !self
             .board
             .verify_attack_target(self.attack_target_player(), target_idx)
         {
-            return Err(self.resp_err(ResponseErrorKind::InvalidAttackTarget, resp));
+            return Err(self.resp_err(
+                GameEventKind::AttackTargetSelectionRequired,
+                ResponseErrorKind::InvalidAttackTarget,
+                resp,
+            ));
         }
 
-        self.attack.target_card_idx = Some(target_idx);
+        self.attack.set_target(target_idx)?;
         self.event_queue.push_main(resp);
 
         Ok(())
@@ -343,38 +523,51 @@ impl Game {
     fn resolve_resp_number_guess(&mut self) -> ProcessEventResult {
         let resp = self.take_turn_player_resp();
         let GameEvent::NumberGuessed(num) = resp else {
-            return Err(self.invalid_resp_kind(GameEventKind::NumberGuessed, resp));
+            return Err(self.invalid_resp_kind(
+                GameEventKind::NumberGuessRequired,
+                GameEventKind::NumberGuessed,
+                resp,
+            ));
         };
 
-        if !(0..=11).contains(&num.0) {
-            return Err(self.resp_err(ResponseErrorKind::NumberOutOfRange, resp));
+        if !self.settings.card_numbers().contains(&num.0) {
+            return Err(self.resp_err(
+                GameEventKind::NumberGuessRequired,
+                ResponseErrorKind::NumberOutOfRange,
+                resp,
+            ));
         }
 
-        self.attack.guess = Some(num);
+        self.attack.set_guess(num)?;
         self.event_queue.push_main(resp);
 
         Ok(())
     }
 
-    fn resolve_attack(&mut self) {
-        let attacked = self.attack_target_player();
-        let target_idx = self.attack.target_card_idx.unwrap();
-        let guess = self.attack.guess.unwrap();
+    fn resolve_attack(&mut self) -> ProcessEventResult {
+        let (attacked, target_idx, guess) = self.attack.attack()?;
 
-        let res = self.board.resolve_attack(attacked, target_idx, guess);
+        let res = self
+            .board
+            .resolve_attack(attacked, target_idx, guess)
+            .map_err(|kind| self.invalid_attack_target(kind, target_idx))?;
 
         self.event_queue.push_main(if res {
             GameEvent::AttackSucceeded
         } else {
             GameEvent::AttackFailed
         });
+
+        Ok(())
     }
 
-    fn resolve_succeeded_attack(&mut self) {
-        let attacked = self.attack_target_player();
-        let target_idx = self.attack.target_card_idx.unwrap();
+    fn resolve_succeeded_attack(&mut self) -> ProcessEventResult {
+        let (attacked, target_idx, _) = self.attack.attack()?;
 
-        let change = self.board.resolve_succeeded_attack(attacked, target_idx);
+        let change = self
+            .board
+            .resolve_succeeded_attack(attacked, target_idx)
+            .map_err(|kind| self.invalid_attack_target(kind, target_idx))?;
         self.event_queue.push_sub(GameEvent::BoardChanged(change));
 
         let res = self.board.has_player_lost_game(attacked);
@@ -384,9 +577,9 @@ impl Game {
             GameEvent::AttackOrStayDecisionRequired
         });
 
-        // Cleanup
-        self.attack.target_card_idx.take();
-        self.attack.guess.take();
+        self.attack.finish_attack();
+
+        Ok(())
     }
 
     fn resolve_failed_attack(&mut self) {
@@ -402,7 +595,11 @@ impl Game {
     fn resolve_resp_attack_or_stay_decision(&mut self) -> ProcessEventResult {
         let resp = self.take_turn_player_resp();
         let GameEvent::AttackOrStayDecided { .. } = resp else {
-            return Err(self.invalid_resp_kind(GameEventKind::AttackOrStayDecided, resp));
+            return Err(self.invalid_resp_kind(
+                GameEventKind::AttackOrStayDecisionRequired,
+                GameEventKind::AttackOrStayDecided,
+                resp,
+            ));
         };
 
         self.event_queue.push_main(resp);
@@ -411,9 +608,9 @@ impl Game {
     }
 
     fn resolve_stay(&mut self) {
-        let change = self.board.resolve_stay(self.turn_player());
-
-        self.event_queue.push_sub(GameEvent::BoardChanged(change));
+        for change in self.board.resolve_stay(self.turn_player()) {
+            self.event_queue.push_sub(GameEvent::BoardChanged(change));
+        }
         self.event_queue.push_main(GameEvent::TurnEnded);
     }
 
@@ -423,23 +620,77 @@ impl Game {
         self.attack.cleanup();
     }
 
-    fn resp_err(&self, kind: ResponseErrorKind, resp: GameEvent) -> ProcessEventError {
+    fn resp_err(
+        &self,
+        processing: GameEventKind,
+        kind: ResponseErrorKind,
+        resp: GameEvent,
+    ) -> ProcessEventError {
         ResponseError {
             kind,
             player: self.turn_player(),
             response: resp,
+            processing,
         }
         .into()
     }
 
-    fn invalid_resp_kind(&self, kind: GameEventKind, resp: GameEvent) -> ProcessEventError {
+    /// Blames the attack target that the turn player selected.
+    fn invalid_attack_target(&self, kind: ResponseErrorKind, target_idx: u32) -> ProcessEventError {
+        self.resp_err(
+            GameEventKind::AttackTargetSelectionRequired,
+            kind,
+            GameEvent::AttackTargetSelected { target_idx },
+        )
+    }
+
+    fn invalid_resp_kind(
+        &self,
+        processing: GameEventKind,
+        kind: GameEventKind,
+        resp: GameEvent,
+    ) -> ProcessEventError {
         self.resp_err(
+            processing,
             ResponseErrorKind::InvalidGameEventKind { expected: kind },
             resp,
         )
     }
 }
 
+/// The stage of the game, returned by [`Game::phase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum GamePhase {
+    /// The turn player is choosing a card to attack.
+    AwaitingAttackTarget,
+    /// The turn player is guessing the number of the attacked card.
+    AwaitingGuess,
+    /// The turn player is deciding whether to attack again or stay.
+    AwaitingAttackOrStay,
+    /// No decision is required.
+    Idle,
+    /// The game has ended, with a winner or in a draw.
+    Ended,
+}
+
+/// An error indicating that a game cannot be created.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GameCreateError {
+    #[error("duplicated PlayerId: {0:?}")]
+    DuplicatePlayerId(PlayerId),
+    #[error(transparent)]
+    InvalidSettings(#[from] SettingsError),
+}
+
+/// An error indicating that a player's response cannot be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum StoreResponseError {
+    #[error("unknown PlayerId: {0:?}")]
+    UnknownPlayer(PlayerId),
+    #[error("the player has already responded: {0:?}")]
+    AlreadyResponded(PlayerId),
+}
+
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum NextEventError {
     #[error("the event is processing")]
@@ -456,6 +707,20 @@ pub enum ProcessEventError {
     Failed,
     #[error(transparent)]
     ResponseError(#[from] ResponseError),
+    #[error(transparent)]
+    AttackContext(#[from] AttackContextError),
+}
+
+/// A violation of the order of an attack:
+/// a target is selected, a number is guessed, and then the attack is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AttackContextError {
+    #[error("no player is under attack")]
+    NoTargetPlayer,
+    #[error("no attack target is selected")]
+    NoTargetCard,
+    #[error("no number is guessed")]
+    NoGuess,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -463,11 +728,20 @@ pub struct ResponseError {
     pub kind: ResponseErrorKind,
     pub player: PlayerId,
     pub response: GameEvent,
+    /// The kind of the event the response was sent to.
+    pub processing: GameEventKind,
 }
 
 impl fmt::Display for ResponseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(
+            f,
+            "invalid response from {:?} to {:?}: {:?} (got {:?})",
+            self.player,
+            self.processing,
+            self.kind,
+            self.response.kind()
+        )
     }
 }
 
@@ -481,7 +755,7 @@ pub enum ResponseErrorKind {
 }
 
 /// Board information
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 struct Board {
     talon: Talon,
     players: BTreeMap<PlayerId, Player>,
@@ -492,28 +766,42 @@ impl Board {
         Self { talon, players }
     }
 
-    fn draw_direct(&mut self, player: PlayerId) -> BoardChange {
-        let card = self.talon.draw().expect("talon should have some cards");
+    fn view(&self, viewer: PlayerId) -> BoardView {
+        BoardView {
+            talon_len: self.talon.len(),
+            players: self
+                .players
+                .iter()
+                .map(|(id, player)| (*id, player.view(*id == viewer)))
+                .collect(),
+        }
+    }
 
-        let idx = self
-            .players
-            .get_mut(&player)
-            .unwrap()
-            .insert_card_to_field(card);
+    /// Moves a card from the talon to the player's field.
+    fn draw_direct(&mut self, player: PlayerId) -> Result<[BoardChange; 2], TalonEmpty> {
+        let card = self.talon.draw_checked()?;
 
-        BoardChange::CardMoved {
-            player,
-            movement: CardMovement::TalonToField { insert_at: idx },
-            card: card.full_view(),
-        }
+        let field_owner = self.players.get_mut(&player).unwrap();
+        let len_before = field_owner.field.len();
+        let idx = field_owner.insert_card_to_field(card);
+
+        Ok([
+            BoardChange::CardMoved {
+                player,
+                movement: CardMovement::TalonToField { insert_at: idx },
+                card: card.full_view(),
+            },
+            BoardChange::field_insertion(player, idx, len_before),
+        ])
     }
 
-    fn draw(&mut self, player: PlayerId) -> Option<BoardChange> {
-        let card = self.talon.draw()?;
+    /// Moves a card from the talon to the player's attacker.
+    fn draw(&mut self, player: PlayerId) -> Result<BoardChange, TalonEmpty> {
+        let card = self.talon.draw_checked()?;
 
         self.players.get_mut(&player).unwrap().insert_attacker(card);
 
-        Some(BoardChange::CardMoved {
+        Ok(BoardChange::CardMoved {
             player,
             movement: CardMovement::TalonToAttacker,
             card: card.full_view(),
@@ -524,55 +812,80 @@ impl Board {
         let Some(card) = self
             .players
             .get(&target_player)
-            .unwrap()
-            .field
-            .get(target_idx as usize)
+            .and_then(|v| v.field_card(target_idx))
         else {
             return false;
         };
 
-        !card.pub_info.revealed
+        card.pub_info.is_attackable()
+    }
+
+    fn attack_targets(&self, attacker: PlayerId) -> Vec<(PlayerId, u32)> {
+        self.players
+            .iter()
+            .filter(|(id, _)| **id != attacker)
+            .flat_map(|(id, player)| {
+                field_attack_targets(player.field.iter().map(|card| card.pub_info))
+                    .map(move |idx| (*id, idx))
+            })
+            .collect()
     }
 
     /// Returns `true` if guess is correct.
-    fn resolve_attack(&mut self, attacked: PlayerId, target_idx: u32, guess: CardNumber) -> bool {
-        let attacked_card = self.players.get(&attacked).unwrap().field[target_idx as usize];
-        guess == attacked_card.priv_info.number
+    fn resolve_attack(
+        &mut self,
+        attacked: PlayerId,
+        target_idx: u32,
+        guess: CardNumber,
+    ) -> Result<bool, ResponseErrorKind> {
+        let attacked_card = self
+            .players
+            .get(&attacked)
+            .and_then(|v| v.field_card(target_idx))
+            .ok_or(ResponseErrorKind::InvalidAttackTarget)?;
+
+        Ok(guess == attacked_card.priv_info.number)
     }
 
-    fn resolve_succeeded_attack(&mut self, attacked: PlayerId, target_idx: u32) -> BoardChange {
+    fn resolve_succeeded_attack(
+        &mut self,
+        attacked: PlayerId,
+        target_idx: u32,
+    ) -> Result<BoardChange, ResponseErrorKind> {
         let attacked_card = self
             .players
             .get_mut(&attacked)
-            .unwrap()
-            .field
-            .get_mut(target_idx as usize)
-            .unwrap();
+            .and_then(|v| v.field_card_mut(target_idx))
+            .ok_or(ResponseErrorKind::InvalidAttackTarget)?;
 
         attacked_card.pub_info.revealed = true;
 
-        BoardChange::CardRevealed {
+        Ok(BoardChange::CardRevealed {
             player: attacked,
             location: CardLocation::Field { idx: target_idx },
             card: *attacked_card,
-        }
+        })
     }
 
-    fn has_player_lost_game(&mut self, player: PlayerId) -> bool {
+    /// Returns the player whose field has cards, all of which are revealed.
+    fn fully_revealed_player(&self) -> Option<PlayerId> {
         self.players
-            .get(&player)
-            .unwrap()
-            .field
             .iter()
-            .all(|v| v.pub_info.revealed)
+            .find(|(_, player)| player.has_lost())
+            .map(|(id, _)| *id)
+    }
+
+    fn has_player_lost_game(&self, player: PlayerId) -> bool {
+        self.players.get(&player).unwrap().has_lost()
     }
 
-    fn resolve_failed_attack(&mut self, attacker: PlayerId) -> [BoardChange; 2] {
+    fn resolve_failed_attack(&mut self, attacker: PlayerId) -> [BoardChange; 3] {
         let player = self.players.get_mut(&attacker).unwrap();
 
         let mut attacker_card = player.attacker.take().unwrap();
         attacker_card.pub_info.revealed = true;
 
+        let len_before = player.field.len();
         let idx = player.insert_card_to_field(attacker_card);
 
         [
@@ -586,25 +899,38 @@ impl Board {
                 movement: CardMovement::AttackerToField { insert_at: idx },
                 card: attacker_card.full_view(),
             },
+            BoardChange::field_insertion(attacker, idx, len_before),
         ]
     }
 
-    fn resolve_stay(&mut self, attacker: PlayerId) -> BoardChange {
+    fn resolve_stay(&mut self, attacker: PlayerId) -> [BoardChange; 2] {
         let player = self.players.get_mut(&attacker).unwrap();
 
         let attacker_card = player.attacker.take().unwrap();
 
+        let len_before = player.field.len();
         let idx = player.insert_card_to_field(attacker_card);
 
-        BoardChange::CardMoved {
-            player: attacker,
-            movement: CardMovement::AttackerToField { insert_at: idx },
-            card: attacker_card.full_view(),
-        }
+        [
+            BoardChange::CardMoved {
+                player: attacker,
+                movement: CardMovement::AttackerToField { insert_at: idx },
+                card: attacker_card.full_view(),
+            },
+            BoardChange::field_insertion(attacker, idx, len_before),
+        ]
     }
 }
 
-#[derive(Debug, Clone, Default)]
+/// Board information visible to a specific player.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BoardView {
+    /// A number of cards left in the talon.
+    pub talon_len: usize,
+    pub players: BTreeMap<PlayerId, PlayerView>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
 struct AttackContext {
     target_player: Option<PlayerId>,
     target_card_idx: Option<u32>,
@@ -615,4 +941,180 @@ impl AttackContext {
     fn cleanup(&mut self) {
         *self = Self::default();
     }
+
+    /// Starts a new attack on the card, discarding the previous guess.
+    fn set_target(&mut self, target_card_idx: u32) -> Result<(), AttackContextError> {
+        if self.target_player.is_none() {
+            return Err(AttackContextError::NoTargetPlayer);
+        }
+
+        self.target_card_idx = Some(target_card_idx);
+        self.guess = None;
+        Ok(())
+    }
+
+    fn set_guess(&mut self, guess: CardNumber) -> Result<(), AttackContextError> {
+        if self.target_card_idx.is_none() {
+            return Err(AttackContextError::NoTargetCard);
+        }
+
+        self.guess = Some(guess);
+        Ok(())
+    }
+
+    /// Returns the attacked player, the index of the attacked card and the guess.
+    fn attack(&self) -> Result<(PlayerId, u32, CardNumber), AttackContextError> {
+        Ok((
+            self.target_player
+                .ok_or(AttackContextError::NoTargetPlayer)?,
+            self.target_card_idx
+                .ok_or(AttackContextError::NoTargetCard)?,
+            self.guess.ok_or(AttackContextError::NoGuess)?,
+        ))
+    }
+
+    /// Clears the finished attack, keeping the attacked player.
+    fn finish_attack(&mut self) {
+        self.target_card_idx = None;
+        self.guess = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{respond, RandomPolicy};
+
+    #[test]
+    fn board_view_masks_opponent_face_down_cards() {
+        let settings = GameSettings::default();
+        let (me, opponent) = PlayerId::dummy_pair();
+        let mut policy = RandomPolicy::new(&settings, 0);
+        let mut game = Game::for_2_players_seeded((me, opponent), settings, 0).unwrap();
+
+        // Play until the first attack, when both fields are dealt and nothing is revealed yet.
+        loop {
+            let events = game.next_event().unwrap().collect::<Vec<_>>();
+            if matches!(events[0].1, GameEvent::AttackTargetSelectionRequired { .. }) {
+                break;
+            }
+            for (player_id, event) in events {
+                let resp = respond(&game, player_id, &event, &mut policy).unwrap();
+                game.store_player_response(player_id, resp).unwrap();
+            }
+            game.process_event().unwrap();
+        }
+
+        let view = game.board_view(me);
+        assert_eq!(view.talon_len, game.board.talon.len());
+
+        let own = &view.players[&me];
+        assert!(!own.field.is_empty());
+        assert!(own.field.iter().all(|card| card.priv_info.is_some()));
+
+        let theirs = &view.players[&opponent];
+        assert_eq!(
+            theirs.field.len(),
+            game.board.players[&opponent].field.len()
+        );
+        assert!(theirs
+            .field
+            .iter()
+            .all(|card| !card.pub_info.revealed && card.priv_info.is_none()));
+
+        let (attacking, watching) = if game.turn_player() == me {
+            (own, theirs)
+        } else {
+            (theirs, own)
+        };
+        assert!(watching.attacker.is_none());
+        assert_eq!(
+            attacking.attacker.unwrap().priv_info.is_some(),
+            game.turn_player() == me
+        );
+    }
+
+    #[test]
+    fn talon_running_out_while_dealing_draws_the_game() {
+        let settings = GameSettings::default();
+        let mut policy = RandomPolicy::new(&settings, 0);
+        let mut game = Game::for_2_players_seeded(PlayerId::dummy_pair(), settings, 0).unwrap();
+        let mut drained = false;
+
+        loop {
+            let events = match game.next_event() {
+                Ok(v) => v.collect::<Vec<_>>(),
+                Err(NextEventError::NoMoreEvent) => break,
+                Err(e) => panic!("{}", e),
+            };
+
+            // Take away the cards to deal, as if the deck had been too small.
+            if !drained && matches!(events[0].1, GameEvent::CardDistributed(_)) {
+                while game.board.talon.draw().is_some() {}
+                drained = true;
+            }
+
+            for (player_id, event) in events {
+                let resp = respond(&game, player_id, &event, &mut policy).unwrap();
+                game.store_player_response(player_id, resp).unwrap();
+            }
+            game.process_event().unwrap();
+        }
+
+        assert!(drained);
+        assert!(game
+            .history
+            .ends_with(&[GameEvent::NoCardsLeft, GameEvent::GameDrawn]));
+        assert!(!game
+            .history
+            .iter()
+            .any(|event| matches!(event, GameEvent::TurnStarted(_))));
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn legal_attack_targets_agree_with_verification() {
+        let settings = GameSettings::default();
+        let mut policy = RandomPolicy::new(&settings, 0);
+        let mut game = Game::for_2_players_seeded(PlayerId::dummy_pair(), settings, 0).unwrap();
+        let mut checked = 0;
+
+        loop {
+            let events = match game.next_event() {
+                Ok(v) => v.collect::<Vec<_>>(),
+                Err(NextEventError::NoMoreEvent) => break,
+                Err(e) => panic!("{}", e),
+            };
+
+            if let Some(target_player) = events.iter().find_map(|(_, event)| match event {
+                GameEvent::AttackTargetSelectionRequired { target_player } => Some(*target_player),
+                _ => None,
+            }) {
+                let targets = game.legal_attack_targets(game.turn_player());
+                assert!(!targets.is_empty());
+                assert!(targets.iter().all(|(owner, _)| *owner == target_player));
+
+                // One past the end, which is never a target.
+                let field_len = game.board.players[&target_player].field.len() as u32;
+                for idx in 0..=field_len {
+                    assert_eq!(
+                        game.board.verify_attack_target(target_player, idx),
+                        targets.contains(&(target_player, idx)),
+                        "card #{} of {:?}",
+                        idx,
+                        target_player
+                    );
+                }
+                checked += 1;
+            }
+
+            for (player_id, event) in events {
+                let resp = respond(&game, player_id, &event, &mut policy).unwrap();
+                game.store_player_response(player_id, resp).unwrap();
+            }
+            game.process_event().unwrap();
+        }
+
+        assert!(checked > 1);
+    }
 }
@@ -1,4 +1,7 @@
-use crate::{card::Card, event::CardLocation};
+use crate::{
+    card::{field_insert_index, Card, CardView},
+    event::CardLocation,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
@@ -19,18 +22,24 @@ impl PlayerId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
 pub(crate) struct Player {
     pub field: Vec<Card>,
     pub attacker: Option<Card>,
 }
 
 impl Player {
-    pub fn insert_card_to_field(&mut self, card: Card) -> u32 {
-        let Err(idx) = self.field.binary_search(&card) else {
-            panic!("duplicated card detected: {:?}", card);
-        };
+    /// Returns the card at the index of the field, if any.
+    pub fn field_card(&self, idx: u32) -> Option<&Card> {
+        self.field.get(idx as usize)
+    }
+
+    pub fn field_card_mut(&mut self, idx: u32) -> Option<&mut Card> {
+        self.field.get_mut(idx as usize)
+    }
 
+    pub fn insert_card_to_field(&mut self, card: Card) -> u32 {
+        let idx = field_insert_index(&self.field, &card);
         self.field.insert(idx, card);
 
         idx as u32
@@ -45,10 +54,43 @@ impl Player {
 
         CardLocation::Attacker
     }
+
+    /// Returns `true` if the field has cards, all of which are revealed.
+    ///
+    /// An empty field does not count as lost, as every field is empty before the deal.
+    pub fn has_lost(&self) -> bool {
+        !self.field.is_empty() && self.field.iter().all(|v| v.pub_info.revealed)
+    }
+
+    /// Returns the cards of the player as seen by the viewer.
+    ///
+    /// The owner of the cards can see all of them,
+    /// while the others can only see the revealed ones.
+    pub fn view(&self, is_owner: bool) -> PlayerView {
+        let view = |card: &Card| {
+            if is_owner {
+                card.full_view()
+            } else {
+                card.public_view()
+            }
+        };
+
+        PlayerView {
+            field: self.field.iter().map(view).collect(),
+            attacker: self.attacker.as_ref().map(view),
+        }
+    }
+}
+
+/// Player's cards visible to a specific viewer.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PlayerView {
+    pub field: Vec<CardView>,
+    pub attacker: Option<CardView>,
 }
 
 /// Tracks the player whose turn is next.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub(crate) struct TurnPlayer {
     ids: VecDeque<PlayerId>,
 }
@@ -75,6 +117,11 @@ impl TurnPlayer {
     }
 }
 
+/// Hands out unique `PlayerId`s, starting from 1.
+///
+/// IDs are never reused, even after the player leaves,
+/// so a rejoining player cannot collide with a newcomer.
+#[derive(Debug)]
 pub struct AssignPlayerId(PlayerId);
 
 impl Default for AssignPlayerId {
@@ -84,8 +131,58 @@ impl Default for AssignPlayerId {
 }
 
 impl AssignPlayerId {
-    pub fn assign(&mut self) -> PlayerId {
-        self.0 .0 += 1;
-        self.0
+    /// Returns `Err` if all the IDs have been handed out, rather than wrapping around to a used one.
+    pub fn assign(&mut self) -> Result<PlayerId, PlayerIdsExhausted> {
+        self.0 .0 = self.0 .0.checked_add(1).ok_or(PlayerIdsExhausted)?;
+        Ok(self.0)
+    }
+}
+
+/// An error indicating that no more `PlayerId`s can be handed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("ran out of player IDs")]
+pub struct PlayerIdsExhausted;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{CardColor, CardNumber};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn has_lost_only_when_whole_field_is_revealed() {
+        let mut player = Player::default();
+        assert!(!player.has_lost(), "an empty field is not lost");
+
+        player.insert_card_to_field(Card::new(CardNumber(1), CardColor::Black));
+        player.insert_card_to_field(Card::new(CardNumber(2), CardColor::White));
+        assert!(!player.has_lost());
+
+        player.field[0].pub_info.revealed = true;
+        assert!(!player.has_lost(), "a hidden card is left");
+
+        player.field[1].pub_info.revealed = true;
+        assert!(player.has_lost());
+    }
+
+    #[test]
+    fn assigned_ids_are_unique_and_sequential() {
+        let mut assign = AssignPlayerId::default();
+
+        let ids = (0..10000)
+            .map(|_| assign.assign().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids.iter().collect::<BTreeSet<_>>().len(), ids.len());
+        assert!(ids.iter().zip(1..).all(|(id, n)| *id == PlayerId(n)));
+    }
+
+    #[test]
+    fn assign_fails_instead_of_wrapping() {
+        let mut assign = AssignPlayerId(PlayerId(u32::MAX - 1));
+
+        assert_eq!(assign.assign(), Ok(PlayerId(u32::MAX)));
+        assert_eq!(assign.assign(), Err(PlayerIdsExhausted));
+        assert_eq!(assign.assign(), Err(PlayerIdsExhausted));
     }
 }
@@ -0,0 +1,69 @@
+//! Recording games to watch them later.
+
+use crate::{
+    card::Talon,
+    event::GameEvent,
+    player::PlayerId,
+    settings::{GameSettings, SettingsError},
+    Game,
+};
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+
+/// A recorded game, as seen by a single viewer.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Replay {
+    pub settings: GameSettings,
+    /// The seed the game was created with.
+    pub seed: u64,
+    pub players: (PlayerId, PlayerId),
+    /// The player whose view the events are recorded from,
+    /// or `None` if recorded from a spectator's view.
+    pub viewer: Option<PlayerId>,
+    /// The events in the order they were sent.
+    pub events: Vec<GameEvent>,
+}
+
+impl Replay {
+    /// Rebuilds the talon as it was dealt at the start of the game.
+    pub fn talon(&self) -> Result<Talon, SettingsError> {
+        Talon::from_settings_and_seed(self.settings.clone(), self.seed)
+    }
+}
+
+impl Game {
+    /// Records the events processed so far, as seen by the viewer.
+    ///
+    /// Returns `Err` if the viewer is not a player of the game.
+    pub fn export_replay(&self, viewer: Option<PlayerId>) -> anyhow::Result<Replay> {
+        let players = {
+            let mut keys = self.event_responses.keys().cloned();
+            (keys.next().unwrap(), keys.next().unwrap())
+        };
+
+        if let Some(viewer) = viewer {
+            ensure!(
+                viewer == players.0 || viewer == players.1,
+                "unknown PlayerId: {:?}",
+                viewer
+            );
+        }
+
+        let events = self
+            .history
+            .iter()
+            .map(|event| match viewer {
+                Some(id) => event.view(id),
+                None => event.public_view(),
+            })
+            .collect();
+
+        Ok(Replay {
+            settings: self.settings.clone(),
+            seed: self.seed,
+            players,
+            viewer,
+            events,
+        })
+    }
+}
@@ -1,47 +1,158 @@
-use crate::card::{create_cards, Card, CardColor, CardNumberType};
-use anyhow::bail;
+use crate::card::{create_cards, Card, CardColor, CardNumber, CardNumberType, CardRgb};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
 
 const MAX_CARD_NUM_DEFAULT: CardNumberType = 11;
 const COLOR_VARIANTS_MIN: usize = 2;
 const INITIAL_DRAW_NUM: u32 = 4;
+const COPIES_PER_CARD_DEFAULT: u8 = 1;
+const DECISION_TIMEOUT_SECS: u64 = 60;
+
+/// The decision timeouts that a game can be started with.
+///
+/// Shorter ones would make a player forfeit before they can decide,
+/// and longer ones would let a player hold the game indefinitely.
+pub const DECISION_TIMEOUT_RANGE: RangeInclusive<Duration> =
+    Duration::from_secs(5)..=Duration::from_secs(10 * 60);
 
 /// Game settings
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct GameSettings {
-    /// Color variants to include.
+    /// Color variants to include, each of which makes a full set of numbers.
     pub card_colors: Vec<CardColor>,
 
+    /// How the cards of each color are painted.
+    ///
+    /// The colors missing here are painted as [`CardColor::default_rgb`].
+    pub palette: BTreeMap<CardColor, CardRgb>,
+
     /// A maximum card number.
     pub max_card_number: CardNumberType,
 
+    /// How many identical cards the deck has for each pair of a number and a color.
+    pub copies_per_card: u8,
+
     /// A number of cards for each player to draw when the game is started.
     pub initial_draw_num: u32,
+
+    /// How long the turn player may take to make a decision.
+    ///
+    /// Must be within [`DECISION_TIMEOUT_RANGE`].
+    pub decision_timeout: Duration,
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         Self {
             card_colors: vec![CardColor::Black, CardColor::White],
+            palette: BTreeMap::new(),
             max_card_number: MAX_CARD_NUM_DEFAULT,
+            copies_per_card: COPIES_PER_CARD_DEFAULT,
             initial_draw_num: INITIAL_DRAW_NUM,
+            decision_timeout: Duration::from_secs(DECISION_TIMEOUT_SECS),
         }
     }
 }
 
 impl GameSettings {
-    pub(crate) fn build_cards(self) -> anyhow::Result<Vec<Card>> {
-        if self.card_colors.len() < COLOR_VARIANTS_MIN {
-            bail!("there must be at least {} card colors", COLOR_VARIANTS_MIN);
+    /// Returns the range of card numbers used in the game.
+    pub fn card_numbers(&self) -> RangeInclusive<CardNumberType> {
+        0..=self.max_card_number
+    }
+
+    /// Returns how the cards of the color are painted in the game.
+    pub fn card_rgb(&self, color: CardColor) -> CardRgb {
+        self.palette
+            .get(&color)
+            .copied()
+            .unwrap_or_else(|| color.default_rgb())
+    }
+
+    /// Checks if a 2 players game can be started with the settings.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        // A player with an empty field would lose as soon as they are attacked.
+        if self.initial_draw_num == 0 {
+            return Err(SettingsError::NoInitialCards);
+        }
+
+        if !DECISION_TIMEOUT_RANGE.contains(&self.decision_timeout) {
+            return Err(SettingsError::DecisionTimeoutOutOfRange {
+                timeout: self.decision_timeout,
+            });
         }
 
-        if self.max_card_number < MAX_CARD_NUM_DEFAULT {
-            bail!(
-                "max_card_number must be greater than {}",
-                MAX_CARD_NUM_DEFAULT
-            );
+        for color in &self.card_colors {
+            let rgb = self.card_rgb(*color);
+            if rgb.bg == rgb.text {
+                return Err(SettingsError::IllegibleColor(*color));
+            }
         }
 
-        let ret = create_cards(0..=self.max_card_number, self.card_colors).collect();
+        let num_cards = self.clone().build_cards()?.len();
+
+        // At least one card must be left in the talon for the first turn.
+        let needed = self.initial_draw_num as usize * 2 + 1;
+        if num_cards < needed {
+            return Err(SettingsError::DeckTooSmall {
+                needed,
+                have: num_cards,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn build_cards(self) -> Result<Vec<Card>, SettingsError> {
+        match self.card_colors.len() {
+            0 => return Err(SettingsError::NoColors),
+            have if have < COLOR_VARIANTS_MIN => {
+                return Err(SettingsError::TooFewColors {
+                    needed: COLOR_VARIANTS_MIN,
+                    have,
+                });
+            }
+            _ => (),
+        }
+
+        for (i, color) in self.card_colors.iter().enumerate() {
+            if self.card_colors[..i].contains(color) {
+                return Err(SettingsError::DuplicateColors(*color));
+            }
+        }
+
+        if self.copies_per_card == 0 {
+            return Err(SettingsError::NoCopies);
+        }
+
+        let copies = self.copies_per_card as usize;
+        let ret = create_cards(CardNumber::all(self.card_numbers()), self.card_colors)
+            .flat_map(|card| std::iter::repeat_n(card, copies))
+            .collect();
         Ok(ret)
     }
 }
+
+/// An error indicating that a game cannot be created.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SettingsError {
+    #[error("no card colors are specified")]
+    NoColors,
+    #[error("there must be at least {needed} card colors, but {have} are specified")]
+    TooFewColors { needed: usize, have: usize },
+    #[error("duplicated CardColor: {0:?}")]
+    DuplicateColors(CardColor),
+    #[error("the deck must have at least one copy of each card")]
+    NoCopies,
+    #[error("not enough cards to start the game: {needed} needed, {have} available")]
+    DeckTooSmall { needed: usize, have: usize },
+    #[error("each player must draw at least one card at the start")]
+    NoInitialCards,
+    #[error(
+        "the decision timeout must be between {:?} and {:?}, but {timeout:?} is specified",
+        DECISION_TIMEOUT_RANGE.start(),
+        DECISION_TIMEOUT_RANGE.end()
+    )]
+    DecisionTimeoutOutOfRange { timeout: Duration },
+    #[error("the numbers of {0:?} cards are painted in their background color")]
+    IllegibleColor(CardColor),
+}
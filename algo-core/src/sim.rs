@@ -0,0 +1,249 @@
+//! A headless driver to play games without networking.
+
+use crate::{
+    card::{CardNumber, CardNumberType},
+    event::GameEvent,
+    player::PlayerId,
+    settings::GameSettings,
+    BoardView, Game, NextEventError,
+};
+use anyhow::{bail, ensure, Context as _};
+use rand::{rngs::StdRng, seq::IndexedRandom as _, Rng as _, SeedableRng as _};
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+/// Makes decisions on behalf of a player.
+///
+/// Each method receives the board as seen by the player.
+pub trait Policy {
+    /// Returns the index of the card to attack on the target player's field,
+    /// which must be one of `targets`.
+    fn choose_attack_target(
+        &mut self,
+        board: &BoardView,
+        target_player: PlayerId,
+        targets: &[u32],
+    ) -> u32;
+
+    /// Returns the number guessed for the targeted card.
+    fn guess_number(
+        &mut self,
+        board: &BoardView,
+        target_player: PlayerId,
+        target_idx: u32,
+    ) -> CardNumber;
+
+    /// Returns `true` to attack again, or `false` to stay.
+    fn attack_or_stay(&mut self, board: &BoardView) -> bool;
+}
+
+/// The result of a game played by [`run_game`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    /// `None` if the game ended in a draw.
+    pub winner: Option<PlayerId>,
+    pub history: Vec<GameEvent>,
+}
+
+/// Plays a 2 players game to the end, letting the policies make all decisions.
+///
+/// Returns `Err` if:
+/// - the number of policies is not 2
+/// - the game cannot be created with the settings
+/// - a policy makes an invalid decision
+pub fn run_game(
+    settings: GameSettings,
+    seed: u64,
+    mut policies: BTreeMap<PlayerId, Box<dyn Policy>>,
+) -> anyhow::Result<GameResult> {
+    ensure!(policies.len() == 2, "there must be exactly 2 policies");
+
+    let player_ids = {
+        let mut keys = policies.keys().cloned();
+        (keys.next().unwrap(), keys.next().unwrap())
+    };
+    let mut game = Game::for_2_players_seeded(player_ids, settings, seed)?;
+    play_to_end(&mut game, &mut policies)?;
+
+    Ok(GameResult {
+        winner: game.winner(),
+        history: game.history,
+    })
+}
+
+fn play_to_end(
+    game: &mut Game,
+    policies: &mut BTreeMap<PlayerId, Box<dyn Policy>>,
+) -> anyhow::Result<()> {
+    loop {
+        let events = match game.next_event() {
+            Ok(v) => v.collect::<Vec<_>>(),
+            Err(NextEventError::NoMoreEvent) => break,
+            Err(e) => bail!(e),
+        };
+
+        for (player_id, event) in events {
+            let policy = policies.get_mut(&player_id).context("unknown PlayerId")?;
+            let resp = respond(game, player_id, &event, policy.as_mut())?;
+
+            game.store_player_response(player_id, resp)?;
+        }
+
+        game.process_event()?;
+    }
+
+    Ok(())
+}
+
+/// Returns the response of the player to the event, letting the policy make the decision if required.
+///
+/// Returns `Err` if the event requires a decision that the policy cannot make.
+pub fn respond(
+    game: &Game,
+    player_id: PlayerId,
+    event: &GameEvent,
+    policy: &mut dyn Policy,
+) -> anyhow::Result<GameEvent> {
+    if event.is_decision_required() && player_id == game.turn_player() {
+        decide(game, player_id, event, policy)
+    } else {
+        Ok(GameEvent::RespOk)
+    }
+}
+
+fn decide(
+    game: &Game,
+    player_id: PlayerId,
+    event: &GameEvent,
+    policy: &mut dyn Policy,
+) -> anyhow::Result<GameEvent> {
+    let board = game.board_view(player_id);
+
+    let ret = match *event {
+        GameEvent::AttackTargetSelectionRequired { target_player } => {
+            let targets = game
+                .legal_attack_targets(player_id)
+                .into_iter()
+                .filter(|(owner, _)| *owner == target_player)
+                .map(|(_, idx)| idx)
+                .collect::<Vec<_>>();
+            GameEvent::AttackTargetSelected {
+                target_idx: policy.choose_attack_target(&board, target_player, &targets),
+            }
+        }
+        GameEvent::NumberGuessRequired => {
+            let target_idx = game
+                .attack
+                .target_card_idx
+                .context("attack target is not selected")?;
+            GameEvent::NumberGuessed(policy.guess_number(
+                &board,
+                game.attack_target_player(),
+                target_idx,
+            ))
+        }
+        GameEvent::AttackOrStayDecisionRequired => GameEvent::AttackOrStayDecided {
+            attack: policy.attack_or_stay(&board),
+        },
+        ref unexpected => bail!("unexpected decision request: {:?}", unexpected),
+    };
+
+    Ok(ret)
+}
+
+/// A policy that makes every decision at random.
+#[derive(Debug, Clone)]
+pub struct RandomPolicy {
+    rng: StdRng,
+    card_numbers: RangeInclusive<CardNumberType>,
+}
+
+impl RandomPolicy {
+    pub fn new(settings: &GameSettings, seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            card_numbers: settings.card_numbers(),
+        }
+    }
+}
+
+impl Policy for RandomPolicy {
+    fn choose_attack_target(&mut self, _: &BoardView, _: PlayerId, targets: &[u32]) -> u32 {
+        *targets.choose(&mut self.rng).unwrap_or(&0)
+    }
+
+    fn guess_number(&mut self, _: &BoardView, _: PlayerId, _: u32) -> CardNumber {
+        CardNumber(self.rng.random_range(self.card_numbers.clone()))
+    }
+
+    fn attack_or_stay(&mut self, _: &BoardView) -> bool {
+        self.rng.random()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_policies(settings: &GameSettings, seed: u64) -> BTreeMap<PlayerId, Box<dyn Policy>> {
+        let (p1, p2) = PlayerId::dummy_pair();
+        BTreeMap::from([
+            (
+                p1,
+                Box::new(RandomPolicy::new(settings, seed)) as Box<dyn Policy>,
+            ),
+            (p2, Box::new(RandomPolicy::new(settings, !seed))),
+        ])
+    }
+
+    #[test]
+    fn random_policies_play_to_end() {
+        let settings = GameSettings::default();
+        let mut wins = BTreeMap::<Option<PlayerId>, u32>::new();
+
+        for seed in 0..1000 {
+            let mut policies = random_policies(&settings, seed);
+            let mut game =
+                Game::for_2_players_seeded(PlayerId::dummy_pair(), settings.clone(), seed).unwrap();
+            play_to_end(&mut game, &mut policies).unwrap();
+
+            assert!(game.is_over(), "seed {}: the game did not end", seed);
+
+            let winner = game.winner();
+            let loser = game.board.fully_revealed_player();
+            assert_eq!(
+                winner.is_some(),
+                loser.is_some(),
+                "seed {}: a winner must come with a fully revealed loser",
+                seed
+            );
+            if winner.is_some() {
+                assert_ne!(winner, loser, "seed {}", seed);
+            }
+            assert_eq!(
+                game.history.last(),
+                Some(if winner.is_some() {
+                    &GameEvent::GameEnded
+                } else {
+                    &GameEvent::GameDrawn
+                }),
+                "seed {}",
+                seed
+            );
+
+            *wins.entry(winner).or_default() += 1;
+        }
+
+        println!("wins: {:?}", wins);
+        let (p1, p2) = PlayerId::dummy_pair();
+        assert!(wins.get(&Some(p1)).is_some_and(|v| *v > 0));
+        assert!(wins.get(&Some(p2)).is_some_and(|v| *v > 0));
+    }
+
+    #[test]
+    fn run_game_is_deterministic() {
+        let settings = GameSettings::default();
+        let run = || run_game(settings.clone(), 7, random_policies(&settings, 7)).unwrap();
+
+        assert_eq!(run(), run());
+    }
+}
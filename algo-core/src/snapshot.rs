@@ -0,0 +1,125 @@
+use crate::{
+    event::{EventQueue, GameEvent},
+    player::{PlayerId, TurnPlayer},
+    settings::GameSettings,
+    AttackContext, Board, Game,
+};
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A serializable copy of the whole [`Game`] state.
+///
+/// A game can be resumed from a snapshot at any point,
+/// even while an event is waiting for the players' responses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GameSnapshot {
+    settings: GameSettings,
+    seed: u64,
+    board: Board,
+    turn_player: TurnPlayer,
+    attack: AttackContext,
+    staged_event: Option<GameEvent>,
+    event_queue: EventQueue<GameEvent>,
+    event_responses: BTreeMap<PlayerId, Option<GameEvent>>,
+    history: Vec<GameEvent>,
+}
+
+impl Game {
+    /// Captures the current state of the game.
+    pub fn snapshot(&self) -> GameSnapshot {
+        let this = self.clone();
+
+        GameSnapshot {
+            settings: this.settings,
+            seed: this.seed,
+            board: this.board,
+            turn_player: this.turn_player,
+            attack: this.attack,
+            staged_event: this.staged_event,
+            event_queue: this.event_queue,
+            event_responses: this.event_responses,
+            history: this.history,
+        }
+    }
+
+    /// Resumes a game from the snapshot.
+    ///
+    /// Returns `Err` if the players recorded in the snapshot are inconsistent.
+    pub fn restore(snapshot: GameSnapshot) -> anyhow::Result<Self> {
+        let players = snapshot.board.players.keys().collect::<BTreeSet<_>>();
+
+        ensure!(
+            snapshot.event_responses.keys().collect::<BTreeSet<_>>() == players,
+            "invalid snapshot: responding players do not match the board"
+        );
+        ensure!(
+            snapshot.turn_player.turn_order().count() == players.len()
+                && snapshot
+                    .turn_player
+                    .turn_order()
+                    .all(|id| players.contains(&id)),
+            "invalid snapshot: turn order does not match the board"
+        );
+
+        let ret = Self {
+            settings: snapshot.settings,
+            seed: snapshot.seed,
+            board: snapshot.board,
+            turn_player: snapshot.turn_player,
+            attack: snapshot.attack,
+            staged_event: snapshot.staged_event,
+            event_queue: snapshot.event_queue,
+            event_responses: snapshot.event_responses,
+            history: snapshot.history,
+        };
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        event::GameEvent,
+        player::PlayerId,
+        settings::GameSettings,
+        sim::{respond, RandomPolicy},
+        Game,
+    };
+
+    /// Responds to the staged event for all players, then processes it.
+    fn play_event(game: &mut Game, events: Vec<(PlayerId, GameEvent)>, policy: &mut RandomPolicy) {
+        for (player_id, event) in events {
+            let resp = respond(game, player_id, &event, policy).unwrap();
+            game.store_player_response(player_id, resp).unwrap();
+        }
+        game.process_event().unwrap();
+    }
+
+    #[test]
+    fn resumes_from_snapshot_to_same_next_event() {
+        let settings = GameSettings::default();
+        let mut policy = RandomPolicy::new(&settings, 0);
+        let mut game = Game::for_2_players_seeded(PlayerId::dummy_pair(), settings, 0).unwrap();
+
+        let events = loop {
+            let events = game.next_event().unwrap().collect::<Vec<_>>();
+            if events[0].1 == GameEvent::NumberGuessRequired {
+                break events;
+            }
+            play_event(&mut game, events, &mut policy);
+        };
+
+        let bytes = bincode::serialize(&game.snapshot()).unwrap();
+        let mut restored = Game::restore(bincode::deserialize(&bytes).unwrap()).unwrap();
+        assert_eq!(restored.snapshot(), game.snapshot());
+
+        play_event(&mut game, events.clone(), &mut policy.clone());
+        play_event(&mut restored, events, &mut policy);
+
+        let next = game.next_event().unwrap().collect::<Vec<_>>();
+        let restored_next = restored.next_event().unwrap().collect::<Vec<_>>();
+        assert_eq!(restored_next, next);
+        assert_eq!(restored.snapshot(), game.snapshot());
+    }
+}
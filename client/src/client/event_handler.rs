@@ -1,6 +1,13 @@
 use bevy::prelude::*;
 use protocol::{EventBox, EventKind, NextEventId, WithMetadata};
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::sync::mpsc::{error::SendError, UnboundedReceiver, UnboundedSender};
 
 #[derive(Debug, Resource)]
@@ -9,6 +16,7 @@ pub struct EventHandler<I, O> {
     out_tx: UnboundedSender<WithMetadata<O>>,
     pub storage: EventBox<I>,
     next_id: NextEventId,
+    round_trip_time: RoundTripTime,
 }
 
 impl<I, O> EventHandler<I, O> {
@@ -21,9 +29,21 @@ impl<I, O> EventHandler<I, O> {
             out_tx,
             storage: EventBox::default(),
             next_id: NextEventId::default(),
+            round_trip_time: RoundTripTime::default(),
         }
     }
 
+    /// Shares the round-trip time measured by the connection.
+    pub fn with_round_trip_time(mut self, round_trip_time: RoundTripTime) -> Self {
+        self.round_trip_time = round_trip_time;
+        self
+    }
+
+    /// Returns the latest round-trip time to the peer, if measured yet.
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        self.round_trip_time.get()
+    }
+
     pub fn send_request(
         &mut self,
         event: O,
@@ -54,6 +74,32 @@ impl<I, O> EventHandler<I, O> {
     }
 }
 
+/// The latest round-trip time to the peer, written by the connection task.
+#[derive(Debug, Clone)]
+pub struct RoundTripTime(Arc<AtomicU32>);
+
+impl Default for RoundTripTime {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU32::new(Self::UNKNOWN)))
+    }
+}
+
+impl RoundTripTime {
+    const UNKNOWN: u32 = u32::MAX;
+
+    pub fn get(&self) -> Option<Duration> {
+        match self.0.load(Ordering::Relaxed) {
+            Self::UNKNOWN => None,
+            ms => Some(Duration::from_millis(ms.into())),
+        }
+    }
+
+    pub fn set(&self, rtt: Duration) {
+        let ms = u32::try_from(rtt.as_millis()).unwrap_or(u32::MAX);
+        self.0.store(ms.min(Self::UNKNOWN - 1), Ordering::Relaxed);
+    }
+}
+
 pub struct EventHandlerPlugin<I, O> {
     _marker: PhantomData<fn(&I, &O)>,
 }
@@ -1,7 +1,16 @@
-use super::{InboundEvent, OutboundEvent, DISCONNECTED_EV_ID};
+use super::{InboundEvent, OutboundEvent, RoundTripTime, DISCONNECTED_EV_ID};
 use bevy::prelude::{debug, info};
-use protocol::WithMetadata;
-use tokio::{net::TcpStream, sync::mpsc};
+use bincode_io::Format;
+use protocol::{
+    heartbeat::{Heartbeat, HeartbeatSettings, HEARTBEAT_EV_ID},
+    WithMetadata, HANDSHAKE_EV_ID, PROTOCOL_VERSION,
+};
+use std::time::Duration;
+use tokio::{
+    net::TcpStream,
+    sync::mpsc,
+    time::{Instant, Interval, MissedTickBehavior},
+};
 use tokio_util::sync::CancellationToken;
 
 type TcpStreamWrapper =
@@ -12,6 +21,8 @@ pub struct EventRelay {
     out_rx: mpsc::UnboundedReceiver<WithMetadata<OutboundEvent>>,
     in_tx: mpsc::UnboundedSender<WithMetadata<InboundEvent>>,
     shutdown_token: CancellationToken,
+    heartbeat: Heartbeat,
+    round_trip_time: RoundTripTime,
 }
 
 impl Drop for EventRelay {
@@ -26,22 +37,46 @@ const INTERNAL_DISCONNECTED_EV: WithMetadata<InboundEvent> = WithMetadata {
     event: InboundEvent::ServerShutdown,
 };
 
+const HELLO: WithMetadata<OutboundEvent> = WithMetadata {
+    kind: protocol::EventKind::Request,
+    id: HANDSHAKE_EV_ID,
+    event: OutboundEvent::Hello {
+        version: PROTOCOL_VERSION,
+    },
+};
+
+const PING: WithMetadata<OutboundEvent> = WithMetadata {
+    kind: protocol::EventKind::Request,
+    id: HEARTBEAT_EV_ID,
+    event: OutboundEvent::Ping,
+};
+
 impl EventRelay {
     pub fn new(
         stream: TcpStream,
         out_rx: mpsc::UnboundedReceiver<WithMetadata<OutboundEvent>>,
         in_tx: mpsc::UnboundedSender<WithMetadata<InboundEvent>>,
         shutdown_token: CancellationToken,
+        round_trip_time: RoundTripTime,
+        format: Format,
+        read_buf_size: usize,
     ) -> Self {
         Self {
-            stream: TcpStreamWrapper::new(stream, 1024),
+            stream: TcpStreamWrapper::new(stream, read_buf_size, protocol::MAX_FRAME_LEN, format),
             out_rx,
             in_tx,
             shutdown_token,
+            heartbeat: Heartbeat::new(HeartbeatSettings::default()),
+            round_trip_time,
         }
     }
 
     pub async fn run(&mut self) -> anyhow::Result<()> {
+        let mut ping_timer = ping_timer(self.heartbeat.interval());
+
+        // The server expects the protocol version before anything else.
+        self.stream.write(&HELLO).await?;
+
         loop {
             // debug!("entering select");
             tokio::select! {
@@ -51,7 +86,15 @@ impl EventRelay {
                 }
 
                 Ok(_) = self.stream.readable() => {
-                    self.relay_inbound_ev()?;
+                    self.relay_inbound_ev().await?;
+                }
+
+                _ = ping_timer.tick() => {
+                    if let Err(e) = self.heartbeat.ping() {
+                        self.in_tx.send(INTERNAL_DISCONNECTED_EV)?;
+                        return Err(e.into());
+                    }
+                    self.stream.write(&PING).await?;
                 }
 
                 Some(outbound_ev) = self.out_rx.recv() => {
@@ -64,7 +107,7 @@ impl EventRelay {
         Ok(())
     }
 
-    fn relay_inbound_ev(&mut self) -> anyhow::Result<()> {
+    async fn relay_inbound_ev(&mut self) -> anyhow::Result<()> {
         match self.stream.try_read() {
             Err(e) if e.would_block() => {
                 debug!("relaying inbound ev: exiting due to WouldBlock");
@@ -75,6 +118,24 @@ impl EventRelay {
                 Err(e.into())
             }
             Ok(ev) => {
+                self.heartbeat.received();
+
+                match ev.event {
+                    InboundEvent::Ping => {
+                        self.stream
+                            .write(&ev.response_to(OutboundEvent::Pong))
+                            .await?;
+                        return Ok(());
+                    }
+                    InboundEvent::Pong => {
+                        if let Some(rtt) = self.heartbeat.pong() {
+                            self.round_trip_time.set(rtt);
+                        }
+                        return Ok(());
+                    }
+                    _ => (),
+                }
+
                 info!("read: {:?}", ev);
 
                 self.in_tx.send(ev)?;
@@ -83,3 +144,10 @@ impl EventRelay {
         }
     }
 }
+
+/// Returns a timer that ticks every `period`, starting after the first period.
+fn ping_timer(period: Duration) -> Interval {
+    let mut timer = tokio::time::interval_at(Instant::now() + period, period);
+    timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    timer
+}
@@ -1,4 +1,6 @@
 use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
+use bincode_io::Format;
+use rand::Rng as _;
 use std::{
     net::{IpAddr, SocketAddr},
     thread,
@@ -17,6 +19,7 @@ mod event_relay;
 use event_relay::EventRelay;
 
 pub mod event_handler;
+use event_handler::RoundTripTime;
 
 pub type EventHandler = event_handler::EventHandler<InboundEvent, OutboundEvent>;
 pub type ReceivedRequest = event_handler::ReceivedRequest<InboundEvent>;
@@ -42,14 +45,39 @@ pub fn client_connection_plugin(app: &mut App) {
 /// [`Client`] resource is added to the world.
 ///
 /// [`client_connection_plugin`] is required.
-pub fn spawn_client(commands: &mut Commands, addr: IpAddr, port: u16) {
-    let conn_handle = connect(addr, port);
+pub fn spawn_client(
+    commands: &mut Commands,
+    addr: IpAddr,
+    port: u16,
+    retry_settings: ConnectRetrySettings,
+    format: Format,
+    read_buf_size: usize,
+) {
+    let conn_handle = connect(addr, port, retry_settings, format, read_buf_size);
     commands.spawn((
         ConnectionHandle(Some(conn_handle)),
         Name::new("ConnectionHandle"),
     ));
 }
 
+/// Settings for retrying to connect to the server.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectRetrySettings {
+    /// The number of attempts to connect before giving up.
+    pub max_attempts: u32,
+    /// The upper limit of the interval between attempts, before jitter is applied.
+    pub max_backoff: Duration,
+}
+
+impl Default for ConnectRetrySettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
 #[derive(Debug, Event)]
 pub struct SpawnClientResult(pub Result<(), Box<str>>);
 
@@ -78,7 +106,13 @@ impl ShutdownClientOnDrop {
     }
 }
 
-fn connect(addr: IpAddr, port: u16) -> ConnectionHandleImpl {
+fn connect(
+    addr: IpAddr,
+    port: u16,
+    retry_settings: ConnectRetrySettings,
+    format: Format,
+    read_buf_size: usize,
+) -> ConnectionHandleImpl {
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
@@ -96,13 +130,16 @@ fn connect(addr: IpAddr, port: u16) -> ConnectionHandleImpl {
 
     let (oneshot_tx, oneshot_rx) = oneshot::channel();
 
+    let round_trip_time = RoundTripTime::default();
+    let round_trip_time_cloned = round_trip_time.clone();
+
     let thread_handle = thread::spawn(move || {
         // MAYBE: handle panics?
 
         rt.block_on(async move {
             let socket_addr = SocketAddr::new(addr, port);
 
-            let stream = match connect_tcp(socket_addr, cancel_token_cloned).await {
+            let stream = match connect_tcp(socket_addr, retry_settings, cancel_token_cloned).await {
                 Ok(v) => {
                     conn_res_tx
                         .send(Ok(()))
@@ -120,7 +157,15 @@ fn connect(addr: IpAddr, port: u16) -> ConnectionHandleImpl {
             };
 
             info!("created socket: {}", stream.local_addr().unwrap()); // DEBUG
-            let mut event_relay = EventRelay::new(stream, out_rx, in_tx, shutdown_token_cloned);
+            let mut event_relay = EventRelay::new(
+                stream,
+                out_rx,
+                in_tx,
+                shutdown_token_cloned,
+                round_trip_time_cloned,
+                format,
+                read_buf_size,
+            );
 
             tokio::select! {
                 ret = event_relay.run() => ret,
@@ -130,7 +175,7 @@ fn connect(addr: IpAddr, port: u16) -> ConnectionHandleImpl {
         .ok(); // FIXME: Not ok
     });
 
-    let ev_handler = EventHandler::new(in_rx, out_tx);
+    let ev_handler = EventHandler::new(in_rx, out_tx).with_round_trip_time(round_trip_time);
     let shutdown_handler = ShutdownClientOnDrop {
         thread_handle: Some(thread_handle),
         oneshot_tx: Some(oneshot_tx),
@@ -258,33 +303,40 @@ impl CancelConnTask {
 
 async fn connect_tcp(
     socket_addr: SocketAddr,
+    retry_settings: ConnectRetrySettings,
     cancel_token: CancellationToken,
 ) -> Result<TcpStream, ConnectTcpError> {
-    let mut intvl = 0;
+    let mut intvl = Duration::ZERO;
     let mut attempts = 0;
-    let max_attempts = 4;
 
     loop {
         tokio::select! {
-            _ = tokio::time::sleep(Duration::from_secs(intvl)) => {
-                match TcpStream::connect(socket_addr).await {
-                    Ok(stream) => {
-                        return Ok(stream);
-                    }
-                    Err(_) => {
-                        attempts += 1;
-                        if attempts >= max_attempts {
-                            return Err(ConnectTcpError::Timedout);
-                        }
-
-                        intvl = next_backoff_intvl(intvl);
-                        info!("failed to connect, retrying in {:?}", intvl);
-                    }
-                }
+            _ = tokio::time::sleep(intvl) => (),
+            _ = cancel_token.cancelled() => {
+                return Err(ConnectTcpError::Cancelled);
             }
+        }
+
+        let res = tokio::select! {
+            v = TcpStream::connect(socket_addr) => v,
             _ = cancel_token.cancelled() => {
                 return Err(ConnectTcpError::Cancelled);
             }
+        };
+
+        match res {
+            Ok(stream) => {
+                return Ok(stream);
+            }
+            Err(e) => {
+                attempts += 1;
+                if attempts >= retry_settings.max_attempts {
+                    return Err(ConnectTcpError::Timedout);
+                }
+
+                intvl = backoff_intvl(attempts, retry_settings.max_backoff);
+                info!("failed to connect ({}), retrying in {:?}", e, intvl);
+            }
         }
     }
 }
@@ -297,10 +349,15 @@ enum ConnectTcpError {
     Timedout,
 }
 
-fn next_backoff_intvl(prev_intvl: u64) -> u64 {
-    if prev_intvl == 0 {
-        1
-    } else {
-        prev_intvl * 2
-    }
+/// Returns the interval before the next attempt.
+///
+/// The interval doubles from 1 second up to `max_backoff`,
+/// and is randomly shortened by up to half to spread out reconnecting clients.
+fn backoff_intvl(failed_attempts: u32, max_backoff: Duration) -> Duration {
+    let exp = failed_attempts.saturating_sub(1).min(31);
+    let intvl = Duration::from_secs(1)
+        .saturating_mul(1 << exp)
+        .min(max_backoff);
+
+    intvl.mul_f64(rand::rng().random_range(0.5..=1.0))
 }
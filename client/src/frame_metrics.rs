@@ -0,0 +1,79 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+pub fn frame_metrics_plugin(app: &mut App) {
+    if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+        app.add_plugins(FrameTimeDiagnosticsPlugin);
+    }
+
+    app.add_systems(Startup, setup).add_systems(
+        Update,
+        (
+            toggle.run_if(input_just_pressed(TOGGLE_KEY)),
+            FrameMetrics::update,
+        )
+            .chain(),
+    );
+}
+
+/// An overlay showing the FPS and the frame time, hidden until toggled.
+#[derive(Component)]
+struct FrameMetrics;
+
+impl FrameMetrics {
+    fn update(
+        diagnostics: Res<DiagnosticsStore>,
+        overlay: Single<(&mut Text, &Visibility), With<Self>>,
+    ) {
+        let (mut text, visibility) = overlay.into_inner();
+        if *visibility == Visibility::Hidden {
+            return;
+        }
+
+        let smoothed = |path| {
+            diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.smoothed())
+                .unwrap_or_default()
+        };
+
+        text.0 = format!(
+            "{:.0} FPS\n{:.2} ms",
+            smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+            smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME),
+        );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        FrameMetrics,
+        Text::default(),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Right),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(4.0),
+            right: Val::Px(4.0),
+            ..default()
+        },
+        GlobalZIndex(i32::MAX),
+        Visibility::Hidden,
+        Name::new("FrameMetrics"),
+    ));
+}
+
+fn toggle(mut visibility: Single<&mut Visibility, With<FrameMetrics>>) {
+    **visibility = match **visibility {
+        Visibility::Hidden => Visibility::Visible,
+        _ => Visibility::Hidden,
+    };
+}
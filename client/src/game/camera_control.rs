@@ -0,0 +1,198 @@
+use super::{dialog::dialog_takes_keys, GameMode, CAMERA_ROTATION, CAMERA_TRANSLATION};
+use bevy::{
+    input::common_conditions::{input_just_pressed, input_pressed},
+    prelude::*,
+};
+use bevy_simple_text_input::TextInputInactive;
+use std::{f32::consts::FRAC_PI_8, ops::RangeInclusive};
+
+const KEY_FORWARD: KeyCode = KeyCode::KeyW;
+const KEY_BACK: KeyCode = KeyCode::KeyS;
+const KEY_LEFT: KeyCode = KeyCode::KeyA;
+const KEY_RIGHT: KeyCode = KeyCode::KeyD;
+const KEY_ZOOM_IN: KeyCode = KeyCode::ShiftLeft;
+const KEY_ZOOM_OUT: KeyCode = KeyCode::ControlLeft;
+const KEY_ROTATE_FORWARD: KeyCode = KeyCode::KeyR;
+const KEY_ROTATE_BACK: KeyCode = KeyCode::KeyF;
+const KEY_ORBIT_LEFT: KeyCode = KeyCode::KeyZ;
+const KEY_ORBIT_RIGHT: KeyCode = KeyCode::KeyC;
+const KEY_RESET_XF: KeyCode = KeyCode::KeyQ;
+
+const KEY_PRINT_XF: KeyCode = KeyCode::KeyP;
+const KEY_TOGGLE_MSG: KeyCode = KeyCode::KeyM;
+
+/// Lets the player move the camera around the table while in `ctx_state`.
+///
+/// The camera is reset to the default framing when entering the state.
+pub struct CameraControlPlugin {
+    pub ctx_state: GameMode,
+    pub limits: Option<CameraLimits>,
+}
+
+impl Plugin for CameraControlPlugin {
+    fn build(&self, app: &mut App) {
+        let ctx_state = self.ctx_state;
+
+        app.add_systems(
+            OnEnter(ctx_state),
+            (reset_camera, show_control_info(ctx_state)),
+        )
+        .add_systems(
+            Update,
+            (
+                camera_vertical_move::<-1>.run_if(input_pressed(KEY_FORWARD)),
+                camera_vertical_move::<1>.run_if(input_pressed(KEY_BACK)),
+                camera_horizontal_move::<1>.run_if(input_pressed(KEY_RIGHT)),
+                camera_horizontal_move::<-1>.run_if(input_pressed(KEY_LEFT)),
+                zoom_camera::<1>.run_if(input_pressed(KEY_ZOOM_IN)),
+                zoom_camera::<-1>.run_if(input_pressed(KEY_ZOOM_OUT)),
+                rotate_camera::<-1>.run_if(input_pressed(KEY_ROTATE_FORWARD)),
+                rotate_camera::<1>.run_if(input_pressed(KEY_ROTATE_BACK)),
+                orbit_camera::<-1>.run_if(input_pressed(KEY_ORBIT_LEFT)),
+                orbit_camera::<1>.run_if(input_pressed(KEY_ORBIT_RIGHT)),
+                reset_camera.run_if(input_pressed(KEY_RESET_XF)),
+                print_camera_transform.run_if(input_just_pressed(KEY_PRINT_XF)),
+                toggle_control_msg.run_if(input_just_pressed(KEY_TOGGLE_MSG)),
+            )
+                .run_if(
+                    in_state(ctx_state)
+                        .and(not(typing))
+                        .and(not(dialog_takes_keys)),
+                ),
+        );
+
+        if let Some(limits) = self.limits.clone() {
+            app.add_systems(
+                PostUpdate,
+                (move |xf: Single<&mut Transform, With<Camera3d>>| limits.apply(xf))
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(ctx_state)),
+            );
+        }
+    }
+
+    // Each game mode adds its own instance.
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+/// Keeps the camera around the table, so that the players don't lose the play area.
+#[derive(Debug, Clone)]
+pub struct CameraLimits {
+    /// Distance from the center of the table.
+    pub distance: RangeInclusive<f32>,
+    /// Minimum height above the table.
+    pub min_height: f32,
+    /// Downward tilt of the camera, in radians (negative values look down).
+    pub pitch: RangeInclusive<f32>,
+}
+
+impl CameraLimits {
+    fn apply(&self, mut xf: Single<&mut Transform, With<Camera3d>>) {
+        let distance = xf.translation.length();
+        let clamped = distance.clamp(*self.distance.start(), *self.distance.end());
+        if distance > 0.0 && clamped != distance {
+            xf.translation *= clamped / distance;
+        }
+
+        xf.translation.y = xf.translation.y.max(self.min_height);
+
+        let (yaw, pitch, roll) = xf.rotation.to_euler(EulerRot::YXZ);
+        let clamped = pitch.clamp(*self.pitch.start(), *self.pitch.end());
+        if clamped != pitch {
+            xf.rotation = Quat::from_euler(EulerRot::YXZ, yaw, clamped, roll);
+        }
+    }
+}
+
+/// The keys would also go into the chat while typing.
+fn typing(inputs: Query<&TextInputInactive>) -> bool {
+    inputs.iter().any(|inactive| !inactive.0)
+}
+
+#[derive(Component)]
+struct CameraControlInfo;
+
+fn show_control_info(ctx_state: GameMode) -> impl Fn(Commands) {
+    move |mut commands: Commands| {
+        commands.spawn((
+            StateScoped(ctx_state),
+            CameraControlInfo,
+            Text(format!(
+                "[Camera]\n  Move: {} {} {} {}\n  Zoom: {} {}\nRotate: {} {}\n Orbit: {} {}\n Reset: {}\n\n{}",
+                key_name(KEY_FORWARD),
+                key_name(KEY_BACK),
+                key_name(KEY_LEFT),
+                key_name(KEY_RIGHT),
+                key_name(KEY_ZOOM_IN),
+                key_name(KEY_ZOOM_OUT),
+                key_name(KEY_ROTATE_FORWARD),
+                key_name(KEY_ROTATE_BACK),
+                key_name(KEY_ORBIT_LEFT),
+                key_name(KEY_ORBIT_RIGHT),
+                key_name(KEY_RESET_XF),
+                format_args!(
+                    "[Misc]\nPrint camera xf: {}\nToggle this msg: {}",
+                    key_name(KEY_PRINT_XF),
+                    key_name(KEY_TOGGLE_MSG),
+                ),
+            )),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+/// Moves along the table, relative to where the camera is facing.
+fn camera_horizontal_move<const D: i32>(mut xf: Single<&mut Transform, With<Camera3d>>) {
+    let right = on_table(*xf.right());
+    xf.translation += right * D as f32 * 0.05;
+}
+
+fn camera_vertical_move<const D: i32>(mut xf: Single<&mut Transform, With<Camera3d>>) {
+    let back = on_table(*xf.back());
+    xf.translation += back * D as f32 * 0.05;
+}
+
+fn on_table(dir: Vec3) -> Vec3 {
+    Vec3::new(dir.x, 0.0, dir.z).normalize_or_zero()
+}
+
+fn zoom_camera<const D: i32>(mut xf: Single<&mut Transform, With<Camera3d>>) {
+    let forward = xf.rotation.mul_vec3(Vec3::NEG_Z);
+    xf.translation += forward * D as f32 * 0.1;
+}
+
+fn rotate_camera<const D: i32>(mut xf: Single<&mut Transform, With<Camera3d>>) {
+    xf.rotation *= Quat::from_rotation_x(FRAC_PI_8 * 0.01 * D as f32);
+}
+
+/// Circles around the center of the table.
+fn orbit_camera<const D: i32>(mut xf: Single<&mut Transform, With<Camera3d>>) {
+    xf.rotate_around(
+        Vec3::ZERO,
+        Quat::from_rotation_y(FRAC_PI_8 * 0.05 * D as f32),
+    );
+}
+
+fn reset_camera(mut xf: Single<&mut Transform, With<Camera3d>>) {
+    xf.translation = CAMERA_TRANSLATION;
+    xf.rotation = CAMERA_ROTATION;
+}
+
+fn print_camera_transform(xf: Single<&Transform, With<Camera3d>>) {
+    info!("{:?}", *xf);
+}
+
+fn toggle_control_msg(mut vis: Single<&mut Visibility, With<CameraControlInfo>>) {
+    **vis = match **vis {
+        Visibility::Hidden => Visibility::Visible,
+        Visibility::Visible => Visibility::Hidden,
+        v => v,
+    };
+}
+
+fn key_name(code: KeyCode) -> String {
+    let s = format!("{:?}", code);
+    s.strip_prefix("Key").map(|v| v.to_string()).unwrap_or(s)
+}
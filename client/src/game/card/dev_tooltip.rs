@@ -0,0 +1,85 @@
+use super::instance::CardInstance;
+use crate::game::{
+    card_field::{CardField, CardFieldOwnedBy},
+    CTX_STATE,
+};
+use bevy::{
+    picking::{hover::HoverMap, pointer::PointerId},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+const TOOLTIP_OFFSET: Vec2 = Vec2::splat(16.0);
+
+/// Shows the state of the hovered card, for debugging desyncs.
+pub fn card_dev_tooltip_plugin(app: &mut App) {
+    app.add_systems(OnEnter(CTX_STATE), CardDevTooltip::setup)
+        .add_systems(Update, CardDevTooltip::update.run_if(in_state(CTX_STATE)));
+}
+
+#[derive(Component)]
+struct CardDevTooltip;
+
+impl CardDevTooltip {
+    fn setup(mut commands: Commands) {
+        commands.spawn((
+            StateScoped(CTX_STATE),
+            CardDevTooltip,
+            Node {
+                position_type: PositionType::Absolute,
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            Text::default(),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            GlobalZIndex(i32::MAX),
+            Visibility::Hidden,
+            PickingBehavior::IGNORE,
+            Name::new("CardDevTooltip"),
+        ));
+    }
+
+    fn update(
+        hover_map: Res<HoverMap>,
+        parents: Query<&Parent>,
+        cards: Query<&CardInstance>,
+        fields: Query<(&CardField, &CardFieldOwnedBy)>,
+        window: Single<&Window, With<PrimaryWindow>>,
+        mut tooltip: Single<(&mut Text, &mut Node, &mut Visibility), With<Self>>,
+    ) {
+        let hovered = hover_map
+            .get(&PointerId::Mouse)
+            .into_iter()
+            .flat_map(|v| v.keys())
+            .find_map(|entity| {
+                // The mesh is a child of the card entity.
+                std::iter::once(*entity)
+                    .chain(parents.get(*entity).map(Parent::get))
+                    .find_map(|e| cards.get(e).ok().map(|card| (e, card)))
+            });
+
+        let (text, node, visibility) = &mut *tooltip;
+
+        let (Some((entity, card)), Some(cursor)) = (hovered, window.cursor_position()) else {
+            **visibility = Visibility::Hidden;
+            return;
+        };
+
+        let location = fields
+            .iter()
+            .find_map(|(field, owner)| {
+                let idx = field.cards().iter().position(|v| *v == entity)?;
+                Some(format!(" idx={} owner={:?}", idx, owner.0))
+            })
+            .unwrap_or_default();
+
+        text.0 = format!("{}{}", card.get(), location);
+        node.left = Val::Px(cursor.x + TOOLTIP_OFFSET.x);
+        node.top = Val::Px(cursor.y + TOOLTIP_OFFSET.y);
+        **visibility = Visibility::Inherited;
+    }
+}
@@ -1,3 +1,4 @@
+use super::picking::InteractionLocked;
 use crate::game::CTX_STATE;
 use bevy::{
     animation::{animated_field, AnimationTarget, AnimationTargetId},
@@ -71,6 +72,7 @@ impl CardPickingState {
                     }
                 }
                 CardPickingState::Pickable => {
+                    commands.remove_resource::<InteractionLocked>();
                     commands.trigger_targets(
                         observer_controller::Activate::<Pointer<Click>>::new(),
                         entity,
@@ -3,101 +3,121 @@ use bevy::{
     animation::{animated_field, AnimationTarget, AnimationTargetId},
     prelude::*,
 };
-use client::utils::AddObserverExt as _;
+use client::utils::{set_timeout::AnimationSpeed, AddObserverExt as _};
 use std::f32::consts::PI;
 
-const FLIP_ANIMATION_DURATION_SECS: f32 = 0.5;
+/// The shortest flip, as the animation clip cannot be empty.
+const MIN_FLIP_DURATION_SECS: f32 = 0.01;
 
 pub fn card_flip_plugin(app: &mut App) {
-    app.add_systems(Startup, setup_animation)
+    app.init_resource::<FlipSettings>()
         .add_state_scoped_observer_named(CTX_STATE, FlipCard::handle_trigger)
-        .add_state_scoped_observer_named(CTX_STATE, CardFlipStarted::handle_trigger)
         .add_state_scoped_observer_named(CTX_STATE, CardFlipFinished::handle_trigger)
         .add_systems(Update, CardFlipTarget::adjust_y.run_if(in_state(CTX_STATE)));
 }
 
-#[derive(Resource)]
-struct CardFlipAnimation {
-    animation_target: AnimationTarget,
-    node_idx: AnimationNodeIndex,
+/// How cards are flipped, unless specified by [`FlipCard`].
+///
+/// The duration is scaled by [`AnimationSpeed`].
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct FlipSettings {
+    pub duration_secs: f32,
+    pub ease: EaseFunction,
 }
 
-fn setup_animation(
-    mut commands: Commands,
-    mut clips: ResMut<Assets<AnimationClip>>,
-    mut graphs: ResMut<Assets<AnimationGraph>>,
-) {
-    let duration = FLIP_ANIMATION_DURATION_SECS;
-
-    let animation_target_id = AnimationTargetId::from_name(&"CardFlip".into());
-
-    let mut clip = AnimationClip::default();
-    clip.add_event_to_target(animation_target_id, 0.0, CardFlipStarted);
-    clip.add_curve_to_target(
-        animation_target_id,
-        AnimatableCurve::new(
-            animated_field!(Transform::rotation),
-            EasingCurve::new(
-                Quat::from_rotation_z(-PI),
-                Quat::from_rotation_z(0.0),
-                EaseFunction::CubicInOut,
-            )
-            .reparametrize_linear(Interval::new(0.0, duration).unwrap())
-            .unwrap(),
-        ),
-    );
-    clip.add_event_to_target(animation_target_id, duration, CardFlipFinished);
-
-    let (graph, node_idx) = AnimationGraph::from_clip(clips.add(clip));
-    let graph_handle = graphs.add(graph);
-
-    let player = commands
-        .spawn((
-            AnimationPlayer::default(),
-            AnimationGraphHandle(graph_handle),
-            Name::new("CardFlipAnimationPlayer"),
-        ))
-        .id();
-
-    commands.insert_resource(CardFlipAnimation {
-        animation_target: AnimationTarget {
-            id: animation_target_id,
-            player,
-        },
-        node_idx,
-    });
+impl Default for FlipSettings {
+    fn default() -> Self {
+        Self {
+            duration_secs: 0.5,
+            ease: EaseFunction::CubicInOut,
+        }
+    }
+}
+
+impl FlipSettings {
+    fn animation_clip(&self, duration_secs: f32) -> AnimationClip {
+        let target_id = flip_animation_target_id();
+
+        let mut clip = AnimationClip::default();
+        clip.add_curve_to_target(
+            target_id,
+            AnimatableCurve::new(
+                animated_field!(Transform::rotation),
+                EasingCurve::new(
+                    Quat::from_rotation_z(-PI),
+                    Quat::from_rotation_z(0.0),
+                    self.ease,
+                )
+                .reparametrize_linear(Interval::new(0.0, duration_secs).unwrap())
+                .unwrap(),
+            ),
+        );
+        clip.add_event_to_target(target_id, duration_secs, CardFlipFinished);
+        clip
+    }
+}
+
+fn flip_animation_target_id() -> AnimationTargetId {
+    AnimationTargetId::from_name(&"CardFlip".into())
 }
 
-#[derive(Event)]
-pub struct FlipCard;
+/// Flips the card face up.
+///
+/// Flipping a card again while it is flipping restarts the animation.
+#[derive(Default, Event)]
+pub struct FlipCard {
+    /// Overrides the [`FlipSettings`] resource.
+    pub settings: Option<FlipSettings>,
+}
 
 impl FlipCard {
+    #[allow(clippy::too_many_arguments)]
     fn handle_trigger(
         trigger: Trigger<Self>,
         mut commands: Commands,
         children: Query<&Children>,
-        animation: Res<CardFlipAnimation>,
-        mut animation_player: Query<&mut AnimationPlayer>,
+        flipping: Query<&CardFlipTarget>,
+        default_settings: Res<FlipSettings>,
+        speed: Res<AnimationSpeed>,
+        mut clips: ResMut<Assets<AnimationClip>>,
+        mut graphs: ResMut<Assets<AnimationGraph>>,
     ) {
         let card_entity = trigger.entity();
         let child_entity = children.get(card_entity).unwrap()[0];
 
-        commands
-            .entity(child_entity)
-            .insert(animation.animation_target);
-        animation_player
-            .get_mut(animation.animation_target.player)
-            .unwrap()
-            .start(animation.node_idx);
-    }
-}
-
-#[derive(Clone, Event)]
-struct CardFlipStarted;
+        // Stop the flip in progress, not to animate the card twice.
+        if let Ok(prev) = flipping.get(child_entity) {
+            commands.entity(prev.player).despawn();
+        }
 
-impl CardFlipStarted {
-    fn handle_trigger(trigger: Trigger<Self>, mut commands: Commands) {
-        commands.entity(trigger.entity()).insert(CardFlipTarget);
+        let settings = trigger.settings.unwrap_or(*default_settings);
+        let duration_secs = speed
+            .scale(settings.duration_secs)
+            .max(MIN_FLIP_DURATION_SECS);
+
+        let (graph, node_idx) =
+            AnimationGraph::from_clip(clips.add(settings.animation_clip(duration_secs)));
+
+        let mut animation_player = AnimationPlayer::default();
+        animation_player.start(node_idx);
+
+        // Each flip has its own player, so that cards can flip independently.
+        let player = commands
+            .spawn((
+                StateScoped(CTX_STATE),
+                animation_player,
+                AnimationGraphHandle(graphs.add(graph)),
+                Name::new("CardFlipAnimationPlayer"),
+            ))
+            .id();
+
+        commands.entity(child_entity).insert((
+            AnimationTarget {
+                id: flip_animation_target_id(),
+                player,
+            },
+            CardFlipTarget { player },
+        ));
     }
 }
 
@@ -108,19 +128,25 @@ impl CardFlipFinished {
     fn handle_trigger(
         trigger: Trigger<Self>,
         mut commands: Commands,
-        mut transform: Query<&mut Transform>,
+        mut query: Query<(&mut Transform, &CardFlipTarget)>,
     ) {
         let entity = trigger.entity();
+        let (mut transform, target) = query.get_mut(entity).unwrap();
+
+        commands.entity(target.player).despawn();
         commands
             .entity(entity)
             .remove::<(AnimationTarget, CardFlipTarget)>();
 
-        transform.get_mut(entity).unwrap().translation.y = 0.0;
+        transform.translation.y = 0.0;
     }
 }
 
+/// The card being flipped, and the player of the animation.
 #[derive(Component)]
-struct CardFlipTarget;
+struct CardFlipTarget {
+    player: Entity,
+}
 
 impl CardFlipTarget {
     fn adjust_y(mut query: Query<&mut Transform, With<Self>>) {
@@ -1,15 +1,25 @@
-use crate::{game::CTX_STATE, AppState};
-use algo_core::card::CardNumber;
-use bevy::prelude::*;
+use crate::{
+    game::{
+        dialog::{Dialog, DialogButton},
+        CTX_STATE,
+    },
+    AppState,
+};
+use algo_core::card::{CardNumber, CardNumberType};
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 use client::utils::AddObserverExt as _;
+use std::{collections::BTreeSet, ops::RangeInclusive};
 
-const PANEL_SIZE: Vec2 = Vec2::new(360.0, 200.0);
+const PANEL_WIDTH: f32 = 360.0;
+const ROW_HEIGHT: f32 = 100.0;
 const PANEL_TRANSLATION: Vec3 = Vec3::new(0.0, -80.0, 0.0);
 
-const ITEMS_PER_ROW: u8 = 6;
-const ITEMS_PER_COL: u8 = 2;
+const ITEMS_PER_ROW: usize = 6;
 const GAP: Vec2 = Vec2::new(4.0, 4.0);
 
+const FOCUS_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+const FOCUS_FRAME_WIDTH: f32 = 4.0;
+
 pub fn card_guessing_plugin(app: &mut App) {
     app.add_sub_state::<NumSelectorState>()
         .enable_state_scoped_entities::<NumSelectorState>()
@@ -17,6 +27,24 @@ pub fn card_guessing_plugin(app: &mut App) {
         .add_systems(
             OnEnter(NumSelectorState::Selecting),
             SpawnNumSelector::setup_button_interaction,
+        )
+        .add_systems(OnEnter(NumSelectorState::Inactive), cleanup)
+        .add_systems(OnEnter(NumSelectorState::Confirming), PendingGuess::setup)
+        .add_state_scoped_observer_named(CTX_STATE, ConfirmGuess::handle_trigger)
+        .add_systems(
+            Update,
+            (
+                NumSelectorFocus::move_focus,
+                NumSelectorFocus::show_focus
+                    .run_if(resource_exists_and_changed::<NumSelectorFocus>),
+                NumSelectorFocus::select.run_if(input_just_pressed(KeyCode::Enter)),
+            )
+                .chain()
+                .run_if(in_state(NumSelectorState::Selecting)),
+        )
+        .add_systems(
+            Update,
+            PendingGuess::confirm_with_keys.run_if(in_state(NumSelectorState::Confirming)),
         );
 }
 
@@ -26,8 +54,8 @@ pub(crate) enum NumSelectorState {
     #[default]
     Inactive,
     Selecting,
-    #[allow(unused)]
-    Confirming, // TODO
+    /// A number is chosen and waits for the player to confirm it.
+    Confirming,
 }
 
 impl NumSelectorState {
@@ -37,7 +65,12 @@ impl NumSelectorState {
 }
 
 #[derive(Event)]
-pub struct SpawnNumSelector;
+pub struct SpawnNumSelector {
+    /// Numbers to show as buttons.
+    pub numbers: RangeInclusive<CardNumberType>,
+    /// Numbers to show as buttons that cannot be selected.
+    pub disabled: BTreeSet<CardNumberType>,
+}
 
 impl SpawnNumSelector {
     fn handle_trigger(
@@ -54,52 +87,72 @@ impl SpawnNumSelector {
         let card_entity = trigger.entity();
         commands.entity(card_entity).insert(NumSelectorTarget);
 
-        let gap_p = GAP / PANEL_SIZE;
+        let numbers = trigger.numbers.clone();
+        let disabled = trigger.disabled.clone();
+        let rows = numbers.len().div_ceil(ITEMS_PER_ROW).max(1);
+        let panel_size = Vec2::new(PANEL_WIDTH, ROW_HEIGHT * rows as f32);
+
+        let gap_p = GAP / panel_size;
         let button_w_plus_gap_p = (1.0 - gap_p.x) / ITEMS_PER_ROW as f32;
-        let button_h_plus_gap_p = (1.0 - gap_p.y) / ITEMS_PER_COL as f32;
+        let button_h_plus_gap_p = (1.0 - gap_p.y) / rows as f32;
         let button_size = Vec2::new(
-            PANEL_SIZE.x * (button_w_plus_gap_p - gap_p.x),
-            PANEL_SIZE.y * (button_h_plus_gap_p - gap_p.y),
+            panel_size.x * (button_w_plus_gap_p - gap_p.x),
+            panel_size.y * (button_h_plus_gap_p - gap_p.y),
         );
 
+        let mut buttons = Vec::new();
+
         commands
             .spawn((
-                StateScoped(NumSelectorState::Selecting),
-                Sprite::from_color(Color::srgba(1.0, 1.0, 1.0, 0.5), PANEL_SIZE),
+                // Kept while confirming, so that the player can go back to selecting.
+                StateScoped(CTX_STATE),
+                NumSelectorPanel,
+                Sprite::from_color(Color::srgba(1.0, 1.0, 1.0, 0.5), panel_size),
                 Transform::from_translation(PANEL_TRANSLATION),
                 Name::new("NumSelector"),
             ))
             .with_children(|parent| {
-                for row in 0..ITEMS_PER_COL {
-                    for col in 0..ITEMS_PER_ROW {
-                        let x = {
-                            let i = col as i32 - ITEMS_PER_ROW as i32 / 2;
-                            (PANEL_SIZE.x * button_w_plus_gap_p) * (i as f32 + 0.5)
-                        };
-                        let y = {
-                            let j = row as i32 - ITEMS_PER_COL as i32 / 2;
-                            -(PANEL_SIZE.y * button_h_plus_gap_p) * (j as f32 + 0.5)
-                        };
-
-                        let n = col + row * ITEMS_PER_ROW;
-
-                        parent
-                            .spawn((
-                                NumSelectorButton {
-                                    output: CardNumber(n),
-                                },
-                                Sprite::from_color(Color::srgba(0.3, 0.3, 0.3, 0.7), button_size),
-                                Transform::from_xyz(x, y, 1.0),
-                                Name::new(format!("NumSelectorButton[{col}, {row}]")),
-                            ))
-                            .with_children(|parent| {
-                                parent.spawn((
-                                    Text2d(format!("{}", n)),
-                                    TextFont::from_font_size(48.0),
-                                    Transform::from_xyz(0.0, 0.0, 2.0),
-                                ));
-                            });
+                parent.spawn((
+                    NumSelectorFocusFrame,
+                    Sprite::from_color(FOCUS_COLOR, button_size + 2.0 * FOCUS_FRAME_WIDTH),
+                    Transform::from_xyz(0.0, 0.0, 0.5),
+                    Visibility::Hidden,
+                    Name::new("NumSelectorFocusFrame"),
+                ));
+
+                for (idx, n) in CardNumber::all(numbers).enumerate() {
+                    let (row, col) = (idx / ITEMS_PER_ROW, idx % ITEMS_PER_ROW);
+
+                    let x = {
+                        let i = col as f32 - ITEMS_PER_ROW as f32 / 2.0;
+                        (panel_size.x * button_w_plus_gap_p) * (i + 0.5)
+                    };
+                    let y = {
+                        let j = row as f32 - rows as f32 / 2.0;
+                        -(panel_size.y * button_h_plus_gap_p) * (j + 0.5)
+                    };
+
+                    let is_disabled = disabled.contains(&n.0);
+                    let (bg_alpha, text_alpha) = if is_disabled { (0.2, 0.3) } else { (0.7, 1.0) };
+
+                    let mut button = parent.spawn((
+                        NumSelectorButton { output: n },
+                        Sprite::from_color(Color::srgba(0.3, 0.3, 0.3, bg_alpha), button_size),
+                        Transform::from_xyz(x, y, 1.0),
+                        Name::new(format!("NumSelectorButton[{col}, {row}]")),
+                    ));
+                    if is_disabled {
+                        button.insert(NumSelectorButtonDisabled);
                     }
+                    buttons.push((button.id(), !is_disabled));
+                    button.with_children(|parent| {
+                        parent.spawn((
+                            Text2d(format!("{}", n.0)),
+                            TextFont::from_font_size(48.0),
+                            TextColor(Color::srgba(1.0, 1.0, 1.0, text_alpha)),
+                            Transform::from_xyz(0.0, 0.0, 2.0),
+                        ));
+                    });
                 }
             });
 
@@ -112,12 +165,16 @@ impl SpawnNumSelector {
         //     card_entity,
         // );
 
+        commands.insert_resource(NumSelectorFocus {
+            buttons,
+            focused: None,
+        });
         next_state.set(NumSelectorState::Selecting);
     }
 
     fn setup_button_interaction(
         mut commands: Commands,
-        buttons: Query<Entity, With<NumSelectorButton>>,
+        buttons: Query<Entity, (With<NumSelectorButton>, Without<NumSelectorButtonDisabled>)>,
     ) {
         let observers = [
             Observer::new(NumSelectorButton::pointer_over),
@@ -137,11 +194,18 @@ impl SpawnNumSelector {
 #[derive(Component)]
 struct NumSelectorTarget;
 
+#[derive(Component)]
+struct NumSelectorPanel;
+
 #[derive(Component)]
 struct NumSelectorButton {
     output: CardNumber,
 }
 
+/// Marks a [`NumSelectorButton`] that does not respond to the pointer.
+#[derive(Component)]
+struct NumSelectorButtonDisabled;
+
 impl NumSelectorButton {
     fn pointer_over(trigger: Trigger<Pointer<Over>>, mut sprites: Query<&mut Sprite>) {
         sprites.get_mut(trigger.entity()).unwrap().color = Color::srgba(0.3, 0.3, 0.3, 1.0);
@@ -154,15 +218,182 @@ impl NumSelectorButton {
     fn pointer_click(
         trigger: Trigger<Pointer<Click>>,
         buttons: Query<&NumSelectorButton>,
+        mut next_state: ResMut<NextState<NumSelectorState>>,
+        mut commands: Commands,
+    ) {
+        let output = buttons.get(trigger.entity()).unwrap().output;
+        select(output, &mut next_state, &mut commands);
+    }
+}
+
+/// The button focused with the arrow keys.
+#[derive(Resource)]
+struct NumSelectorFocus {
+    /// Button entities in the grid order, and whether they can be selected.
+    buttons: Vec<(Entity, bool)>,
+    /// Index of the focused button, if any.
+    focused: Option<usize>,
+}
+
+#[derive(Component)]
+struct NumSelectorFocusFrame;
+
+impl NumSelectorFocus {
+    fn move_focus(keys: Res<ButtonInput<KeyCode>>, mut focus: ResMut<Self>) {
+        let step = if keys.just_pressed(KeyCode::ArrowLeft) {
+            -1
+        } else if keys.just_pressed(KeyCode::ArrowRight) {
+            1
+        } else if keys.just_pressed(KeyCode::ArrowUp) {
+            -(ITEMS_PER_ROW as isize)
+        } else if keys.just_pressed(KeyCode::ArrowDown) {
+            ITEMS_PER_ROW as isize
+        } else {
+            return;
+        };
+
+        let Some(current) = focus.focused else {
+            focus.focused = focus.buttons.iter().position(|(_, enabled)| *enabled);
+            return;
+        };
+
+        // Skip the disabled buttons, and stay put at the edges.
+        let mut idx = current as isize;
+        loop {
+            idx += step;
+            let Some((_, enabled)) = usize::try_from(idx).ok().and_then(|i| focus.buttons.get(i))
+            else {
+                return;
+            };
+            if *enabled {
+                focus.focused = Some(idx as usize);
+                return;
+            }
+        }
+    }
+
+    fn show_focus(
+        focus: Res<Self>,
+        buttons: Query<&Transform, With<NumSelectorButton>>,
+        frame: Single<
+            (&mut Transform, &mut Visibility),
+            (With<NumSelectorFocusFrame>, Without<NumSelectorButton>),
+        >,
+    ) {
+        let Some((button, _)) = focus.focused.map(|idx| focus.buttons[idx]) else {
+            return;
+        };
+
+        let (mut transform, mut visibility) = frame.into_inner();
+        let button_translation = buttons.get(button).unwrap().translation;
+        transform.translation = button_translation.with_z(transform.translation.z);
+        *visibility = Visibility::Inherited;
+    }
+
+    fn select(
+        focus: Res<Self>,
+        buttons: Query<&NumSelectorButton>,
+        mut next_state: ResMut<NextState<NumSelectorState>>,
+        mut commands: Commands,
+    ) {
+        let Some((button, _)) = focus.focused.map(|idx| focus.buttons[idx]) else {
+            return;
+        };
+
+        let output = buttons.get(button).unwrap().output;
+        select(output, &mut next_state, &mut commands);
+    }
+}
+
+/// Asks the player to confirm the number, as the attack cannot be undone.
+fn select(
+    output: CardNumber,
+    next_state: &mut NextState<NumSelectorState>,
+    commands: &mut Commands,
+) {
+    commands.insert_resource(PendingGuess(output));
+    next_state.set(NumSelectorState::Confirming);
+}
+
+/// The number chosen by the player, waiting for confirmation.
+#[derive(Resource)]
+struct PendingGuess(CardNumber);
+
+#[derive(Component)]
+struct ConfirmGuessDialog;
+
+impl PendingGuess {
+    fn setup(pending: Res<Self>, mut commands: Commands) {
+        commands.spawn((
+            StateScoped(CTX_STATE),
+            ConfirmGuessDialog,
+            Dialog::new(
+                None,
+                [
+                    DialogButton::new(
+                        format!("Guess {}", pending.0 .0),
+                        |commands| commands.trigger(ConfirmGuess(true)),
+                        default(),
+                    ),
+                    DialogButton::new(
+                        "Cancel",
+                        |commands| commands.trigger(ConfirmGuess(false)),
+                        default(),
+                    ),
+                ],
+            ),
+            Transform::from_translation(PANEL_TRANSLATION.with_z(10.0)),
+        ));
+    }
+
+    /// Enter confirms the number, and Escape cancels it.
+    fn confirm_with_keys(
+        keys: Res<ButtonInput<KeyCode>>,
+        dialog: Option<Single<Entity, With<ConfirmGuessDialog>>>,
+        mut commands: Commands,
+    ) {
+        let confirmed = if keys.just_pressed(KeyCode::Enter) {
+            true
+        } else if keys.just_pressed(KeyCode::Escape) {
+            false
+        } else {
+            return;
+        };
+
+        // Removing `Dialog` despawns the dialog.
+        if let Some(dialog) = dialog {
+            commands.entity(*dialog).remove::<Dialog>();
+        }
+        commands.trigger(ConfirmGuess(confirmed));
+    }
+}
+
+/// Answers the confirmation of the [`PendingGuess`].
+#[derive(Event)]
+struct ConfirmGuess(bool);
+
+impl ConfirmGuess {
+    fn handle_trigger(
+        trigger: Trigger<Self>,
+        pending: Option<Res<PendingGuess>>,
         target: Single<Entity, With<NumSelectorTarget>>,
         mut next_state: ResMut<NextState<NumSelectorState>>,
         mut commands: Commands,
     ) {
-        let target = *target;
+        let Some(pending) = pending else {
+            return;
+        };
+        commands.remove_resource::<PendingGuess>();
+
+        if !trigger.0 {
+            next_state.set(NumSelectorState::Selecting);
+            return;
+        }
+
         commands
-            .entity(target)
+            .entity(*target)
             .remove::<NumSelectorTarget>()
-            .trigger(NumSelected(buttons.get(trigger.entity()).unwrap().output));
+            .trigger(NumSelected(pending.0));
 
         // commands.trigger_targets(DespawnFollower, target);
 
@@ -170,5 +401,21 @@ impl NumSelectorButton {
     }
 }
 
+fn cleanup(
+    panels: Query<Entity, With<NumSelectorPanel>>,
+    dialogs: Query<Entity, With<ConfirmGuessDialog>>,
+    mut commands: Commands,
+) {
+    for panel in &panels {
+        commands.entity(panel).despawn_recursive();
+    }
+    for dialog in &dialogs {
+        commands.entity(dialog).remove::<Dialog>();
+    }
+
+    commands.remove_resource::<NumSelectorFocus>();
+    commands.remove_resource::<PendingGuess>();
+}
+
 #[derive(Debug, Clone, Event)]
 pub struct NumSelected(pub CardNumber);
@@ -6,7 +6,7 @@ use super::{
     tag::{DespawnCardTag, SpawnCardTag},
 };
 use crate::game::CTX_STATE;
-use algo_core::card::{CardPrivInfo, CardView};
+use algo_core::card::{CardColor, CardPrivInfo, CardView};
 use bevy::prelude::*;
 use client::utils::AddObserverExt as _;
 use std::f32::consts::PI;
@@ -14,6 +14,7 @@ use std::f32::consts::PI;
 pub fn card_instance_plugin(app: &mut App) {
     app.add_systems(Update, CardInstance::on_change.run_if(in_state(CTX_STATE)))
         .add_state_scoped_observer_named(CTX_STATE, CardInstance::init)
+        .add_state_scoped_observer_named(CTX_STATE, SetColor::handle_trigger)
         .add_state_scoped_observer_named(CTX_STATE, AddPrivInfo::handle_trigger)
         .add_state_scoped_observer_named(CTX_STATE, Reveal::handle_trigger)
         .add_state_scoped_observer_named(CTX_STATE, RevealWith::handle_trigger)
@@ -110,7 +111,7 @@ impl CardInstance {
         card_view: &CardView,
     ) -> impl Bundle {
         let color = card_view.pub_info.color;
-        let number = card_view.priv_info.map(|v| v.number);
+        let number = card_view.number_known();
 
         let material_handle =
             card_materials.get_or_create_card_material(color, number, images, materials);
@@ -170,6 +171,41 @@ impl ComputedCardInstState {
     }
 }
 
+/// Corrects the color of a card spawned from a talon view without the drawing order.
+///
+/// Does nothing if the color is already correct.
+#[derive(Clone, Event)]
+pub struct SetColor(pub CardColor);
+
+impl SetColor {
+    fn handle_trigger(
+        trigger: Trigger<Self>,
+        mut query: Query<&mut CardInstance>,
+        mut commands: Commands,
+    ) {
+        let entity = trigger.entity();
+        let mut card_instance = query.get_mut(entity).unwrap();
+        let color = trigger.event().0;
+
+        if card_instance.0.pub_info.color == color {
+            return;
+        }
+
+        if !ComputedCardInstState::compute(&card_instance.0)
+            .unwrap()
+            .is_spawned()
+        {
+            warn!("invalid card instance state for an event `SetColor`");
+            return;
+        }
+
+        let card = &mut card_instance.0;
+        card.pub_info.color = color;
+
+        commands.trigger_targets(UpdateMaterial(*card), entity);
+    }
+}
+
 /// `Spawned` --> `Private`
 #[derive(Clone, Event)]
 pub struct AddPrivInfo(pub CardPrivInfo);
@@ -214,7 +250,7 @@ impl Reveal {
 
         card.pub_info.revealed = true;
 
-        commands.trigger_targets(FlipCard, entity);
+        commands.trigger_targets(FlipCard::default(), entity);
     }
 }
 
@@ -242,7 +278,7 @@ impl RevealWith {
         commands
             .entity(entity)
             .trigger(UpdateMaterial(*card))
-            .trigger(FlipCard);
+            .trigger(FlipCard::default());
     }
 }
 
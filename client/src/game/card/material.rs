@@ -1,14 +1,20 @@
-use algo_core::card::{CardColor, CardNumber};
+use algo_core::{
+    card::{CardColor, CardNumber, CardRgb},
+    settings::GameSettings,
+};
 use bevy::{
     asset::RenderAssetUsages,
+    log::{debug, warn},
     pbr::StandardMaterial,
-    prelude::{App, Assets, Commands, Font, Handle, Image, ResMut, Resource, Startup, TextFont},
+    prelude::{
+        App, Assets, Commands, Font, Handle, Image, Res, ResMut, Resource, Startup, TextFont,
+    },
     render::render_resource::{Extent3d, TextureDimension, TextureFormat},
 };
 use client::utils::into_color::IntoColor as _;
 use fontdue::layout::{CoordinateSystem, GlyphPosition, Layout, TextStyle};
 use image::{Rgb, RgbaImage};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fs, path::PathBuf};
 
 const CARD_IMG_PX_WIDTH: u32 = 500;
 const CARD_IMG_PX_HEIGHT: u32 = 809;
@@ -17,24 +23,68 @@ const FONT_SIZE: f32 = 512.0;
 /// The thickness of lines to be drawn under the numbers 6 or 9.
 const UNDERLINE_PX_HEIGHT: u32 = 30;
 
+/// The ratio of the card width that a shrunk number occupies.
+const SHRUNK_TEXT_WIDTH_RATIO: f32 = 0.9;
+
 pub fn card_material_plugin(app: &mut App) {
-    app.add_systems(Startup, setup_resource);
+    app.init_resource::<CardPalette>()
+        .add_systems(Startup, setup_resource);
 }
 
-fn setup_resource(mut commands: Commands, font_assets: ResMut<Assets<Font>>) {
+fn setup_resource(
+    mut commands: Commands,
+    font_assets: ResMut<Assets<Font>>,
+    cache: Option<Res<CardTextureCache>>,
+    palette: Res<CardPalette>,
+) {
     let id = TextFont::default().font.id();
 
     let Some(font) = font_assets.get(id) else {
         panic!("could not access font asset");
     };
 
-    let Ok(res) = CardMaterials::from_bytes(&font.data[..]) else {
+    let Ok(mut res) = CardMaterials::from_bytes(&font.data[..]) else {
         panic!("failed to read font data");
     };
+    res.cache_dir = cache.map(|v| v.0.clone());
+    res.palette = *palette;
 
     commands.insert_resource(res)
 }
 
+/// The directory to store the generated card textures in,
+/// so that they can be reused by later launches.
+#[derive(Debug, Clone, Resource)]
+pub struct CardTextureCache(pub PathBuf);
+
+/// The colors to paint the cards with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum CardPalette {
+    /// The colors given by the game, see [`GameSettings::palette`].
+    #[default]
+    Standard,
+    /// Tints the colors with distinct hues, so that they are told apart by more than brightness.
+    Colorblind,
+}
+
+impl CardPalette {
+    /// Returns how the cards of the color are painted in the game.
+    pub fn card_rgb(self, settings: &GameSettings, color: CardColor) -> CardRgb {
+        let bg = match (self, color) {
+            (Self::Standard, _) => return settings.card_rgb(color),
+            // Taken from the Okabe-Ito palette, which stays distinct under color vision deficiency.
+            (Self::Colorblind, CardColor::Black) => [0, 45, 110],
+            (Self::Colorblind, CardColor::White) => [250, 238, 170],
+            (Self::Colorblind, CardColor::Red) => [213, 94, 0],
+            (Self::Colorblind, CardColor::Green) => [0, 158, 115],
+        };
+        CardRgb {
+            bg,
+            ..color.default_rgb()
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct CardMaterials {
     font: fontdue::Font,
@@ -43,6 +93,10 @@ pub struct CardMaterials {
         (CardColor, Option<CardNumber>),
         (Handle<StandardMaterial>, Option<Handle<Image>>),
     >,
+    cache_dir: Option<PathBuf>,
+    palette: CardPalette,
+    /// The settings of the current game, whose palette the cards are painted with.
+    settings: GameSettings,
 }
 
 impl CardMaterials {
@@ -51,9 +105,25 @@ impl CardMaterials {
             font,
             font_size: FONT_SIZE,
             handles: BTreeMap::default(),
+            cache_dir: None,
+            palette: CardPalette::default(),
+            settings: GameSettings::default(),
         })
     }
 
+    /// Paints the cards with the palette of the game from now on.
+    pub fn use_game_settings(&mut self, settings: &GameSettings) {
+        if settings.palette != self.settings.palette {
+            self.handles.clear();
+        }
+        self.settings.clone_from(settings);
+    }
+
+    /// Returns how the cards of the color are painted.
+    pub fn card_rgb(&self, color: CardColor) -> CardRgb {
+        self.palette.card_rgb(&self.settings, color)
+    }
+
     pub fn get_or_create_card_material(
         &mut self,
         color: CardColor,
@@ -66,10 +136,10 @@ impl CardMaterials {
         }
 
         // Create a new material
-        let img = number.map(|n| into_bevy_image(self.generate_card_inner(color, n)));
+        let img = number.map(|n| into_bevy_image(self.load_or_generate_card(color, n)));
 
         let base_color = if img.is_none() {
-            color.bg_color_rgb().into_color()
+            self.card_rgb(color).bg.into_color()
         } else {
             bevy::color::Color::WHITE
         };
@@ -100,16 +170,73 @@ impl CardMaterials {
         self.handles.get(&(color, number)).unwrap().1.clone()
     }
 
-    // FIXME: Number 88 exceeds `CARD_WIDTH`.
+    /// Loads the card image from the cache directory if available,
+    /// or generates and stores it otherwise.
+    fn load_or_generate_card(&self, color: CardColor, number: CardNumber) -> RgbaImage {
+        let Some(path) = self.cache_path(color, number) else {
+            return self.generate_card_inner(color, number);
+        };
+
+        let cached = fs::read(&path)
+            .ok()
+            .and_then(|v| RgbaImage::from_raw(CARD_IMG_PX_WIDTH, CARD_IMG_PX_HEIGHT, v));
+        if let Some(img) = cached {
+            debug!("loaded card texture from {:?}", path);
+            return img;
+        }
+
+        let img = self.generate_card_inner(color, number);
+
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, img.as_raw()));
+        match written {
+            Ok(_) => debug!("stored card texture to {:?}", path),
+            Err(e) => warn!("failed to store card texture to {:?}: {}", path, e),
+        }
+
+        img
+    }
+
+    /// Returns the path to the cached image, which changes with the font and the colors.
+    fn cache_path(&self, color: CardColor, number: CardNumber) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        let CardRgb { bg, text } = self.card_rgb(color);
+        let file_name = format!(
+            "{:02x}{:02x}{:02x}-{:02x}{:02x}{:02x}-{}-{:016x}.rgba",
+            bg[0],
+            bg[1],
+            bg[2],
+            text[0],
+            text[1],
+            text[2],
+            number.0,
+            self.font.file_hash()
+        );
+        Some(dir.join(file_name))
+    }
+
     fn generate_card_inner(&self, color: CardColor, number: CardNumber) -> RgbaImage {
         // Create an image buffer
-        let bg_color = color.bg_color_rgb().into();
+        let rgb = self.card_rgb(color);
+        let bg_color = rgb.bg.into();
         let card_width = CARD_IMG_PX_WIDTH;
         let card_height = CARD_IMG_PX_HEIGHT;
         let mut img_buf = filled_rgba_img_buf(card_width, card_height, bg_color);
 
         // Setup text processor
-        let text_processor = self.setup_process_text(number.0.to_string());
+        let text = number.0.to_string();
+        let mut text_processor = self.setup_process_text(&text, self.font_size);
+        let (text_width, text_height) = text_processor.img_size();
+
+        // Shrink the text if it does not fit in the card.
+        if text_width > card_width || text_height > card_height {
+            let scale = (card_width as f32 / text_width as f32)
+                .min(card_height as f32 / text_height as f32)
+                * SHRUNK_TEXT_WIDTH_RATIO;
+            text_processor = self.setup_process_text(&text, self.font_size * scale);
+        }
         let (text_width, text_height) = text_processor.img_size();
 
         // Verify text size
@@ -117,7 +244,7 @@ impl CardMaterials {
             // Draw text
             let x_offset = (card_width - text_width) / 2;
             let y_offset = (card_height - text_height) / 2;
-            let text_color = color.text_color_rgb().into();
+            let text_color = rgb.text.into();
 
             let mut draw_method =
                 common_draw_method(&mut img_buf, card_width, card_height, bg_color, text_color);
@@ -146,12 +273,9 @@ impl CardMaterials {
             .expect("image buffer size should be correct")
     }
 
-    fn setup_process_text(&self, text: impl AsRef<str>) -> TextProcessor<'_> {
+    fn setup_process_text(&self, text: impl AsRef<str>, font_size: f32) -> TextProcessor<'_> {
         let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
-        layout.append(
-            &[&self.font],
-            &TextStyle::new(text.as_ref(), self.font_size, 0),
-        );
+        layout.append(&[&self.font], &TextStyle::new(text.as_ref(), font_size, 0));
 
         let glyphs = layout.glyphs();
         let glyphs_bb = GlyphsBB::calculate(glyphs);
@@ -171,7 +295,7 @@ impl CardMaterials {
         text_color: Rgb<u8>,
     ) -> RgbaImage {
         // Prepare text processor
-        let text_processor = self.setup_process_text(text);
+        let text_processor = self.setup_process_text(text, self.font_size);
 
         // Create an image buffer
         let (img_width, img_height) = text_processor.img_size();
@@ -207,7 +331,7 @@ impl CardMaterials {
 
     #[allow(unused)]
     fn get_text_size(&self, text: impl AsRef<str>) -> (u32, u32) {
-        self.setup_process_text(text).img_size()
+        self.setup_process_text(text, self.font_size).img_size()
     }
 }
 
@@ -223,8 +347,7 @@ impl TextProcessor<'_> {
     }
 
     fn draw_text(&self, mut f: impl FnMut(usize, usize, u8)) {
-        let y_min = self.glyphs_bb.y_min;
-        let mut next_x0 = 0;
+        let GlyphsBB { x_min, y_min, .. } = self.glyphs_bb;
 
         for g in self.layout.glyphs() {
             let (metrics, char_data) = self.font.rasterize_config(g.key);
@@ -232,9 +355,10 @@ impl TextProcessor<'_> {
             let char_width = metrics.width;
             let char_height = metrics.height;
 
-            let y0 = g.y as usize - y_min as usize;
-            let x0 = next_x0;
-            next_x0 += char_width;
+            // Flooring keeps the glyph inside the bounding box,
+            // whose size is rounded up.
+            let x0 = (g.x - x_min).floor().max(0.0) as usize;
+            let y0 = (g.y - y_min).floor().max(0.0) as usize;
 
             for y_offset in 0..char_height {
                 for x_offset in 0..char_width {
@@ -250,45 +374,43 @@ impl TextProcessor<'_> {
 /// Glyphs' Bounding Box
 #[derive(Debug, Clone, Copy)]
 struct GlyphsBB {
-    _x_min: u32,
-    y_min: u32,
+    x_min: f32,
+    y_min: f32,
     width: u32,
     height: u32,
 }
 
 impl GlyphsBB {
+    /// Calculates the box from the glyphs' laid out positions,
+    /// so that the advance and kerning between them are kept.
     fn calculate<'a>(glyphs: impl IntoIterator<Item = &'a GlyphPosition>) -> Self {
-        let mut x_min = u32::MAX;
-        let mut y_min = u32::MAX;
-        let mut x_max = 0;
-        let mut y_max = 0;
-
-        let mut prev_x2 = None;
-        let mut x_gaps = 0;
-
-        for glyph in glyphs {
-            let x1 = glyph.x as u32;
-            let y1 = glyph.y as u32;
-            let x2 = x1 + glyph.width as u32;
-            let y2 = y1 + glyph.height as u32;
-
-            if let Some(prev_x2) = prev_x2 {
-                let gap = x1 - prev_x2;
-                x_gaps += gap;
-            }
-            prev_x2 = Some(x2);
+        Self::from_rects(
+            glyphs
+                .into_iter()
+                .map(|g| (g.x, g.y, g.width as f32, g.height as f32)),
+        )
+    }
 
-            x_min = x_min.min(x1);
-            y_min = y_min.min(y1);
-            x_max = x_max.max(x2);
-            y_max = y_max.max(y2);
+    /// Calculates the box enclosing the rectangles, given as `(x, y, width, height)`.
+    fn from_rects(rects: impl IntoIterator<Item = (f32, f32, f32, f32)>) -> Self {
+        let mut x_min = f32::MAX;
+        let mut y_min = f32::MAX;
+        let mut x_max = f32::MIN;
+        let mut y_max = f32::MIN;
+
+        for (x, y, width, height) in rects {
+            x_min = x_min.min(x);
+            y_min = y_min.min(y);
+            x_max = x_max.max(x + width);
+            y_max = y_max.max(y + height);
         }
 
-        let width = x_max - x_min + 1 - x_gaps;
-        let height = y_max - y_min + 1;
+        // Empty if there are no glyphs.
+        let width = (x_max - x_min).ceil().max(0.0) as u32;
+        let height = (y_max - y_min).ceil().max(0.0) as u32;
 
         Self {
-            _x_min: x_min,
+            x_min,
             y_min,
             width,
             height,
@@ -382,3 +504,38 @@ fn into_bevy_image(image: RgbaImage) -> Image {
         RenderAssetUsages::all(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_digit_bb_covers_all_glyphs() {
+        // Two digits laid out side by side, as in "10", with the second one sitting lower.
+        let glyphs = [(3.0, 0.0, 180.0, 400.0), (205.5, 12.0, 200.0, 388.0)];
+
+        let bb = GlyphsBB::from_rects(glyphs);
+        let sum_of_widths = glyphs.iter().map(|g| g.2).sum::<f32>();
+
+        assert!(bb.width as f32 >= sum_of_widths);
+        assert_eq!(bb.width, 403);
+        assert_eq!(bb.height, 400);
+        assert_eq!((bb.x_min, bb.y_min), (3.0, 0.0));
+    }
+
+    #[test]
+    fn overlapping_glyphs_do_not_underflow() {
+        // Kerning may pull a glyph over the previous one, and the order is not guaranteed.
+        let bb = GlyphsBB::from_rects([(150.0, 0.0, 200.0, 400.0), (0.0, 0.0, 200.0, 400.0)]);
+
+        assert_eq!(bb.width, 350);
+        assert_eq!(bb.height, 400);
+    }
+
+    #[test]
+    fn no_glyphs_make_empty_bb() {
+        let bb = GlyphsBB::from_rects([]);
+
+        assert_eq!((bb.width, bb.height), (0, 0));
+    }
+}
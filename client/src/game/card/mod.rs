@@ -4,6 +4,8 @@ use bevy::{
 };
 
 pub mod attacker;
+#[cfg(feature = "dev")]
+pub mod dev_tooltip;
 pub mod effects;
 pub mod flip_animation;
 pub mod guessing;
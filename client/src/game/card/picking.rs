@@ -2,7 +2,7 @@ use crate::game::CTX_STATE;
 use bevy::prelude::*;
 use bevy_mod_outline::{OutlineMode, OutlinePlugin, OutlineVolume};
 use client::utils::{
-    observer_controller::{self, ObserverControllerPlugin},
+    observer_controller::{self, ObserverControllerPlugin, ObserverControllerSettings},
     AddObserverExt as _,
 };
 
@@ -10,12 +10,35 @@ pub fn card_picking_plugin(app: &mut App) {
     app.add_plugins((
         MeshPickingPlugin,
         OutlinePlugin,
-        ObserverControllerPlugin::<Pointer<Click>>::default().state_scoped(CTX_STATE),
+        // A card is clicked at most once per selection,
+        // and its observer is activated again for the next one.
+        ObserverControllerPlugin::<Pointer<Click>>::new(ObserverControllerSettings {
+            pause_after_first: true,
+            ..default()
+        })
+        .state_scoped(CTX_STATE),
     ))
     .add_state_scoped_observer_named(CTX_STATE, PickableCard::init)
     .add_state_scoped_observer_named(CTX_STATE, PickableCard::cleanup);
 }
 
+/// Set once a card is picked, until cards become pickable again for the next selection.
+///
+/// Click handlers check this so that a double click cannot select twice.
+#[derive(Resource)]
+pub struct InteractionLocked;
+
+impl InteractionLocked {
+    /// Locks the interaction, returning `false` if it is already locked.
+    pub fn try_lock(locked: &Option<Res<Self>>, commands: &mut Commands) -> bool {
+        if locked.is_some() {
+            return false;
+        }
+        commands.insert_resource(Self);
+        true
+    }
+}
+
 #[derive(Component)]
 struct PickingObservers {
     over: Entity,
@@ -40,6 +63,7 @@ impl PickableCard {
         commands
             .entity(child)
             .insert((PickableCard__, PickingObservers { over, out }));
+        commands.remove_resource::<InteractionLocked>();
 
         commands.trigger_targets(
             observer_controller::Activate::<Pointer<Click>>::new(),
@@ -1,25 +1,55 @@
 use crate::AppState;
 use algo_core::player::PlayerId;
+use anyhow::{bail, ensure};
 use bevy::prelude::*;
-use client::utils::{animate_once::AnimateOnce, AddObserverExt as _};
+use client::utils::{
+    animate_once::{AnimateOnce, AnimateTransform},
+    AddObserverExt as _,
+};
 
-use super::CARD_WIDTH_PLUS_GAP;
+use super::{CARD_DEPTH, CARD_WIDTH, CARD_WIDTH_PLUS_GAP};
 
 const CARD_INSERTION_ANIMATION_SECS: f32 = 0.5;
 
+/// The width above which the cards are spaced more tightly, so that a large field stays in view.
+const DEFAULT_FIELD_MAX_WIDTH: f32 = CARD_WIDTH_PLUS_GAP * 10.0;
+
 pub fn card_field_plugin(app: &mut App) {
     app.add_state_scoped_observer_named(AppState::Game, CardPosition::init)
-        .add_state_scoped_observer_named(AppState::Game, CardPosition::shift);
+        .add_state_scoped_observer_named(AppState::Game, CardPosition::shift)
+        .add_state_scoped_observer_named(AppState::Game, CardPosition::relocate);
 }
 
-#[derive(Debug, Default, Component)]
+#[derive(Debug, Component)]
 #[require(Transform)]
 pub struct CardField {
     cards: Vec<Entity>,
+    /// The card inserted most recently, until the order of the field is applied.
+    last_inserted: Option<Entity>,
+    /// The width that the cards are laid out within, from the left edge to the right edge.
+    max_width: f32,
+}
+
+impl Default for CardField {
+    fn default() -> Self {
+        Self {
+            cards: Vec::new(),
+            last_inserted: None,
+            max_width: DEFAULT_FIELD_MAX_WIDTH,
+        }
+    }
 }
 
 impl CardField {
+    /// Sets the width above which the cards overlap, instead of running off the screen.
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = max_width.max(CARD_WIDTH);
+        self
+    }
+
     /// Inserts a pre-existing card into the field.
+    ///
+    /// The cards after `idx` slide aside to make room.
     pub fn insert_card(
         &mut self,
         self_entity: Entity,
@@ -38,11 +68,96 @@ impl CardField {
         });
 
         self.cards.insert(idx as usize, entity);
+        self.last_inserted = Some(entity);
+    }
+
+    /// Lays out the cards in the order given by the server after the last insertion.
+    ///
+    /// `new_order[i]` is the index before the insertion of the card to place at `i`,
+    /// where the inserted card is counted as if it had been appended to the end.
+    /// The cards already in place stay still.
+    pub fn apply_order(
+        &mut self,
+        self_entity: Entity,
+        new_order: &[u32],
+        commands: &mut Commands,
+    ) -> anyhow::Result<()> {
+        let Some(inserted) = self.last_inserted.take() else {
+            bail!("no card has been inserted");
+        };
+
+        let len = self.cards.len();
+        let mut sorted = new_order.to_vec();
+        sorted.sort_unstable();
+        ensure!(
+            sorted.into_iter().eq(0..len as u32),
+            "not an order of {} cards: {:?}",
+            len,
+            new_order
+        );
+
+        let mut before = self.cards.clone();
+        before.retain(|v| *v != inserted);
+        before.push(inserted);
+
+        let cards = new_order
+            .iter()
+            .map(|i| before[*i as usize])
+            .collect::<Vec<_>>();
+
+        for (idx, (old, new)) in self.cards.iter().zip(&cards).enumerate() {
+            if old != new {
+                commands.trigger_targets(
+                    CardRelocated {
+                        origin: self_entity,
+                        idx: idx as u32,
+                        len: len as u32,
+                    },
+                    *new,
+                );
+            }
+        }
+        self.cards = cards;
+
+        Ok(())
     }
 
     pub fn cards(&self) -> &[Entity] {
         &self.cards
     }
+
+    /// Returns the width taken by the cards, including a gap on either side.
+    pub fn width(&self) -> f32 {
+        let len = self.cards.len().max(1) as u32;
+        (len - 1) as f32 * self.spacing(len) + CARD_WIDTH_PLUS_GAP
+    }
+
+    /// Returns the distance between the centers of adjacent cards, when there are `len` of them.
+    fn spacing(&self, len: u32) -> f32 {
+        if len <= 1 {
+            return CARD_WIDTH_PLUS_GAP;
+        }
+        CARD_WIDTH_PLUS_GAP.min((self.max_width - CARD_WIDTH) / (len - 1) as f32)
+    }
+
+    /// Returns where the `idx`-th of `len` cards is placed,
+    /// relative to the field at `origin`.
+    fn card_translation(&self, origin: Transform, idx: u32, len: u32) -> Vec3 {
+        let spacing = self.spacing(len);
+
+        let j = idx as i32 - len as i32 / 2;
+        let offset = if len % 2 == 0 { 0.5 } else { 0.0 };
+        let distance = (j as f32 + offset) * spacing;
+
+        // Overlapping cards are stacked from the left, so that each one lies on the previous one.
+        let height = if spacing < CARD_WIDTH {
+            idx as f32 * CARD_DEPTH
+        } else {
+            0.0
+        };
+
+        origin.translation + distance * origin.right() + height * origin.up()
+    }
 }
 
 #[derive(Debug, Component)]
@@ -72,17 +187,17 @@ impl CardPosition {
         trigger: Trigger<OnAdd, Self>,
         mut commands: Commands,
         mut query: Query<(&Self, &Transform)>,
-        transform_query: Query<&Transform>,
+        fields: Query<(&Transform, &CardField)>,
     ) {
         let entity = trigger.entity();
         let (Self { origin, idx, len }, transform) = query.get_mut(entity).unwrap();
-        let origin_xf = transform_query.get(*origin).unwrap();
+        let (origin_xf, field) = fields.get(*origin).unwrap();
 
         // Translation
         let animation = AnimateOnce::translation_and_rotation(
             *transform,
             Transform {
-                translation: calculate_card_translation(*origin_xf, *idx, *len),
+                translation: field.card_translation(*origin_xf, *idx, *len),
                 rotation: transform.rotation * origin_xf.rotation,
                 ..*transform
             },
@@ -92,29 +207,60 @@ impl CardPosition {
         commands.trigger_targets(animation, entity);
     }
 
+    /// Slides the card to its new place, as another card is inserted.
+    ///
+    /// The slide starts from wherever the card is when the animation begins,
+    /// so that a card still moving from an earlier insertion is redirected smoothly
+    /// when several cards are inserted in a row.
     fn shift(
         trigger: Trigger<OtherCardInserted>,
         mut commands: Commands,
-        mut query: Query<(&Transform, &mut Self)>,
-        origin_transform: Query<&Transform, With<CardField>>,
+        mut query: Query<&mut Self>,
+        fields: Query<(&Transform, &CardField)>,
     ) {
         let entity = trigger.entity();
-        let (xf, mut card_pos) = query.get_mut(entity).unwrap();
-        let origin_xf = origin_transform.get(card_pos.origin).unwrap();
+        let mut card_pos = query.get_mut(entity).unwrap();
+        let (origin_xf, field) = fields.get(card_pos.origin).unwrap();
 
         card_pos.sync_idx_for_insertion(trigger.idx);
+        card_pos.slide(entity, *origin_xf, field, &mut commands);
+    }
 
-        let new_translation = calculate_card_translation(*origin_xf, card_pos.idx, card_pos.len);
-        let animation = AnimateOnce::translation_and_rotation(
-            *xf,
-            Transform {
-                translation: new_translation,
-                ..*origin_xf
-            },
-            CARD_INSERTION_ANIMATION_SECS,
-            EaseFunction::QuarticOut,
+    /// Slides the card to the place given by the server.
+    fn relocate(
+        trigger: Trigger<CardRelocated>,
+        mut commands: Commands,
+        mut query: Query<&mut Self>,
+        fields: Query<(&Transform, &CardField)>,
+    ) {
+        let entity = trigger.entity();
+        let &CardRelocated { origin, idx, len } = trigger.event();
+        let (origin_xf, field) = fields.get(origin).unwrap();
+
+        let mut card_pos = query.get_mut(entity).unwrap();
+        *card_pos = Self { origin, idx, len };
+        card_pos.slide(entity, *origin_xf, field, &mut commands);
+    }
+
+    fn slide(
+        &self,
+        entity: Entity,
+        origin_xf: Transform,
+        field: &CardField,
+        commands: &mut Commands,
+    ) {
+        let new_translation = field.card_translation(origin_xf, self.idx, self.len);
+        commands.trigger_targets(
+            AnimateTransform::new(
+                Transform {
+                    translation: new_translation,
+                    ..origin_xf
+                },
+                CARD_INSERTION_ANIMATION_SECS,
+                EaseFunction::QuarticOut,
+            ),
+            entity,
         );
-        commands.trigger_targets(animation, entity);
     }
 
     fn sync_idx_for_insertion(&mut self, inserted_at: u32) {
@@ -130,10 +276,9 @@ struct OtherCardInserted {
     idx: u32,
 }
 
-fn calculate_card_translation(origin: Transform, idx: u32, len: u32) -> Vec3 {
-    let j = idx as i32 - len as i32 / 2;
-    let offset = if len % 2 == 0 { 0.5 } else { 0.0 };
-    let distance = (j as f32 + offset) * CARD_WIDTH_PLUS_GAP;
-
-    origin.translation + distance * origin.right()
+#[derive(Debug, Event)]
+struct CardRelocated {
+    origin: Entity,
+    idx: u32,
+    len: u32,
 }
@@ -2,15 +2,29 @@ use super::CTX_STATE;
 use bevy::prelude::*;
 use client::utils::{set_timeout::SetTimeout, AddObserverExt};
 
+const KEY_PRESS_FOCUSED: KeyCode = KeyCode::Enter;
+const KEY_FOCUS_PREV: KeyCode = KeyCode::ArrowLeft;
+const KEY_FOCUS_NEXT: KeyCode = KeyCode::ArrowRight;
+
 pub fn dialog_plugin(app: &mut App) {
     app.add_state_scoped_observer_named(CTX_STATE, Dialog::on_insert)
-        .add_state_scoped_observer_named(CTX_STATE, Dialog::on_remove);
+        .add_state_scoped_observer_named(CTX_STATE, Dialog::on_remove)
+        .add_systems(
+            Update,
+            Dialog::handle_keys.run_if(in_state(CTX_STATE).and(dialog_takes_keys)),
+        );
+}
+
+/// Whether a dialog is waiting for keyboard input, so that other key bindings should step aside.
+pub fn dialog_takes_keys(dialogs: Query<&Dialog>) -> bool {
+    dialogs.iter().any(Dialog::takes_keys)
 }
 
 pub struct DialogButton {
     title: String,
     action: Option<Box<dyn FnOnce(&mut Commands) + Send + Sync + 'static>>,
     style: DialogButtonStyle,
+    hotkey: Option<KeyCode>,
 }
 
 impl DialogButton {
@@ -23,8 +37,15 @@ impl DialogButton {
             title: title.into(),
             action: Some(Box::new(action)),
             style,
+            hotkey: None,
         }
     }
+
+    /// Presses the button with the key, as well as with a click.
+    pub fn with_hotkey(mut self, hotkey: KeyCode) -> Self {
+        self.hotkey = Some(hotkey);
+        self
+    }
 }
 
 #[derive(Debug, Clone, Component)]
@@ -36,6 +57,19 @@ pub struct DialogButtonStyle {
     pub size: Vec2,
 }
 
+impl DialogButtonStyle {
+    fn paint(&self, sprite: &mut Sprite, text_color: &mut TextColor, highlighted: bool) {
+        (sprite.color, text_color.0) = if highlighted {
+            (
+                self.bg_color_on_hover.unwrap_or(self.bg_color),
+                self.text_color_on_hover.unwrap_or(self.text_color),
+            )
+        } else {
+            (self.bg_color, self.text_color)
+        };
+    }
+}
+
 impl Default for DialogButtonStyle {
     fn default() -> Self {
         Self {
@@ -55,6 +89,8 @@ pub struct Dialog {
     _title: Option<String>,
     buttons: Vec<DialogButton>,
     gap: Vec2,
+    /// The button pressed with Enter.
+    focus: Option<u32>,
 }
 
 impl Dialog {
@@ -63,9 +99,23 @@ impl Dialog {
             _title: title,
             buttons: buttons.into_iter().collect(),
             gap: Vec2::new(10.0, 10.0),
+            focus: None,
         }
     }
 
+    /// Focuses the button at `idx` first, so that Enter presses it.
+    ///
+    /// The arrow keys move the focus to the other buttons.
+    pub fn with_default(mut self, idx: usize) -> Self {
+        assert!(idx < self.buttons.len(), "no button at {}", idx);
+        self.focus = Some(idx as u32);
+        self
+    }
+
+    fn takes_keys(&self) -> bool {
+        self.focus.is_some() || self.buttons.iter().any(|button| button.hotkey.is_some())
+    }
+
     fn on_insert(trigger: Trigger<OnInsert, Self>, mut commands: Commands, dialog: Query<&Dialog>) {
         let entity = trigger.entity();
         let dialog = dialog.get(entity).unwrap();
@@ -93,16 +143,25 @@ impl Dialog {
                 let mut x = -size.x / 2.0 + x_gap;
 
                 for (i, button) in dialog.buttons.iter().enumerate() {
-                    parent
-                        .spawn((
-                            Sprite::from_color(button.style.bg_color, button.style.size),
-                            Transform::from_xyz(x + button.style.size.x / 2.0, 0.0, 1.0),
-                            button.style.clone(),
-                            DialogButtonIndex(i as u32),
-                        ))
+                    let mut sprite = Sprite::from_color(button.style.bg_color, button.style.size);
+                    let mut text_color = TextColor::default();
+                    let focused = dialog.focus == Some(i as u32);
+                    button.style.paint(&mut sprite, &mut text_color, focused);
+
+                    let mut button_entity = parent.spawn((
+                        sprite,
+                        Transform::from_xyz(x + button.style.size.x / 2.0, 0.0, 1.0),
+                        button.style.clone(),
+                        DialogButtonIndex(i as u32),
+                    ));
+                    if focused {
+                        button_entity.insert(DialogButtonFocused);
+                    }
+                    button_entity
                         .with_child((
                             Text2d(button.title.clone()),
                             TextFont::from_font_size(32.0),
+                            text_color,
                             Transform::from_xyz(0.0, 0.0, 1.0),
                         ))
                         .observe(button_pointer_over)
@@ -136,6 +195,67 @@ impl Dialog {
         // .remove::<(Sprite, DialogButtonPressObservedBy)>();
     }
 
+    fn handle_keys(
+        keys: Res<ButtonInput<KeyCode>>,
+        mut dialogs: Query<(Entity, &mut Dialog, &Children)>,
+        mut buttons: Query<(
+            Entity,
+            &DialogButtonIndex,
+            &DialogButtonStyle,
+            &mut Sprite,
+            &Children,
+        )>,
+        mut text_colors: Query<&mut TextColor>,
+        mut commands: Commands,
+    ) {
+        for (entity, mut dialog, children) in &mut dialogs {
+            let hotkey_pressed = dialog.buttons.iter().position(|button| {
+                button
+                    .hotkey
+                    .is_some_and(|hotkey| keys.just_pressed(hotkey))
+            });
+            if let Some(idx) = hotkey_pressed {
+                commands
+                    .entity(entity)
+                    .trigger(DialogButtonPressed { idx: idx as u32 });
+                continue;
+            }
+
+            let Some(focus) = dialog.focus else {
+                continue;
+            };
+            if keys.just_pressed(KEY_PRESS_FOCUSED) {
+                commands
+                    .entity(entity)
+                    .trigger(DialogButtonPressed { idx: focus });
+                continue;
+            }
+
+            let len = dialog.buttons.len() as u32;
+            let new_focus = if keys.just_pressed(KEY_FOCUS_PREV) {
+                (focus + len - 1) % len
+            } else if keys.just_pressed(KEY_FOCUS_NEXT) {
+                (focus + 1) % len
+            } else {
+                continue;
+            };
+            dialog.focus = Some(new_focus);
+
+            let mut own_buttons = buttons.iter_many_mut(children.iter());
+            while let Some((button, idx, style, mut sprite, children)) = own_buttons.fetch_next() {
+                let focused = idx.0 == new_focus;
+                if focused {
+                    commands.entity(button).insert(DialogButtonFocused);
+                } else {
+                    commands.entity(button).remove::<DialogButtonFocused>();
+                }
+                if let Ok(mut text_color) = text_colors.get_mut(children[0]) {
+                    style.paint(&mut sprite, &mut text_color, focused);
+                }
+            }
+        }
+    }
+
     fn calculate_size(&self) -> Vec2 {
         self.buttons
             .iter()
@@ -149,6 +269,10 @@ impl Dialog {
 #[derive(Component)]
 struct DialogButtonIndex(u32);
 
+/// Marks the button pressed with Enter, which stays highlighted.
+#[derive(Component)]
+struct DialogButtonFocused;
+
 #[derive(Component)]
 struct DialogButtonPressed {
     idx: u32,
@@ -169,9 +293,13 @@ impl DialogButtonPressed {
         let idx = trigger.event().idx;
         let mut dialog = query.get_mut(entity).unwrap();
 
+        // A key and a click may both press a button in the same frame.
+        let Some(action) = dialog.buttons[idx as usize].action.take() else {
+            return;
+        };
         commands.entity(entity).remove::<Dialog>();
 
-        (dialog.buttons[idx as usize].action.take().unwrap())(&mut commands);
+        action(&mut commands);
     }
 }
 
@@ -186,24 +314,32 @@ fn button_pointer_over(
     let entity = trigger.entity();
     let (mut sprite, style, children) = query.get_mut(entity).unwrap();
 
-    if let Some(bg_color) = style.bg_color_on_hover {
-        sprite.color = bg_color;
-    }
-    if let Some(text_color) = style.text_color_on_hover {
-        text_colors.get_mut(children[0]).unwrap().0 = text_color;
-    }
+    style.paint(
+        &mut sprite,
+        &mut text_colors.get_mut(children[0]).unwrap(),
+        true,
+    );
 }
 
 fn button_pointer_out(
     trigger: Trigger<Pointer<Out>>,
-    mut query: Query<(&mut Sprite, &DialogButtonStyle, &Children)>,
+    mut query: Query<(
+        &mut Sprite,
+        &DialogButtonStyle,
+        &Children,
+        Has<DialogButtonFocused>,
+    )>,
     mut text_colors: Query<&mut TextColor>,
 ) {
     let entity = trigger.entity();
-    let (mut sprite, style, children) = query.get_mut(entity).unwrap();
-
-    sprite.color = style.bg_color;
-    text_colors.get_mut(children[0]).unwrap().0 = style.text_color;
+    let (mut sprite, style, children, focused) = query.get_mut(entity).unwrap();
+
+    // The focused button keeps its highlight.
+    style.paint(
+        &mut sprite,
+        &mut text_colors.get_mut(children[0]).unwrap(),
+        focused,
+    );
 }
 
 fn button_pointer_click(
@@ -9,8 +9,11 @@ use client::utils::{
 };
 
 mod card;
+pub(crate) use card::material::{CardPalette, CardTextureCache};
 use card::{guessing::NumSelected, CardPlugins};
 
+mod camera_control;
+
 mod card_field;
 use card_field::card_field_plugin;
 
@@ -23,6 +26,7 @@ use p2::p2_plugin;
 
 mod sandbox;
 use sandbox::game_sandbox_plugin;
+pub(crate) use sandbox::AiDifficulty;
 
 const CTX_STATE: AppState = AppState::Game;
 
@@ -43,12 +47,25 @@ const CARD_WIDTH_PLUS_GAP: f32 = CARD_WIDTH * (1.0 + CARD_X_GAP_RATIO);
 
 const TALON_TRANSLATION: Vec3 = Vec3::new(2.0, CARD_DEPTH / 2.0, 0.0);
 
+/// The height of the talon above which the cards are stacked more tightly,
+/// so that a large deck stays in view.
+const TALON_MAX_HEIGHT: f32 = CARD_DEPTH * 40.0;
+
+/// Returns the offset of the `idx`-th card from the bottom of a talon of `len` cards.
+fn talon_card_offset(idx: usize, len: usize) -> Vec3 {
+    let spacing = CARD_DEPTH.min(TALON_MAX_HEIGHT / len.max(1) as f32);
+    Vec3::Y * idx as f32 * spacing
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, SubStates)]
 #[source(AppState = AppState::Game)]
 pub(crate) enum GameMode {
     #[default]
     TwoPlayers,
     Sandbox,
+    /// Passed through between two games with the same players,
+    /// so that the next game is set up from scratch.
+    Restarting,
 }
 
 pub fn game_plugin(app: &mut App) {
@@ -70,6 +87,8 @@ pub fn game_plugin(app: &mut App) {
             dialog_plugin,
             p2_plugin,
             game_sandbox_plugin,
+            #[cfg(feature = "dev")]
+            card::dev_tooltip::card_dev_tooltip_plugin,
         ))
         .add_systems(OnEnter(CTX_STATE), setup_game);
 }
@@ -0,0 +1,114 @@
+use super::P2_CTX_STATE;
+use bevy::prelude::*;
+use client::utils::AddObserverExt as _;
+
+const INDICATOR_COLOR: Color = Color::srgba(1.0, 0.3, 0.2, 0.6);
+const INDICATOR_LINE_WIDTH: f32 = 4.0;
+const FADE_SECS: f32 = 0.5;
+
+pub fn attack_indicator_plugin(app: &mut App) {
+    app.insert_gizmo_config(
+        AttackIndicatorGizmos,
+        GizmoConfig {
+            line_width: INDICATOR_LINE_WIDTH,
+            // Draw over the cards.
+            depth_bias: -1.0,
+            ..default()
+        },
+    )
+    .add_state_scoped_observer_named(P2_CTX_STATE, ShowAttackIndicator::show)
+    .add_state_scoped_observer_named(P2_CTX_STATE, FadeAttackIndicator::fade)
+    .add_systems(Update, AttackIndicator::draw.run_if(in_state(P2_CTX_STATE)));
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct AttackIndicatorGizmos;
+
+/// An arrow from where the attacker was to the attacked card.
+#[derive(Component)]
+struct AttackIndicator {
+    from: Vec3,
+    target: Entity,
+    /// Set once the indicator starts fading out.
+    fade: Option<Timer>,
+}
+
+impl AttackIndicator {
+    fn draw(
+        mut gizmos: Gizmos<AttackIndicatorGizmos>,
+        time: Res<Time>,
+        mut indicators: Query<(Entity, &mut Self)>,
+        transforms: Query<&GlobalTransform>,
+        mut commands: Commands,
+    ) {
+        for (entity, mut indicator) in &mut indicators {
+            let alpha_ratio = match &mut indicator.fade {
+                Some(timer) => {
+                    timer.tick(time.delta());
+                    timer.fraction_remaining()
+                }
+                None => 1.0,
+            };
+
+            let Some(target) = transforms
+                .get(indicator.target)
+                .ok()
+                .filter(|_| alpha_ratio > 0.0)
+            else {
+                commands.entity(entity).despawn();
+                continue;
+            };
+
+            let color = INDICATOR_COLOR.with_alpha(INDICATOR_COLOR.alpha() * alpha_ratio);
+            gizmos.arrow(indicator.from, target.translation(), color);
+        }
+    }
+}
+
+/// Replaces the indicator with a new one, from the current position of the attacker.
+#[derive(Event)]
+pub(super) struct ShowAttackIndicator {
+    pub attacker: Entity,
+    pub target: Entity,
+}
+
+impl ShowAttackIndicator {
+    fn show(
+        trigger: Trigger<Self>,
+        indicators: Query<Entity, With<AttackIndicator>>,
+        transforms: Query<&GlobalTransform>,
+        mut commands: Commands,
+    ) {
+        for entity in &indicators {
+            commands.entity(entity).despawn();
+        }
+
+        let Ok(attacker) = transforms.get(trigger.attacker) else {
+            return;
+        };
+
+        commands.spawn((
+            StateScoped(P2_CTX_STATE),
+            AttackIndicator {
+                from: attacker.translation(),
+                target: trigger.target,
+                fade: None,
+            },
+            Name::new("AttackIndicator"),
+        ));
+    }
+}
+
+/// Fades out the indicator, as the turn has ended.
+#[derive(Event)]
+pub(super) struct FadeAttackIndicator;
+
+impl FadeAttackIndicator {
+    fn fade(_trigger: Trigger<Self>, mut indicators: Query<&mut AttackIndicator>) {
+        for mut indicator in &mut indicators {
+            indicator
+                .fade
+                .get_or_insert_with(|| Timer::from_seconds(FADE_SECS, TimerMode::Once));
+        }
+    }
+}
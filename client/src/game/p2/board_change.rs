@@ -3,11 +3,17 @@ use client::utils::observer_controller;
 use super::*;
 
 pub fn board_change_plugin(app: &mut App) {
-    app.add_state_scoped_observer_named(P2_CTX_STATE, ApplyBoardChange::talon_to_field)
-        .add_state_scoped_observer_named(P2_CTX_STATE, ApplyBoardChange::talon_to_attacker)
-        .add_state_scoped_observer_named(P2_CTX_STATE, ApplyBoardChange::attacker_to_field)
-        .add_state_scoped_observer_named(P2_CTX_STATE, ApplyBoardChange::reveal_attacker)
-        .add_state_scoped_observer_named(P2_CTX_STATE, ApplyBoardChange::reveal_field_card);
+    app.add_state_scoped_observers_named(
+        P2_CTX_STATE,
+        (
+            ApplyBoardChange::talon_to_field,
+            ApplyBoardChange::talon_to_attacker,
+            ApplyBoardChange::attacker_to_field,
+            ApplyBoardChange::reveal_attacker,
+            ApplyBoardChange::reveal_field_card,
+            ApplyBoardChange::field_reordered,
+        ),
+    );
 }
 
 #[derive(Deref, DerefMut, Event)]
@@ -25,7 +31,7 @@ impl ApplyBoardChange {
             player,
             movement: CardMovement::TalonToField { insert_at },
             card,
-        } = trigger.event().0
+        } = trigger.event().0.clone()
         else {
             return;
         };
@@ -37,6 +43,9 @@ impl ApplyBoardChange {
             .find(|(_, idx)| idx.0 == talon_top_idx.0)
             .unwrap();
 
+        // The talon may have been received without the drawing order.
+        commands.trigger_targets(card_instance::SetColor(card.pub_info.color), card_entity);
+
         // If private info is provided, attach that to the card
         if let Some(priv_info) = card.priv_info {
             commands.trigger_targets(card_instance::AddPrivInfo(priv_info), card_entity);
@@ -62,7 +71,7 @@ impl ApplyBoardChange {
             player: _,
             movement: CardMovement::TalonToAttacker,
             card,
-        } = trigger.event().0
+        } = trigger.event().0.clone()
         else {
             return;
         };
@@ -74,6 +83,9 @@ impl ApplyBoardChange {
             .find(|(_, idx)| idx.0 == talon_top_idx.0)
             .unwrap();
 
+        // The talon may have been received without the drawing order.
+        commands.trigger_targets(card_instance::SetColor(card.pub_info.color), card_entity);
+
         // If private info is provided, attach that to the card
         if let Some(priv_info) = card.priv_info {
             commands.trigger_targets(card_instance::AddPrivInfo(priv_info), card_entity);
@@ -107,7 +119,7 @@ impl ApplyBoardChange {
             player,
             movement: CardMovement::AttackerToField { insert_at },
             card: _,
-        } = trigger.event().0
+        } = trigger.event().0.clone()
         else {
             return;
         };
@@ -135,7 +147,7 @@ impl ApplyBoardChange {
             player: _,
             location: CardLocation::Attacker,
             card,
-        } = trigger.event().0
+        } = trigger.event().0.clone()
         else {
             return;
         };
@@ -154,6 +166,25 @@ impl ApplyBoardChange {
         }));
     }
 
+    fn field_reordered(
+        trigger: Trigger<Self>,
+        mut fields: Query<(Entity, &CardFieldOwnedBy, &mut CardField)>,
+        mut commands: Commands,
+    ) {
+        let BoardChange::FieldReordered { player, new_order } = &trigger.event().0 else {
+            return;
+        };
+
+        let (field_entity, _, mut field) = fields
+            .iter_mut()
+            .find(|(_, owned_by, _)| owned_by.0 == *player)
+            .unwrap();
+
+        if let Err(e) = field.apply_order(field_entity, new_order, &mut commands) {
+            warn!("failed to apply the order of the field: {}", e);
+        }
+    }
+
     fn reveal_field_card(
         trigger: Trigger<Self>,
         mut fields: Query<(&CardFieldOwnedBy, &mut CardField)>,
@@ -164,7 +195,7 @@ impl ApplyBoardChange {
             player,
             location: CardLocation::Field { idx },
             card,
-        } = trigger.event().0
+        } = trigger.event().0.clone()
         else {
             return;
         };
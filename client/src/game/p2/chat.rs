@@ -0,0 +1,151 @@
+use super::P2_CTX_STATE;
+use crate::JoinedPlayers;
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_simple_text_input::{
+    TextInput, TextInputInactive, TextInputPlaceholder, TextInputSubmitEvent, TextInputTextColor,
+    TextInputTextFont,
+};
+use client::{
+    client::{InboundEvent, OutboundEvent},
+    utils::log_display::{LogEvent, Message},
+    EventHandler,
+};
+use protocol::client_to_server::CHAT_MAX_LEN;
+
+const CHAT_COLOR: Color = Color::srgb(1.0, 0.6, 0.9);
+
+pub fn chat_plugin(app: &mut App) {
+    app.add_systems(OnEnter(P2_CTX_STATE), setup)
+        .add_systems(
+            Update,
+            (
+                send_chat,
+                focus_chat_input,
+                unfocus_chat_input.run_if(input_just_pressed(MouseButton::Left)),
+            )
+                .run_if(in_state(P2_CTX_STATE)),
+        )
+        .add_systems(FixedUpdate, recv_chat.run_if(in_state(P2_CTX_STATE)));
+}
+
+#[derive(Component)]
+struct ChatInput;
+
+fn setup(mut commands: Commands, joined_players: Res<JoinedPlayers>) {
+    // Spectators can only read the chat.
+    if joined_players.spectating {
+        return;
+    }
+
+    commands.spawn((
+        StateScoped(P2_CTX_STATE),
+        ChatInput,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(0.0),
+            bottom: Val::Px(0.0),
+            width: Val::Px(360.0),
+            height: Val::Px(32.0),
+            border: UiRect::all(Val::Px(2.0)),
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BorderColor(Color::srgb_u8(200, 200, 200)),
+        BackgroundColor(Color::srgba_u8(60, 60, 60, 200)),
+        TextInput,
+        TextInputInactive(true),
+        TextInputPlaceholder {
+            value: "Chat".into(),
+            text_color: Some(Color::srgb_u8(150, 150, 150).into()),
+            ..default()
+        },
+        TextInputTextFont(TextFont {
+            font_size: 18.0,
+            ..default()
+        }),
+        TextInputTextColor(Color::WHITE.into()),
+        Name::new("ChatInput"),
+    ));
+}
+
+fn focus_chat_input(
+    mut query: Query<
+        (&Interaction, &mut TextInputInactive),
+        (With<ChatInput>, Changed<Interaction>),
+    >,
+) {
+    for (interaction, mut inactive) in &mut query {
+        if matches!(interaction, Interaction::Pressed) && inactive.0 {
+            inactive.0 = false;
+        }
+    }
+}
+
+fn unfocus_chat_input(mut query: Query<(&Interaction, &mut TextInputInactive), With<ChatInput>>) {
+    for (interaction, mut inactive) in &mut query {
+        if matches!(interaction, Interaction::None) && !inactive.0 {
+            inactive.0 = true;
+        }
+    }
+}
+
+fn send_chat(
+    mut reader: EventReader<TextInputSubmitEvent>,
+    chat_input: Query<(), With<ChatInput>>,
+    mut ev_handler: ResMut<EventHandler>,
+    mut commands: Commands,
+) {
+    for ev in reader.read() {
+        if !chat_input.contains(ev.entity) {
+            continue;
+        }
+
+        let text = ev
+            .value
+            .trim()
+            .chars()
+            .take(CHAT_MAX_LEN)
+            .collect::<String>();
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Err(e) = ev_handler.send_request(OutboundEvent::Chat(text.clone())) {
+            warn!("failed to send chat: {}", e);
+            continue;
+        }
+
+        commands.send_event(LogEvent::Push(Message::new(
+            format!("[You] {}", text),
+            CHAT_COLOR,
+        )));
+    }
+}
+
+fn recv_chat(
+    mut ev_handler: ResMut<EventHandler>,
+    joined_players: Res<JoinedPlayers>,
+    mut commands: Commands,
+) {
+    while let Some((_, ev)) = ev_handler
+        .storage
+        .take_request_if(|v| matches!(v, InboundEvent::Chat { .. }))
+    {
+        let InboundEvent::Chat { from, text } = ev else {
+            unreachable!();
+        };
+
+        let sender = if !joined_players.spectating {
+            joined_players.name(from).unwrap_or("Opponent")
+        } else if Some(from) == joined_players.my_player {
+            "Near player"
+        } else {
+            "Far player"
+        };
+
+        commands.send_event(LogEvent::Push(Message::new(
+            format!("[{}] {}", sender, text),
+            CHAT_COLOR,
+        )));
+    }
+}
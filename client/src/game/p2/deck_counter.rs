@@ -0,0 +1,43 @@
+use super::{TalonTopCardIndex, P2_CTX_STATE};
+use bevy::prelude::*;
+
+pub fn deck_counter_plugin(app: &mut App) {
+    app.add_systems(OnEnter(P2_CTX_STATE), setup)
+        .add_systems(Update, DeckCounter::update.run_if(in_state(P2_CTX_STATE)));
+}
+
+/// Shows the number of cards left in the talon.
+#[derive(Component)]
+struct DeckCounter;
+
+impl DeckCounter {
+    fn update(
+        talon_top_idx: Query<&TalonTopCardIndex, Changed<TalonTopCardIndex>>,
+        mut counter: Single<&mut Text, With<Self>>,
+    ) {
+        // The index of the top card equals the number of cards left.
+        if let Ok(idx) = talon_top_idx.get_single() {
+            counter.0 = format!("Deck: {}", idx.0);
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(P2_CTX_STATE),
+        DeckCounter,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(50.0),
+            top: Val::Px(8.0),
+            ..default()
+        },
+        Text::new("Deck: -"),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        PickingBehavior::IGNORE,
+        Name::new("DeckCounter"),
+    ));
+}
@@ -0,0 +1,79 @@
+use super::P2_CTX_STATE;
+use crate::{game::card::instance::CardInstance, AppArgs};
+use algo_core::card::CardNumberType;
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use client::{
+    log_macros::*,
+    utils::log_display::{LogEvent, Message},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F2;
+
+pub fn guess_hint_plugin(app: &mut App) {
+    app.add_systems(OnEnter(P2_CTX_STATE), setup).add_systems(
+        Update,
+        toggle.run_if(in_state(P2_CTX_STATE).and(input_just_pressed(TOGGLE_KEY))),
+    );
+}
+
+/// Whether to disable the numbers that the guessed card cannot have.
+#[derive(Resource)]
+pub struct GuessHint {
+    pub enabled: bool,
+}
+
+impl GuessHint {
+    /// Returns the numbers that the target card cannot have,
+    /// or an empty set if the hint is disabled.
+    ///
+    /// Only the cards whose numbers the player already knows are taken into account,
+    /// so that the hint never reveals more than the player could deduce.
+    /// A number is impossible once all `copies_per_card` copies of it are known.
+    pub fn impossible_numbers(
+        &self,
+        target: Entity,
+        cards: &Query<(Entity, &CardInstance)>,
+        copies_per_card: u8,
+    ) -> BTreeSet<CardNumberType> {
+        if !self.enabled {
+            return BTreeSet::new();
+        }
+
+        let Ok((_, target_card)) = cards.get(target) else {
+            return BTreeSet::new();
+        };
+        let color = target_card.get().pub_info.color;
+
+        cards
+            .iter()
+            .filter(|(entity, _)| *entity != target)
+            .map(|(_, card)| card.get())
+            .filter(|card| card.pub_info.color == color)
+            .filter_map(|card| card.priv_info.map(|v| v.number.0))
+            .fold(BTreeMap::<_, u8>::new(), |mut counts, number| {
+                *counts.entry(number).or_default() += 1;
+                counts
+            })
+            .into_iter()
+            .filter(|(_, count)| *count >= copies_per_card)
+            .map(|(number, _)| number)
+            .collect()
+    }
+}
+
+fn setup(mut commands: Commands, app_args: Res<AppArgs>) {
+    commands.insert_resource(GuessHint {
+        enabled: app_args.guess_hint,
+    });
+}
+
+fn toggle(mut hint: ResMut<GuessHint>, mut commands: Commands) {
+    hint.enabled = !hint.enabled;
+
+    display_info!(
+        commands,
+        "guess hint: {} (toggle with F2)",
+        if hint.enabled { "on" } else { "off" }
+    );
+}
@@ -1,28 +1,35 @@
 use super::{
+    camera_control::{CameraControlPlugin, CameraLimits},
     card::{
         attacker::AttackTo,
         effects::CardPickingState,
         guessing::NumSelected,
         instance::{self as card_instance, CardInstance},
+        material::CardMaterials,
+        picking::InteractionLocked,
     },
     card_field::{CardField, CardPosition, MyCardField},
-    GameMode, CARD_DEPTH, HALF_CARD_DEPTH, TALON_TRANSLATION,
+    talon_card_offset, GameMode, HALF_CARD_DEPTH, TALON_TRANSLATION,
 };
 use crate::{
     game::{
         card::guessing::SpawnNumSelector, card_field::CardFieldOwnedBy, CARD_HEIGHT,
         CARD_Z_GAP_RATIO,
     },
-    AppState, JoinedPlayers,
+    AppArgs, AppState, JoinedPlayers, JoinedServer, WatchingReplay,
 };
 use algo_core::{
-    card::{CardView, TalonView},
+    card::{field_attack_targets, CardView, TalonView},
     event::{BoardChange, CardLocation, CardMovement, GameEvent},
     player::PlayerId,
+    settings::GameSettings,
 };
 use bevy::prelude::*;
 use client::{
-    client::{InboundEvent, DISCONNECTED_EV_ID},
+    client::{
+        spawn_client, InboundEvent, OutboundEvent, ReceivedResponse, SpawnClientResult,
+        DISCONNECTED_EV_ID,
+    },
     log_macros::*,
     utils::{
         animate_once::AnimateTransform,
@@ -33,13 +40,37 @@ use client::{
     },
     EventHandler,
 };
+use protocol::server_to_client::SERVER_SHUTDOWN_EV_ID;
 
 mod board_change;
 use board_change::ApplyBoardChange;
 
+mod attack_indicator;
+use attack_indicator::{FadeAttackIndicator, ShowAttackIndicator};
+
+mod chat;
+
+mod deck_counter;
+
+mod guess_hint;
+use guess_hint::GuessHint;
+
+mod peek_cards;
+use peek_cards::PeekInitialCards;
+
+mod rematch;
+use rematch::{OfferRematch, RematchStarting};
+
+mod replay;
+
 mod response;
 use response::{GameEvHandler, Resp};
 
+mod surrender;
+
+mod turn_highlight;
+use turn_highlight::{HideTurnHighlight, ShowTurnHighlight};
+
 mod ui;
 use ui::{
     history::{CardSnapshotBuilder, HistoryBgColor, PushHistory},
@@ -57,6 +88,7 @@ enum P2State {
     WaitingForGameToStart,
     SetupTalon,
     GameMain,
+    Reconnecting,
     _Todo, // TODO
     Disconnected,
 }
@@ -66,10 +98,17 @@ struct DisconnectionInfo {
     reason: String,
 }
 
+#[derive(Component)]
+struct RejoinRequestEventId(protocol::EventId);
+
 /// Temporary data for initializing talon cards.
 #[derive(Deref, DerefMut, Component)]
 struct Talon(TalonView);
 
+/// Settings of the current game.
+#[derive(Deref, Component)]
+struct Settings(GameSettings);
+
 #[derive(Component, Reflect)]
 struct TalonCardIndex(u32);
 
@@ -102,32 +141,61 @@ struct FirstTurnStarted(bool);
 struct CardDistributionCount(u8);
 
 pub fn p2_plugin(app: &mut App) {
-    app.add_plugins((ui::ui_plugin, response::response_plugin))
-        .add_sub_state::<P2State>()
-        .enable_state_scoped_entities::<P2State>()
-        .add_systems(OnEnter(P2_CTX_STATE), setup)
-        .add_systems(
-            FixedUpdate,
-            check_if_disconnected.run_if(in_state(P2_CTX_STATE)),
-        )
-        .add_plugins(board_change::board_change_plugin)
-        .add_systems(OnEnter(P2State::Disconnected), disconnected)
-        .add_systems(FixedUpdate, recv_game_event.run_if(in_state(P2_CTX_STATE)))
-        .add_systems(OnEnter(P2State::SetupTalon), setup_talon)
-        .add_state_scoped_observer_named(P2_CTX_STATE, TurnStarted::turn_started)
-        .add_state_scoped_observer_named(
-            P2_CTX_STATE,
+    app.add_plugins((
+        ui::ui_plugin,
+        response::response_plugin,
+        attack_indicator::attack_indicator_plugin,
+        chat::chat_plugin,
+        deck_counter::deck_counter_plugin,
+        guess_hint::guess_hint_plugin,
+        peek_cards::peek_cards_plugin,
+        rematch::rematch_plugin,
+        replay::replay_plugin,
+        surrender::surrender_plugin,
+        turn_highlight::turn_highlight_plugin,
+    ))
+    .add_plugins(CameraControlPlugin {
+        ctx_state: P2_CTX_STATE,
+        limits: Some(CameraLimits {
+            distance: 5.0..=16.0,
+            min_height: 2.0,
+            pitch: -1.55..=-0.6,
+        }),
+    })
+    .add_sub_state::<P2State>()
+    .enable_state_scoped_entities::<P2State>()
+    .add_systems(OnEnter(P2_CTX_STATE), setup)
+    .add_systems(
+        FixedUpdate,
+        (check_if_disconnected, check_opponent_connection).run_if(in_state(P2_CTX_STATE)),
+    )
+    .add_plugins(board_change::board_change_plugin)
+    .add_systems(OnEnter(P2State::Reconnecting), start_reconnecting)
+    .add_systems(
+        Update,
+        wait_for_reconnection.run_if(in_state(P2State::Reconnecting)),
+    )
+    .add_state_scoped_observer_named(P2State::Reconnecting, check_response_to_rejoin)
+    .add_systems(OnEnter(P2State::Disconnected), disconnected)
+    .add_systems(
+        FixedUpdate,
+        recv_game_event.run_if(in_state(P2_CTX_STATE).and(not(resource_exists::<RematchStarting>))),
+    )
+    .add_systems(OnEnter(P2State::SetupTalon), setup_talon)
+    .add_state_scoped_observers_named(
+        P2_CTX_STATE,
+        (
+            TurnStarted::turn_started,
             AttackTargetSelectionRequired::attack_target_selection_required,
-        )
-        .add_state_scoped_observer_named(P2_CTX_STATE, AttackTargetSelected::attack_target_selected)
-        .add_state_scoped_observer_named(P2_CTX_STATE, NumberGuessRequired::number_guess_required)
-        .add_state_scoped_observer_named(P2_CTX_STATE, InformAttackResult::inform_attack_result)
-        .add_state_scoped_observer_named(
-            P2_CTX_STATE,
+            AttackTargetSelected::attack_target_selected,
+            NumberGuessRequired::number_guess_required,
+            InformAttackResult::inform_attack_result,
             AttackOrStayDecisionRequired::attack_or_stay_decision_required,
-        )
-        .add_state_scoped_observer_named(P2_CTX_STATE, chosen_attack_or_stay)
-        .add_state_scoped_observer_named(P2_CTX_STATE, GameSet::game_set);
+            chosen_attack_or_stay,
+            GameSet::game_set,
+            PlayerSurrendered::player_surrendered,
+        ),
+    );
 }
 
 fn setup(mut commands: Commands, joined_players: ResMut<JoinedPlayers>) {
@@ -181,13 +249,63 @@ fn setup(mut commands: Commands, joined_players: ResMut<JoinedPlayers>) {
 
 fn check_if_disconnected(
     mut ev_handler: ResMut<EventHandler>,
+    current_state: Res<State<P2State>>,
     mut state: ResMut<NextState<P2State>>,
     mut commands: Commands,
+    joined_players: Res<JoinedPlayers>,
 ) {
+    if *current_state.get() == P2State::Disconnected {
+        return;
+    }
+
+    // Checked before the shutdown, which follows the abort.
+    if let Some((_, ev)) = ev_handler
+        .storage
+        .take_request_if(|v| matches!(v, InboundEvent::GameAborted { .. }))
+    {
+        let InboundEvent::GameAborted { reason } = ev else {
+            unreachable!();
+        };
+
+        warn!("the game was aborted: {}", reason);
+        commands.spawn((
+            StateScoped(P2_CTX_STATE),
+            DisconnectionInfo {
+                reason: format!("Game aborted: {}", reason),
+            },
+        ));
+        state.set(P2State::Disconnected);
+        return;
+    }
+
     let mut reason = String::new();
     let mut disconnected = false;
+    if ev_handler
+        .storage
+        .take_request(SERVER_SHUTDOWN_EV_ID)
+        .is_some()
+    {
+        warn!("the server has shut down");
+        commands.spawn((
+            StateScoped(P2_CTX_STATE),
+            DisconnectionInfo {
+                reason: "Server shut down".into(),
+            },
+        ));
+        state.set(P2State::Disconnected);
+        return;
+    }
+
     if let Some(ev) = ev_handler.storage.take_request(DISCONNECTED_EV_ID) {
         warn!("disconnected from the server: {:?}", ev);
+
+        // Try to take back the seat once before giving up.
+        // Spectators have no seat to take back.
+        if *current_state.get() != P2State::Reconnecting && !joined_players.spectating {
+            state.set(P2State::Reconnecting);
+            return;
+        }
+
         reason = "Disconnected from the server".into();
         disconnected = true;
     }
@@ -201,7 +319,12 @@ fn check_if_disconnected(
         };
 
         warn!("player {:?} disconnected from the server", pid);
-        reason = "Opponent disconnected".into();
+        reason = if joined_players.spectating {
+            "A player disconnected"
+        } else {
+            "Opponent disconnected"
+        }
+        .into();
         disconnected = true;
     }
 
@@ -211,6 +334,133 @@ fn check_if_disconnected(
     }
 }
 
+fn check_opponent_connection(mut ev_handler: ResMut<EventHandler>, mut commands: Commands) {
+    let Some((_, ev)) = ev_handler.storage.take_request_if(|v| {
+        matches!(
+            v,
+            InboundEvent::PlayerReconnecting(_) | InboundEvent::PlayerReconnected(_)
+        )
+    }) else {
+        return;
+    };
+
+    match ev {
+        InboundEvent::PlayerReconnecting(pid) => {
+            warn!("player {:?} lost connection", pid);
+            display_warn!(
+                commands,
+                "Opponent lost connection. Waiting for reconnection..."
+            );
+        }
+        InboundEvent::PlayerReconnected(pid) => {
+            info!("player {:?} reconnected", pid);
+            display_info!(commands, "Opponent reconnected");
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn start_reconnecting(mut commands: Commands, server: Res<JoinedServer>, app_args: Res<AppArgs>) {
+    display_warn!(commands, "connection lost. reconnecting...");
+    commands.trigger(SpawnPopupMessage {
+        message: "Reconnecting...".into(),
+        ..default()
+    });
+
+    spawn_client(
+        &mut commands,
+        server.addr,
+        server.port,
+        app_args.connect_retry_settings(),
+        app_args.wire_format,
+        app_args.read_buf_size,
+    );
+}
+
+fn wait_for_reconnection(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnClientResult>,
+    mut state: ResMut<NextState<P2State>>,
+    mut ev_handler: ResMut<EventHandler>,
+    joined_players: Res<JoinedPlayers>,
+) {
+    let Some(res) = reader.read().next() else {
+        return;
+    };
+
+    // The event handler is replaced with the new one at this point.
+    let res = match res.0 {
+        Ok(_) => match joined_players.reconnect_token {
+            Some(token) => ev_handler
+                .send_request(OutboundEvent::Rejoin {
+                    player: joined_players.my_player.unwrap(),
+                    token,
+                })
+                .map_err(|e| e.to_string()),
+            None => Err("no reconnect token".to_string()),
+        },
+        Err(ref e) => Err(e.to_string()),
+    };
+
+    match res {
+        Ok(id) => {
+            commands.spawn((
+                StateScoped(P2State::Reconnecting),
+                RejoinRequestEventId(id),
+                Name::new("RejoinRequestEventIdStorage"),
+            ));
+        }
+        Err(e) => {
+            warn!("failed to reconnect: {}", e);
+            commands.spawn((
+                StateScoped(P2_CTX_STATE),
+                DisconnectionInfo {
+                    reason: "Disconnected from the server".into(),
+                },
+            ));
+            state.set(P2State::Disconnected);
+        }
+    }
+
+    reader.clear();
+}
+
+fn check_response_to_rejoin(
+    trigger: Trigger<ReceivedResponse>,
+    mut ev_handler: ResMut<EventHandler>,
+    rejoin_request_ev_id: Single<&RejoinRequestEventId>,
+    mut state: ResMut<NextState<P2State>>,
+    mut commands: Commands,
+) {
+    let ev_id = rejoin_request_ev_id.0;
+
+    if trigger.event().id() != ev_id {
+        return;
+    }
+
+    let response = ev_handler
+        .storage
+        .take_response(ev_id)
+        .expect("response should be available");
+
+    match response {
+        InboundEvent::RejoinAccepted => {
+            display_success!(commands, "reconnected to the server");
+            state.set(P2State::GameMain);
+        }
+        unexp => {
+            warn!("failed to rejoin: {:?}", unexp);
+            commands.spawn((
+                StateScoped(P2_CTX_STATE),
+                DisconnectionInfo {
+                    reason: "Disconnected from the server".into(),
+                },
+            ));
+            state.set(P2State::Disconnected);
+        }
+    }
+}
+
 fn disconnected(mut commands: Commands, mut disconnection_info: Single<&mut DisconnectionInfo>) {
     commands.trigger(SpawnPopupMessage {
         message: std::mem::take(&mut disconnection_info.reason),
@@ -224,36 +474,50 @@ fn recv_game_event(
     mut ev_handler: GameEvHandler,
     mut state: ResMut<NextState<P2State>>,
     mut counter: Single<&mut CardDistributionCount>,
+    settings: Option<Single<&Settings>>,
+    mut card_materials: ResMut<CardMaterials>,
+    first_turn_started: Single<&FirstTurnStarted>,
+    joined_players: Res<JoinedPlayers>,
+    args: Res<AppArgs>,
+    replay: Option<Res<WatchingReplay>>,
 ) {
+    // Events may arrive several at once, e.g. when spectators catch up on the game.
+    // `recv_game_ev` takes them one by one, after responding to the previous one.
     let Some(ev) = ev_handler.recv_game_ev() else {
         return;
     };
 
     let mut delay = 0.0;
+    let initial_draw_total = settings.map_or(0, |v| v.initial_draw_num * 2);
 
     match &ev {
         GameEvent::BoardChanged(board_change) => {
-            commands.trigger(ApplyBoardChange(*board_change));
+            commands.trigger(ApplyBoardChange(board_change.clone()));
 
-            delay += if matches!(
-                board_change,
+            delay += match board_change {
+                // The cards move only if the order differs from what the client derived.
+                BoardChange::FieldReordered { .. } => 0.0,
                 BoardChange::CardMoved {
                     movement: CardMovement::TalonToField { .. },
                     ..
-                }
-            ) && counter.0 < (4 * 2)
-            {
-                0.25
-            } else {
-                0.5
+                } if u32::from(counter.0) < initial_draw_total => 0.25,
+                _ => 0.5,
             };
         }
-        GameEvent::GameStarted(talon_view) => {
+        GameEvent::GameStarted { talon, settings } => {
+            // Before the talon cards are spawned, which are painted with the palette.
+            card_materials.use_game_settings(settings);
+
             commands.spawn((
                 StateScoped(P2_CTX_STATE),
-                Talon(talon_view.clone()),
+                Talon(talon.clone()),
                 Name::new("Talon"),
             ));
+            commands.spawn((
+                StateScoped(P2_CTX_STATE),
+                Settings(GameSettings::clone(settings)),
+                Name::new("Settings"),
+            ));
 
             state.set(P2State::SetupTalon);
 
@@ -268,7 +532,18 @@ fn recv_game_event(
         GameEvent::CardDistributed(_) => {
             counter.0 += 1;
         }
-        GameEvent::TurnStarted(pid) => commands.trigger(TurnStarted(*pid)),
+        GameEvent::TurnStarted(pid) => {
+            if args.peek_initial_cards
+                && !first_turn_started.0
+                && !joined_players.spectating
+                && replay.is_none()
+            {
+                // `PeekInitialCards` responds once I've confirmed.
+                commands.trigger(PeekInitialCards { first_player: *pid });
+                return;
+            }
+            commands.trigger(TurnStarted(*pid));
+        }
         GameEvent::TurnPlayerDrewCard => (),
         GameEvent::NoCardsLeft => (),
         GameEvent::AttackTargetSelectionRequired { target_player } => {
@@ -308,7 +583,23 @@ fn recv_game_event(
             commands.trigger(GameSet);
             delay += 0.5;
         }
-        GameEvent::GameEnded => (),
+        GameEvent::PlayerSurrendered(pid) => {
+            commands.trigger(PlayerSurrendered(*pid));
+            delay += 0.5;
+        }
+        GameEvent::GameEnded => {
+            commands.trigger(OfferRematch);
+        }
+        GameEvent::GameDrawn => {
+            display_info!(commands, "Draw!");
+            commands.trigger(SpawnPopupMessage {
+                duration_secs: 1.0,
+                message: "Draw!".into(),
+            });
+            commands.trigger(PushHistory::GameDrawn);
+            // A drawn game ends without `GameEnded`, so the rematch is offered here.
+            commands.trigger(SetTimeout::new(1.0).with_trigger(OfferRematch));
+        }
         GameEvent::AttackOrStayDecisionRequired => {
             commands.trigger(AttackOrStayDecisionRequired);
         }
@@ -322,7 +613,10 @@ fn recv_game_event(
 
             delay += 0.5;
         }
-        GameEvent::TurnEnded => {}
+        GameEvent::TurnEnded => {
+            commands.trigger(FadeAttackIndicator);
+            commands.trigger(HideTurnHighlight);
+        }
         GameEvent::RespOk => unreachable!(),
     }
 
@@ -337,8 +631,9 @@ fn setup_talon(talon: Single<(Entity, &Talon)>, mut commands: Commands) {
 
     // Spawn cards
     for (idx, color) in talon.cards.iter().enumerate() {
-        let mut transform = Transform::from_translation(TALON_TRANSLATION);
-        transform.translation.y += idx as f32 * CARD_DEPTH;
+        let transform = Transform::from_translation(
+            TALON_TRANSLATION + talon_card_offset(idx, talon.cards.len()),
+        );
 
         commands.spawn((
             StateScoped(P2_CTX_STATE),
@@ -373,6 +668,7 @@ impl TurnStarted {
         mut first_turn_done: Single<&mut FirstTurnStarted>,
         fields: Query<&CardField>,
         cards: Query<&CardInstance>,
+        joined_players: Res<JoinedPlayers>,
     ) {
         let (storage_entity, ref mut turn_player) = *query;
 
@@ -382,16 +678,29 @@ impl TurnStarted {
         let is_my_turn = turn_player_id == my_player_field.0;
 
         // Popup message
-        let message = if is_my_turn {
+        let message = if joined_players.spectating {
+            // Spectators never make decisions.
+            commands.entity(storage_entity).remove::<MyTurn>();
+            if is_my_turn {
+                "Near player's turn!".to_string()
+            } else {
+                "Far player's turn!".to_string()
+            }
+        } else if is_my_turn {
             commands.entity(storage_entity).insert(MyTurn);
-            "Your turn!"
+            commands.trigger(ShowTurnHighlight);
+            "Your turn!".to_string()
         } else {
             commands.entity(storage_entity).remove::<MyTurn>();
-            "Opponent's turn!"
+            commands.trigger(HideTurnHighlight);
+            format!(
+                "{}'s turn!",
+                joined_players.name(turn_player_id).unwrap_or("Opponent")
+            )
         };
         display_info!(commands, "{}", message);
         commands.trigger(SpawnPopupMessage {
-            message: message.into(),
+            message: message.clone(),
             ..default()
         });
 
@@ -411,7 +720,7 @@ impl TurnStarted {
             }
         }
         commands.trigger(PushHistory::TurnStarted {
-            message: message.into(),
+            message,
             color: HistoryBgColor::from_bool(is_my_turn),
         })
     }
@@ -448,13 +757,13 @@ impl AttackTargetSelectionRequired {
             .unwrap();
 
         // Find valid attack target cards
-        for attack_target in field
+        let pub_infos = field
             .cards()
             .iter()
-            .filter(|e| !cards.get(**e).unwrap().get().pub_info.revealed)
-        {
+            .map(|e| cards.get(*e).unwrap().get().pub_info);
+        for idx in field_attack_targets(pub_infos) {
             commands
-                .entity(*attack_target)
+                .entity(field.cards()[idx as usize])
                 .insert(CardPickingState::Pickable);
         }
     }
@@ -465,7 +774,13 @@ fn on_click_attack_target(
     query: Query<&CardPosition>,
     mut ev_handler: GameEvHandler,
     mut card_picking_states: Query<&mut CardPickingState>,
+    locked: Option<Res<InteractionLocked>>,
+    mut commands: Commands,
 ) {
+    if !InteractionLocked::try_lock(&locked, &mut commands) {
+        return;
+    }
+
     let entity = trigger.entity();
     let target_idx = query.get(entity).unwrap().idx();
 
@@ -500,6 +815,10 @@ impl AttackTargetSelected {
         let entity = field.cards()[trigger.target_idx as usize];
         attack_target_card.0 = Some(entity);
 
+        commands.trigger(ShowAttackIndicator {
+            attacker: *attacker,
+            target: entity,
+        });
         commands.trigger_targets(
             AttackTo {
                 target_card: entity,
@@ -522,6 +841,9 @@ impl NumberGuessRequired {
         turn_player: Single<Has<MyTurn>, With<TurnPlayer>>,
         mut commands: Commands,
         attack_target_card: Single<&AttackTargetCard>,
+        settings: Single<&Settings>,
+        hint: Res<GuessHint>,
+        cards: Query<(Entity, &CardInstance)>,
     ) {
         if !*turn_player {
             display_info!(commands, "Opponent is guessing a number...");
@@ -530,9 +852,13 @@ impl NumberGuessRequired {
         }
         display_info!(commands, "Guess a card number");
 
+        let target = attack_target_card.0.unwrap();
         commands
-            .entity(attack_target_card.0.unwrap())
-            .trigger(SpawnNumSelector)
+            .entity(target)
+            .trigger(SpawnNumSelector {
+                numbers: settings.card_numbers(),
+                disabled: hint.impossible_numbers(target, &cards, settings.copies_per_card),
+            })
             .trigger(ObserveOnce::<NumSelected>::new(Observer::new(
                 send_guessed_number,
             )));
@@ -609,16 +935,60 @@ fn chosen_attack_or_stay(trigger: Trigger<QuestionAnswered>, mut ev_handler: Gam
     ev_handler.send_game_ev(GameEvent::AttackOrStayDecided { attack });
 }
 
+#[derive(Event)]
+struct PlayerSurrendered(PlayerId);
+
+impl PlayerSurrendered {
+    fn player_surrendered(
+        trigger: Trigger<Self>,
+        my_player_field: Single<&CardFieldOwnedBy, With<MyCardField>>,
+        joined_players: Res<JoinedPlayers>,
+        mut commands: Commands,
+    ) {
+        let near_player_surrendered = trigger.0 == my_player_field.0;
+
+        let message: String = if joined_players.spectating {
+            if near_player_surrendered {
+                "Far Player Wins!"
+            } else {
+                "Near Player Wins!"
+            }
+        } else if near_player_surrendered {
+            "You Lose!"
+        } else {
+            "You Win!"
+        }
+        .into();
+        display_info!(commands, "{}", message);
+
+        commands.trigger(SpawnPopupMessage {
+            duration_secs: 1.0,
+            message,
+        });
+        commands.trigger(PushHistory::Surrendered);
+    }
+}
+
 #[derive(Event)]
 struct GameSet;
 
 impl GameSet {
     fn game_set(
         _trigger: Trigger<Self>,
-        turn_player: Single<Has<MyTurn>, With<TurnPlayer>>,
+        turn_player: Single<(&TurnPlayer, Has<MyTurn>)>,
+        my_player_field: Single<&CardFieldOwnedBy, With<MyCardField>>,
+        joined_players: Res<JoinedPlayers>,
         mut commands: Commands,
     ) {
-        let message = if *turn_player {
+        let (turn_player, is_my_turn) = *turn_player;
+
+        let message = if joined_players.spectating {
+            if turn_player.0 == Some(my_player_field.0) {
+                "Near Player Wins!"
+            } else {
+                "Far Player Wins!"
+            }
+        } else if is_my_turn {
             "You Win!"
         } else {
             "You Lose!"
@@ -626,6 +996,7 @@ impl GameSet {
         .into();
         display_info!(commands, "{}", message);
 
+        commands.trigger(HideTurnHighlight);
         commands.trigger(SpawnPopupMessage {
             duration_secs: 1.0,
             message,
@@ -0,0 +1,115 @@
+use super::{Resp, SpawnPopupMessage, TurnStarted, P2_CTX_STATE};
+use crate::game::{
+    card::instance::CardInstance,
+    card_field::{CardField, MyCardField},
+    dialog::{Dialog, DialogButton},
+    CARD_HEIGHT,
+};
+use algo_core::player::PlayerId;
+use bevy::prelude::*;
+use client::{
+    log_macros::*,
+    utils::{
+        animate_once::AnimateTransform,
+        log_display::{LogEvent, Message},
+        set_timeout::SetTimeout,
+        AddObserverExt as _,
+    },
+};
+
+const LIFT_HEIGHT: f32 = CARD_HEIGHT * 0.3;
+const LIFT_SECS: f32 = 0.3;
+
+pub fn peek_cards_plugin(app: &mut App) {
+    app.add_state_scoped_observer_named(P2_CTX_STATE, PeekInitialCards::peek)
+        .add_state_scoped_observer_named(P2_CTX_STATE, InitialCardsNoted::start_first_turn);
+}
+
+/// Where a card lifted for peeking returns to.
+#[derive(Component)]
+struct PeekedFrom(Transform);
+
+/// Shows my initial cards, and holds the first turn until I confirm that I've noted them.
+#[derive(Event)]
+pub(super) struct PeekInitialCards {
+    /// The player taking the first turn.
+    pub first_player: PlayerId,
+}
+
+impl PeekInitialCards {
+    fn peek(
+        trigger: Trigger<Self>,
+        field: Single<&CardField, With<MyCardField>>,
+        cards: Query<(&CardInstance, &Transform)>,
+        mut commands: Commands,
+    ) {
+        let mut numbers = vec![];
+
+        for &entity in field.cards() {
+            let Ok((card, transform)) = cards.get(entity) else {
+                continue;
+            };
+            if let Some(priv_info) = card.get().priv_info {
+                numbers.push(priv_info.number().0.to_string());
+            }
+
+            let lifted = transform.with_translation(transform.translation + Vec3::Y * LIFT_HEIGHT);
+            commands
+                .entity(entity)
+                .insert(PeekedFrom(*transform))
+                .trigger(AnimateTransform::new(
+                    lifted,
+                    LIFT_SECS,
+                    EaseFunction::QuadraticOut,
+                ));
+        }
+
+        let message = format!("Your cards: {}", numbers.join(", "));
+        display_info!(commands, "{}", message);
+        commands.trigger(SpawnPopupMessage {
+            duration_secs: 2.0,
+            message,
+        });
+
+        let first_player = trigger.event().first_player;
+        commands.spawn((
+            StateScoped(P2_CTX_STATE),
+            Dialog::new(
+                None,
+                [DialogButton::new(
+                    "I've noted my cards",
+                    move |commands| commands.trigger(InitialCardsNoted { first_player }),
+                    default(),
+                )],
+            ),
+            Transform::from_xyz(0.0, 0.0, 10.0),
+        ));
+    }
+}
+
+#[derive(Event)]
+struct InitialCardsNoted {
+    first_player: PlayerId,
+}
+
+impl InitialCardsNoted {
+    fn start_first_turn(
+        trigger: Trigger<Self>,
+        cards: Query<(Entity, &PeekedFrom)>,
+        mut commands: Commands,
+    ) {
+        for (entity, peeked_from) in &cards {
+            commands
+                .entity(entity)
+                .remove::<PeekedFrom>()
+                .trigger(AnimateTransform::new(
+                    peeked_from.0,
+                    LIFT_SECS,
+                    EaseFunction::QuadraticIn,
+                ));
+        }
+
+        commands.trigger(TurnStarted(trigger.event().first_player));
+        commands.trigger(SetTimeout::new(LIFT_SECS).with_trigger(Resp::OK));
+    }
+}
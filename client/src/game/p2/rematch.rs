@@ -0,0 +1,158 @@
+use super::{recv_game_event, P2_CTX_STATE};
+use crate::{
+    game::{
+        dialog::{Dialog, DialogButton},
+        GameMode,
+    },
+    JoinedPlayers, WatchingReplay,
+};
+use bevy::prelude::*;
+use client::{
+    client::{InboundEvent, OutboundEvent},
+    log_macros::*,
+    utils::{
+        log_display::{LogEvent, Message},
+        AddObserverExt as _,
+    },
+    EventHandler,
+};
+
+pub fn rematch_plugin(app: &mut App) {
+    app.add_systems(OnEnter(P2_CTX_STATE), setup)
+        .add_systems(OnEnter(GameMode::Restarting), restart)
+        .add_systems(
+            FixedUpdate,
+            (recv_rematch_messages, start_rematch)
+                .before(recv_game_event)
+                .run_if(in_state(P2_CTX_STATE)),
+        )
+        .add_state_scoped_observer_named(P2_CTX_STATE, OfferRematch::offer)
+        .add_state_scoped_observer_named(P2_CTX_STATE, AnswerRematch::answer);
+}
+
+/// Exists from when a rematch is started until the next game is set up,
+/// so that the events of the next game are left for it.
+#[derive(Resource)]
+pub(super) struct RematchStarting;
+
+#[derive(Component)]
+struct RematchDialog;
+
+fn setup(mut commands: Commands) {
+    commands.remove_resource::<RematchStarting>();
+}
+
+/// Asks the player whether to play again, as the game has ended.
+#[derive(Event)]
+pub(super) struct OfferRematch;
+
+impl OfferRematch {
+    fn offer(
+        _trigger: Trigger<Self>,
+        joined_players: Res<JoinedPlayers>,
+        replay: Option<Res<WatchingReplay>>,
+        mut commands: Commands,
+    ) {
+        // Only players can ask for a rematch.
+        if joined_players.spectating || replay.is_some() {
+            return;
+        }
+
+        commands.spawn((
+            StateScoped(P2_CTX_STATE),
+            RematchDialog,
+            Dialog::new(
+                None,
+                [
+                    DialogButton::new(
+                        "Rematch",
+                        |commands| commands.trigger(AnswerRematch(true)),
+                        default(),
+                    ),
+                    DialogButton::new(
+                        "Leave",
+                        |commands| commands.trigger(AnswerRematch(false)),
+                        default(),
+                    ),
+                ],
+            ),
+            Transform::from_xyz(0.0, 0.0, 10.0),
+        ));
+    }
+}
+
+/// Sends whether the player wants to play again.
+#[derive(Event)]
+struct AnswerRematch(bool);
+
+impl AnswerRematch {
+    fn answer(
+        trigger: Trigger<Self>,
+        mut ev_handler: ResMut<EventHandler>,
+        mut commands: Commands,
+    ) {
+        let accepted = trigger.event().0;
+        let request = if accepted {
+            OutboundEvent::RequestRematch
+        } else {
+            OutboundEvent::RematchDeclined
+        };
+
+        if let Err(e) = ev_handler.send_request(request) {
+            display_error!(commands, "failed to answer the rematch: {}", e);
+            return;
+        }
+
+        if accepted {
+            display_info!(
+                commands,
+                "waiting for the opponent to accept the rematch..."
+            );
+        }
+    }
+}
+
+fn recv_rematch_messages(
+    mut ev_handler: ResMut<EventHandler>,
+    joined_players: Res<JoinedPlayers>,
+    mut commands: Commands,
+) {
+    while let Some((_, ev)) = ev_handler.storage.take_request_if(|v| {
+        matches!(
+            v,
+            InboundEvent::RematchRequested(_) | InboundEvent::RematchDeclined(_)
+        )
+    }) {
+        match ev {
+            InboundEvent::RematchRequested(pid) => {
+                let name = joined_players.name(pid).unwrap_or("Opponent");
+                display_info!(commands, "{} wants a rematch", name);
+            }
+            InboundEvent::RematchDeclined(pid) => {
+                let name = joined_players.name(pid).unwrap_or("Opponent");
+                display_warn!(commands, "{} declined the rematch", name);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Tears down the finished game to set up the next one from scratch.
+fn start_rematch(mut ev_handler: ResMut<EventHandler>, mut commands: Commands) {
+    if ev_handler
+        .storage
+        .take_request_if(|v| matches!(v, InboundEvent::RematchStarted))
+        .is_none()
+    {
+        return;
+    }
+
+    display_success!(commands, "rematch!");
+
+    commands.insert_resource(RematchStarting);
+    commands.set_state(GameMode::Restarting);
+}
+
+fn restart(mut commands: Commands) {
+    commands.set_state(P2_CTX_STATE);
+}
@@ -0,0 +1,147 @@
+use super::{DisconnectionInfo, P2State, P2_CTX_STATE};
+use crate::WatchingReplay;
+use algo_core::event::GameEvent;
+use bevy::prelude::*;
+use client::{
+    client::{InboundEvent, OutboundEvent},
+    log_macros::*,
+    utils::log_display::{LogEvent, Message},
+    EventHandler,
+};
+use protocol::{EventKind, NextEventId, WithMetadata};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 4.0;
+
+pub fn replay_plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(P2_CTX_STATE),
+        setup.run_if(resource_exists::<WatchingReplay>),
+    )
+    .add_systems(
+        OnExit(P2_CTX_STATE),
+        cleanup.run_if(resource_exists::<WatchingReplay>),
+    )
+    .add_systems(
+        FixedUpdate,
+        ReplayDriver::feed.run_if(in_state(P2_CTX_STATE).and(resource_exists::<ReplayDriver>)),
+    )
+    .add_systems(
+        Update,
+        control_playback.run_if(in_state(P2_CTX_STATE).and(resource_exists::<WatchingReplay>)),
+    );
+}
+
+/// Feeds the recorded events to the client as if they were sent by a server.
+#[derive(Resource)]
+struct ReplayDriver {
+    in_tx: UnboundedSender<WithMetadata<InboundEvent>>,
+    out_rx: UnboundedReceiver<WithMetadata<OutboundEvent>>,
+    events: std::iter::Enumerate<std::vec::IntoIter<GameEvent>>,
+    next_id: NextEventId,
+    /// `true` while the client has not responded to the last event.
+    response_pending: bool,
+}
+
+impl ReplayDriver {
+    fn feed(mut this: ResMut<Self>, mut state: ResMut<NextState<P2State>>, mut commands: Commands) {
+        // The responses are only used to pace the replay.
+        while this.out_rx.try_recv().is_ok() {
+            this.response_pending = false;
+        }
+
+        if this.response_pending {
+            return;
+        }
+
+        let Some((idx, event)) = this.events.next() else {
+            commands.spawn((
+                StateScoped(P2_CTX_STATE),
+                DisconnectionInfo {
+                    reason: "Replay finished".into(),
+                },
+            ));
+            state.set(P2State::Disconnected);
+            commands.remove_resource::<Self>();
+            return;
+        };
+
+        let id = this.next_id.produce();
+        let sent = this.in_tx.send(WithMetadata {
+            kind: EventKind::Request,
+            id,
+            event: InboundEvent::GameEvent {
+                seq: idx as u32 + 1,
+                event,
+            },
+        });
+        if sent.is_err() {
+            warn!("the event handler is dropped. stopping the replay");
+            commands.remove_resource::<Self>();
+            return;
+        }
+
+        this.response_pending = true;
+    }
+}
+
+fn setup(mut commands: Commands, replay: Res<WatchingReplay>) {
+    let (in_tx, in_rx) = mpsc::unbounded_channel();
+    let (out_tx, out_rx) = mpsc::unbounded_channel();
+
+    // The game is played through the same path as a live match.
+    commands.insert_resource(EventHandler::new(in_rx, out_tx));
+    commands.insert_resource(ReplayDriver {
+        in_tx,
+        out_rx,
+        events: replay.events.clone().into_iter().enumerate(),
+        next_id: NextEventId::default(),
+        response_pending: false,
+    });
+
+    display_info!(
+        commands,
+        "watching a replay\n[Space] pause/play, [Up]/[Down] change speed"
+    );
+}
+
+fn cleanup(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+    time.set_relative_speed(1.0);
+
+    commands.remove_resource::<ReplayDriver>();
+    commands.remove_resource::<WatchingReplay>();
+}
+
+/// Pauses and changes the speed of the replay by scaling the virtual time,
+/// which drives both the animations and the event pacing.
+fn control_playback(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut commands: Commands,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        if time.is_paused() {
+            time.unpause();
+            display_info!(commands, "replay resumed");
+        } else {
+            time.pause();
+            display_info!(commands, "replay paused");
+        }
+    }
+
+    let speed = time.relative_speed();
+    let new_speed = if keys.just_pressed(KeyCode::ArrowUp) {
+        (speed * 2.0).min(MAX_SPEED)
+    } else if keys.just_pressed(KeyCode::ArrowDown) {
+        (speed / 2.0).max(MIN_SPEED)
+    } else {
+        return;
+    };
+
+    if new_speed != speed {
+        time.set_relative_speed(new_speed);
+        display_info!(commands, "replay speed: x{}", new_speed);
+    }
+}
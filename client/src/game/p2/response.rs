@@ -7,14 +7,23 @@ use client::{
 };
 
 use super::P2_CTX_STATE;
+use crate::JoinedPlayers;
 
 pub fn response_plugin(app: &mut App) {
     app.add_systems(OnEnter(P2_CTX_STATE), setup)
         .add_state_scoped_observer_named(P2_CTX_STATE, Resp::send_resp);
 }
 
-fn setup(mut commands: Commands) {
-    commands.spawn((StateScoped(P2_CTX_STATE), RespId(None)));
+fn setup(mut commands: Commands, joined_players: Res<JoinedPlayers>) {
+    commands.spawn((
+        StateScoped(P2_CTX_STATE),
+        RespId(None),
+        GameEvSeq {
+            auto_ack: joined_players.auto_ack,
+            spectating: joined_players.spectating,
+            ..default()
+        },
+    ));
 }
 
 #[derive(Event)]
@@ -38,22 +47,101 @@ impl Resp {
 #[derive(Component)]
 struct RespId(Option<protocol::EventId>);
 
+/// Tracks the sequence numbers of `GameEvent`s, to apply each of them exactly once.
+#[derive(Default, Component)]
+struct GameEvSeq {
+    /// `None` until the first `GameEvent` arrives.
+    last_applied: Option<u32>,
+    /// The response to the last applied `GameEvent`, kept to be resent
+    /// if the server has not received it.
+    last_resp: Option<GameEvent>,
+    resync_requested: bool,
+    /// Whether the server acknowledges the `GameEvent`s requiring no decision for me.
+    auto_ack: bool,
+    /// Whether the last applied `GameEvent` is acknowledged by the server,
+    /// so that its response is not sent.
+    auto_acked: bool,
+    /// Whether I am spectating, in which case the server takes no resync requests from me.
+    spectating: bool,
+}
+
 #[derive(SystemParam)]
 pub struct GameEvHandler<'w> {
     ev_handler: ResMut<'w, EventHandler>,
     resp_id: Single<'w, &'static mut RespId>,
+    seq: Single<'w, &'static mut GameEvSeq>,
 }
 
 impl GameEvHandler<'_> {
+    /// Returns the next `GameEvent` to apply.
+    ///
+    /// `GameEvent`s already applied are not returned again, and a gap in the sequence
+    /// makes the client request a resync, unless spectating.
+    /// While a response is pending, only the duplicates of the current `GameEvent` are taken.
     pub fn recv_game_ev(&mut self) -> Option<GameEvent> {
-        let (id, ev) = self
-            .ev_handler
-            .storage
-            .take_request_if(InboundEvent::is_game_event)?;
+        loop {
+            let last_applied = self.seq.last_applied;
+            let pending = self.is_response_pending();
+
+            let (id, ev) = self.ev_handler.storage.take_request_if(|ev| {
+                ev.game_event_seq()
+                    .is_some_and(|seq| !pending || Some(seq) <= last_applied)
+            })?;
+            let seq = ev.game_event_seq().expect("should be a `GameEvent`");
+
+            match last_applied {
+                Some(last) if seq == last => {
+                    // Resent after reconnection; respond to the new ID instead of applying again.
+                    debug!("received GameEvent #{} again", seq);
+                    if pending {
+                        self.resp_id.0 = Some(id);
+                    } else if let Some(resp) = self.seq.last_resp.clone() {
+                        self.resp_id.0 = Some(id);
+                        self.send_game_ev(resp);
+                    }
+                }
+                Some(last) if seq < last => {
+                    debug!("ignoring GameEvent #{} already applied", seq);
+                }
+                Some(last) if seq > last + 1 && !self.seq.spectating => {
+                    if !self.seq.resync_requested {
+                        warn!(
+                            "missed GameEvents #{}..#{}; requesting a resync",
+                            last + 1,
+                            seq
+                        );
+                        self.request_resync(last);
+                    }
+                }
+                _ => {
+                    let ev = ev.into_game_event();
+                    self.seq.last_applied = Some(seq);
+                    self.seq.last_resp = None;
+                    self.seq.resync_requested = false;
+                    self.seq.auto_acked = self.seq.auto_ack && !ev.is_decision_required();
+                    self.resp_id.0 = Some(id);
+
+                    return Some(ev);
+                }
+            }
+        }
+    }
 
-        self.resp_id.0 = Some(id);
+    /// Asks the server to resend the `GameEvent`s following `last_seq`.
+    fn request_resync(&mut self, last_seq: u32) {
+        match self
+            .ev_handler
+            .send_request(OutboundEvent::RequestResync { last_seq })
+        {
+            Ok(_) => self.seq.resync_requested = true,
+            // Possibly disconnected from the server; retried on the next gap.
+            Err(e) => warn!("failed to request a resync: {}", e),
+        }
+    }
 
-        Some(ev.into_game_event())
+    /// Returns `true` if the last received `GameEvent` has not been responded yet.
+    pub fn is_response_pending(&self) -> bool {
+        self.resp_id.0.is_some()
     }
 
     pub fn send_game_ev(&mut self, event: GameEvent) {
@@ -64,8 +152,18 @@ impl GameEvHandler<'_> {
             );
             return;
         };
-        self.ev_handler
+        if self.seq.auto_acked {
+            // Done with the event; the server has moved on without waiting for me.
+            return;
+        }
+        self.seq.last_resp = Some(event.clone());
+
+        if let Err(e) = self
+            .ev_handler
             .send_response(id, OutboundEvent::GameEventResponse(event))
-            .unwrap();
+        {
+            // Possibly disconnected from the server.
+            warn!("failed to send GameEvent response: {}", e);
+        }
     }
 }
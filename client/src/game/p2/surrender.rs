@@ -0,0 +1,110 @@
+use super::P2_CTX_STATE;
+use crate::{
+    game::dialog::{Dialog, DialogButton},
+    JoinedPlayers, WatchingReplay,
+};
+use bevy::prelude::*;
+use client::{
+    client::OutboundEvent,
+    log_macros::*,
+    utils::{
+        log_display::{LogEvent, Message},
+        AddObserverExt as _,
+    },
+    EventHandler,
+};
+
+pub fn surrender_plugin(app: &mut App) {
+    app.add_systems(OnEnter(P2_CTX_STATE), setup)
+        .add_state_scoped_observer_named(P2_CTX_STATE, send_surrender);
+}
+
+#[derive(Component)]
+struct SurrenderButton;
+
+#[derive(Component)]
+struct SurrenderDialog;
+
+/// Sends the surrender to the server.
+#[derive(Event)]
+struct Surrender;
+
+fn setup(
+    mut commands: Commands,
+    joined_players: Res<JoinedPlayers>,
+    replay: Option<Res<WatchingReplay>>,
+) {
+    // Only players can surrender.
+    if joined_players.spectating || replay.is_some() {
+        return;
+    }
+
+    commands
+        .spawn((
+            StateScoped(P2_CTX_STATE),
+            SurrenderButton,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(8.0),
+                top: Val::Px(8.0),
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+            Button,
+            BackgroundColor(Color::srgba_u8(160, 40, 40, 200)),
+            BorderRadius::all(Val::Px(4.0)),
+            Name::new("SurrenderButton"),
+        ))
+        .with_child((
+            Text::new("Surrender"),
+            TextFont {
+                font_size: 20.0,
+                ..default()
+            },
+        ))
+        .observe(confirm_surrender);
+}
+
+fn confirm_surrender(
+    _trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    dialog: Query<(), With<SurrenderDialog>>,
+) {
+    if !dialog.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        StateScoped(P2_CTX_STATE),
+        SurrenderDialog,
+        Dialog::new(
+            None,
+            [
+                DialogButton::new(
+                    "Surrender",
+                    |commands| commands.trigger(Surrender),
+                    default(),
+                ),
+                DialogButton::new("Cancel", |_| (), default()),
+            ],
+        ),
+        Transform::from_xyz(0.0, 0.0, 10.0),
+    ));
+}
+
+fn send_surrender(
+    _trigger: Trigger<Surrender>,
+    mut ev_handler: ResMut<EventHandler>,
+    button: Option<Single<Entity, With<SurrenderButton>>>,
+    mut commands: Commands,
+) {
+    if let Err(e) = ev_handler.send_request(OutboundEvent::Surrender) {
+        display_error!(commands, "failed to surrender: {}", e);
+        return;
+    }
+
+    // The game is over once the server accepts it.
+    if let Some(button) = button {
+        commands.entity(*button).despawn_recursive();
+    }
+}
@@ -0,0 +1,87 @@
+use super::P2_CTX_STATE;
+use crate::game::{
+    card_field::{CardField, MyCardField},
+    CARD_HEIGHT, CARD_Z_GAP_RATIO, HALF_CARD_DEPTH,
+};
+use bevy::prelude::*;
+use client::utils::AddObserverExt as _;
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+const HIGHLIGHT_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.8);
+const HIGHLIGHT_LINE_WIDTH: f32 = 3.0;
+const PULSE_SECS: f32 = 2.0;
+
+pub fn turn_highlight_plugin(app: &mut App) {
+    app.insert_gizmo_config(
+        TurnHighlightGizmos,
+        GizmoConfig {
+            line_width: HIGHLIGHT_LINE_WIDTH,
+            ..default()
+        },
+    )
+    .add_state_scoped_observer_named(P2_CTX_STATE, ShowTurnHighlight::show)
+    .add_state_scoped_observer_named(P2_CTX_STATE, HideTurnHighlight::hide)
+    .add_systems(Update, TurnHighlight::draw.run_if(in_state(P2_CTX_STATE)));
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct TurnHighlightGizmos;
+
+/// A border around my field, drawn throughout my turn.
+#[derive(Component)]
+struct TurnHighlight;
+
+impl TurnHighlight {
+    fn draw(
+        mut gizmos: Gizmos<TurnHighlightGizmos>,
+        time: Res<Time>,
+        field: Option<Single<(&CardField, &GlobalTransform), (With<MyCardField>, With<Self>)>>,
+    ) {
+        let Some(field) = field else {
+            return;
+        };
+        let (field, transform) = *field;
+
+        let size = Vec2::new(field.width(), CARD_HEIGHT * (1.0 + CARD_Z_GAP_RATIO * 2.0));
+
+        // Lay the rectangle on the table, around the cards.
+        let isometry = Isometry3d::new(
+            transform.translation() - Vec3::Y * HALF_CARD_DEPTH,
+            transform.rotation() * Quat::from_rotation_x(FRAC_PI_2),
+        );
+
+        // Pulse gently between 60% and 100% of the alpha.
+        let pulse = 0.8 + 0.2 * (time.elapsed_secs() * TAU / PULSE_SECS).sin();
+        let color = HIGHLIGHT_COLOR.with_alpha(HIGHLIGHT_COLOR.alpha() * pulse);
+
+        gizmos.rect(isometry, size, color);
+    }
+}
+
+/// Highlights my field, as my turn has started.
+#[derive(Event)]
+pub(super) struct ShowTurnHighlight;
+
+impl ShowTurnHighlight {
+    fn show(
+        _trigger: Trigger<Self>,
+        field: Single<Entity, With<MyCardField>>,
+        mut commands: Commands,
+    ) {
+        commands.entity(*field).insert(TurnHighlight);
+    }
+}
+
+/// Removes the highlight from my field, as my turn has ended.
+#[derive(Event)]
+pub(super) struct HideTurnHighlight;
+
+impl HideTurnHighlight {
+    fn hide(
+        _trigger: Trigger<Self>,
+        field: Single<Entity, With<MyCardField>>,
+        mut commands: Commands,
+    ) {
+        commands.entity(*field).remove::<TurnHighlight>();
+    }
+}
@@ -0,0 +1,72 @@
+use super::{super::P2State, P2_CTX_STATE};
+use crate::WatchingReplay;
+use bevy::prelude::*;
+use client::EventHandler;
+
+const COLOR_CONNECTED: Color = Color::srgb_u8(0x2E, 0x9E, 0x4F);
+const COLOR_RECONNECTING: Color = Color::srgb_u8(0xD9, 0xA4, 0x1A);
+const COLOR_DISCONNECTED: Color = Color::srgb_u8(0xB5, 0x36, 0x36);
+
+pub fn connection_status_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        ConnectionStatusBadge::update.run_if(in_state(P2_CTX_STATE)),
+    );
+}
+
+/// Shows the state of the connection to the server, with the round-trip time while connected.
+#[derive(Component)]
+pub(super) struct ConnectionStatusBadge;
+
+impl ConnectionStatusBadge {
+    pub(super) fn components() -> impl Bundle {
+        (
+            Node {
+                height: Val::Px(60.0),
+                min_width: Val::Px(80.0),
+                padding: UiRect::horizontal(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(COLOR_CONNECTED),
+            BorderRadius::all(Val::Px(4.0)),
+            Text::new("--"),
+            TextFont::from_font_size(20.0),
+            Self,
+            Name::new("ConnectionStatusBadge"),
+        )
+    }
+
+    fn update(
+        mut badge: Single<(&mut Text, &mut BackgroundColor, &mut Visibility), With<Self>>,
+        state: Res<State<P2State>>,
+        ev_handler: Option<Res<EventHandler>>,
+        replay: Option<Res<WatchingReplay>>,
+    ) {
+        let (ref mut text, ref mut bg_color, ref mut vis) = *badge;
+
+        // A replay has no connection to show.
+        if replay.is_some() {
+            vis.set_if_neq(Visibility::Hidden);
+            return;
+        }
+
+        let (label, color) = match state.get() {
+            P2State::Reconnecting => ("reconnecting".into(), COLOR_RECONNECTING),
+            P2State::Disconnected => ("offline".into(), COLOR_DISCONNECTED),
+            _ => {
+                let label = match ev_handler.and_then(|v| v.round_trip_time()) {
+                    Some(rtt) => format!("{}ms", rtt.as_millis()),
+                    None => "--".into(),
+                };
+                (label, COLOR_CONNECTED)
+            }
+        };
+
+        if text.0 != label {
+            text.0 = label;
+        }
+        bg_color.set_if_neq(BackgroundColor(color));
+    }
+}
@@ -35,19 +35,21 @@ pub fn history_plugin(app: &mut App) {
     )
     .add_observer(CardSnapshot::on_add_card_snapshot)
     .add_observer(HistoryBgColor::set_bg_color)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::initial_cards)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::turn_started)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::draw)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::attack_target_selected)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::number_guessed)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::text_events)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::card_revealed)
-    .add_state_scoped_observer_named(P2_CTX_STATE, PushHistory::attacker_inserted_to_field)
-    .add_state_scoped_observer_named(
+    .add_state_scoped_observers_named(
         P2_CTX_STATE,
-        SpawnLabeledCardSnapshot::spawn_labeled_card_2d,
-    )
-    .add_state_scoped_observer_named(P2_CTX_STATE, SpawnMessage::spawn_message);
+        (
+            PushHistory::initial_cards,
+            PushHistory::turn_started,
+            PushHistory::draw,
+            PushHistory::attack_target_selected,
+            PushHistory::number_guessed,
+            PushHistory::text_events,
+            PushHistory::card_revealed,
+            PushHistory::attacker_inserted_to_field,
+            SpawnLabeledCardSnapshot::spawn_labeled_card_2d,
+            SpawnMessage::spawn_message,
+        ),
+    );
 }
 
 #[derive(Component)]
@@ -97,12 +99,13 @@ impl CardSnapshot {
         let card_color = card.pub_info.color;
         let img = card_materials.get_or_create_card_image(
             card_color,
-            card.priv_info.map(|v| v.number),
+            card.number_known(),
             &mut images,
             &mut materials,
         );
 
-        let bg_color = card_color.bg_color_rgb().into_color();
+        let rgb = card_materials.card_rgb(card_color);
+        let bg_color = rgb.bg.into_color();
         let border_color = MENTION_OUTLINE_COLOR;
 
         commands
@@ -138,25 +141,26 @@ impl CardSnapshot {
                         PickingBehavior::IGNORE,
                     ))
                     .with_children(|parent| {
-                        let Some(img) = img else {
-                            return;
-                        };
-
                         if card.pub_info.revealed {
+                            let Some(img) = img else {
+                                return;
+                            };
                             parent.spawn((ImageNode::new(img), PickingBehavior::IGNORE));
                         } else {
-                            let text_color = card_color.text_color_rgb().into_color();
-                            let card_number = card.priv_info.unwrap().number.0;
+                            let text_color = rgb.text.into_color();
+                            let label = card
+                                .number_known()
+                                .map_or_else(|| "?".to_string(), |n| n.0.to_string());
 
-                            // The card is not revealed but the player knows its number.
-                            // Draw a number in small size.
+                            // The card is not revealed, so draw its number in small size
+                            // if the player knows it, or "?" otherwise.
                             parent.spawn((
                                 Node {
                                     justify_self: JustifySelf::Center,
                                     align_self: AlignSelf::End,
                                     ..default()
                                 },
-                                Text(card_number.to_string()),
+                                Text(label),
                                 TextColor(text_color),
                                 PickingBehavior::IGNORE,
                             ));
@@ -232,6 +236,8 @@ pub enum PushHistory {
     AttackFailed,
     CardRevealed(CardSnapshotBuilder),
     AttackerInsertedToField(CardSnapshotBuilder),
+    GameDrawn,
+    Surrendered,
 }
 
 type HistoryParentQuery<'w> = Single<'w, Entity, With<CurrentHistoryUiParent>>;
@@ -306,6 +312,8 @@ impl PushHistory {
         let message = match trigger.event() {
             Self::AttackSucceeded => "- Succeeded!",
             Self::AttackFailed => "- Failed!",
+            Self::GameDrawn => "Draw!",
+            Self::Surrendered => "Surrendered!",
             _ => return,
         };
         commands.trigger(SpawnMessage(message.into()));
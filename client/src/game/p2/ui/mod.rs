@@ -16,6 +16,9 @@ use popup::PopupUiAnchor;
 pub mod history;
 use history::HistoryUiAnchor;
 
+mod connection_status;
+use connection_status::ConnectionStatusBadge;
+
 const ICON_NOTES: &str = "tabler-icons/notes.png";
 const ICON_NOTES_OFF: &str = "tabler-icons/notes-off.png";
 const ICON_HISTORY: &str = "tabler-icons/history.png";
@@ -25,17 +28,21 @@ const ICON_ARROW_LEFT: &str = "tabler-icons/arrow-bar-to-left.png";
 const ICON_ARROW_RIGHT: &str = "tabler-icons/arrow-bar-to-right.png";
 
 pub fn ui_plugin(app: &mut App) {
-    app.add_plugins((popup::popup_plugin, history::history_plugin))
-        .insert_resource(ImageHandles::new([
-            ICON_NOTES,
-            ICON_NOTES_OFF,
-            ICON_HISTORY,
-            ICON_HISTORY_OFF,
-            ICON_HELP,
-            ICON_ARROW_LEFT,
-            ICON_ARROW_RIGHT,
-        ]))
-        .add_systems(OnEnter(P2_CTX_STATE), setup);
+    app.add_plugins((
+        popup::popup_plugin,
+        history::history_plugin,
+        connection_status::connection_status_plugin,
+    ))
+    .insert_resource(ImageHandles::new([
+        ICON_NOTES,
+        ICON_NOTES_OFF,
+        ICON_HISTORY,
+        ICON_HISTORY_OFF,
+        ICON_HELP,
+        ICON_ARROW_LEFT,
+        ICON_ARROW_RIGHT,
+    ]))
+    .add_systems(OnEnter(P2_CTX_STATE), setup);
 }
 
 #[derive(Default, Component)]
@@ -170,6 +177,9 @@ fn setup(mut commands: Commands, mut images: ResMut<ImageHandles>, asset_server:
                         .with_children(|parent| {
                             parent.spawn((ImageNode::new(images.load(ICON_HELP, &asset_server)),));
                         });
+
+                    // Connection status
+                    parent.spawn(ConnectionStatusBadge::components());
                 });
         });
 
@@ -260,7 +270,8 @@ fn setup(mut commands: Commands, mut images: ResMut<ImageHandles>, asset_server:
                                                 height: Val::Percent(100.0),
                                                 flex_direction: FlexDirection::Column,
                                                 align_items: AlignItems::Center,
-                                                overflow: Overflow::scroll_y(),
+                                                // Wide entries can be scrolled sideways.
+                                                overflow: Overflow::scroll(),
                                                 // padding: UiRect::vertical(Val::Px(10.0)),
                                                 ..default()
                                             },
@@ -1,18 +1,23 @@
 use super::{
+    camera_control::CameraControlPlugin,
     card::{
         attacker::AttackTo,
         guessing::NumSelected,
         instance::{self as card_instance, CardInstance},
-        picking::PickableCard,
+        picking::{InteractionLocked, PickableCard},
     },
     card_field::{CardField, CardFieldOwnedBy, MyCardField},
     dialog::{Dialog, DialogButton, PopupMessageExt as _},
     GameMode, CARD_HEIGHT, CARD_Z_GAP_RATIO, HALF_CARD_DEPTH, TALON_TRANSLATION,
 };
-use crate::{game::card::guessing::SpawnNumSelector, AppState};
+use crate::{game::card::guessing::SpawnNumSelector, AppArgs, AppState};
 use algo_core::{
-    card::{CardColor, CardNumber, CardPrivInfo, CardPubInfo},
+    card::{
+        field_insert_index, Card, CardColor, CardNumber, CardNumberType, CardPrivInfo, CardPubInfo,
+        CardView,
+    },
     player::PlayerId,
+    settings::GameSettings,
 };
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 use client::utils::{
@@ -21,8 +26,12 @@ use client::utils::{
     AddObserverExt as _,
 };
 use itertools::Itertools as _;
-use rand::{rngs::ThreadRng, seq::IndexedRandom, Rng as _};
-use std::collections::{BTreeMap, BTreeSet};
+use rand::{
+    rngs::ThreadRng,
+    seq::{IndexedRandom, SliceRandom as _},
+    Rng as _,
+};
+use std::{collections::BTreeSet, str::FromStr};
 
 mod talon;
 use talon::{SandboxTalon, SpawnCards as _};
@@ -30,13 +39,14 @@ use talon::{SandboxTalon, SpawnCards as _};
 mod attacker;
 use attacker::{AddAttacker, Attacker, AttackerSettings, SandboxAttackerPlugin};
 
-mod camera_control;
-use camera_control::SandboxCameraControlPlugin;
-
 const SANDBOX_CTX_STATE: GameMode = GameMode::Sandbox;
 
 const INITIAL_DRAW_NUM_PER_PLAYER: usize = 4;
 
+/// Reveals all the opponent's cards, to check the win flow without playing it out.
+#[cfg(feature = "dev")]
+const KEY_REVEAL_ALL: KeyCode = KeyCode::KeyV;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, SubStates)]
 #[source(GameMode = GameMode::Sandbox)]
 enum SandboxState {
@@ -76,7 +86,10 @@ enum OpponentTurnState {
 
 pub fn game_sandbox_plugin(app: &mut App) {
     app.add_plugins((
-        SandboxCameraControlPlugin,
+        CameraControlPlugin {
+            ctx_state: SANDBOX_CTX_STATE,
+            limits: None,
+        },
         SandboxAttackerPlugin {
             settings: AttackerSettings {
                 my_attacker_xf: Transform::from_xyz(0.0, HALF_CARD_DEPTH, 0.0),
@@ -84,10 +97,6 @@ pub fn game_sandbox_plugin(app: &mut App) {
             },
         },
     ))
-    // .add_systems(
-    //     Update,
-    //     start_sandbox.run_if(in_state(AppState::Home).and(input_just_pressed(KeyCode::Enter))),
-    // )
     .add_sub_state::<SandboxState>()
     .enable_state_scoped_entities::<SandboxState>()
     .add_sub_state::<MyTurnState>()
@@ -154,6 +163,12 @@ pub fn game_sandbox_plugin(app: &mut App) {
         })
         .run_if(in_state(GameMode::Sandbox).and(input_just_pressed(KeyCode::KeyG))),
     );
+
+    #[cfg(feature = "dev")]
+    app.add_systems(
+        Update,
+        reveal_all.run_if(in_state(SandboxState::MyTurn).and(input_just_pressed(KEY_REVEAL_ALL))),
+    );
 }
 
 #[derive(Resource)]
@@ -181,15 +196,40 @@ impl Default for SandboxPlayers {
 #[derive(Deref, DerefMut, Resource)]
 struct CardPrivInfos(Vec<CardPrivInfo>);
 
+/// Records every guess made by both players.
+#[derive(Default, Deref, DerefMut, Resource)]
+struct GuessLog(Vec<GuessRecord>);
+
+#[derive(Debug, Clone, Copy)]
+struct GuessRecord {
+    target_card: Entity,
+    guess: CardNumber,
+    succeeded: bool,
+}
+
+impl GuessLog {
+    /// Returns numbers that were guessed wrongly on the card.
+    fn wrong_guesses(&self, target_card: Entity) -> impl Iterator<Item = CardNumber> + '_ {
+        self.iter()
+            .filter(move |record| record.target_card == target_card && !record.succeeded)
+            .map(|record| record.guess)
+    }
+}
+
 #[derive(Deref, DerefMut, Component)]
 struct HiddenCardPrivInfo(CardPrivInfo);
 
 #[derive(Default, Component)]
 struct SortFieldCards {
-    cards: Vec<(CardNumber, CardColor)>,
+    cards: Vec<Card>,
 }
 
-fn init_sandbox_resources(mut commands: Commands, mut talon: NonSendMut<Option<SandboxTalon>>) {
+fn init_sandbox_resources(
+    mut commands: Commands,
+    mut talon: NonSendMut<Option<SandboxTalon>>,
+    mut simulator: NonSendMut<OpponentSimulator>,
+    difficulty: Option<Res<AiDifficulty>>,
+) {
     let mut cards = talon::Real.produce_cards();
 
     // DEBUG
@@ -208,6 +248,10 @@ fn init_sandbox_resources(mut commands: Commands, mut talon: NonSendMut<Option<S
     *talon = Some(SandboxTalon::new(cards));
 
     commands.insert_resource(CardPrivInfos(priv_infos));
+    commands.insert_resource(GuessLog::default());
+
+    simulator.difficulty = difficulty.map_or_else(default, |v| *v);
+    info!("AI difficulty: {:?}", simulator.difficulty);
 }
 
 fn setup_sandbox(
@@ -344,25 +388,20 @@ impl InsertCardToField {
         let card_info = {
             let (card, hidden) = cards.get(card_entity).unwrap();
             let card = card.get();
-            (
-                match (card.priv_info, hidden) {
-                    (Some(v), _) => v.number,
-                    (None, Some(v)) => v.0.number,
-                    _ => {
-                        warn!("could not get CardPrivInfo");
-                        return;
-                    }
-                },
-                card.pub_info.color,
-            )
+            let number = match (card.priv_info, hidden) {
+                (Some(v), _) => v.number,
+                (None, Some(v)) => v.0.number,
+                _ => {
+                    warn!("could not get CardPrivInfo");
+                    return;
+                }
+            };
+            Card::new(number, card.pub_info.color)
         };
 
         // Find a correct spot for the card
         let (mut field, mut sorter) = fields.get_mut(field_entity).unwrap();
-        let Err(idx) = sorter.cards.binary_search(&card_info) else {
-            warn!("Card duplicate found: {:?}", card_info);
-            return;
-        };
+        let idx = field_insert_index(&sorter.cards, &card_info);
 
         // Insert the card
         sorter.cards.insert(idx, card_info);
@@ -440,9 +479,37 @@ fn attack_target_selected(
     mut commands: Commands,
     selectable_cards: Query<Entity, With<Selectable>>,
     attacker: Single<Entity, With<Attacker>>,
+    locked: Option<Res<InteractionLocked>>,
+    cards: Query<&CardInstance>,
+    guess_log: Res<GuessLog>,
+    app_args: Res<AppArgs>,
 ) {
+    if !InteractionLocked::try_lock(&locked, &mut commands) {
+        return;
+    }
+
     let selected = trigger.entity();
 
+    if app_args.guess_odds {
+        // My cards and the revealed ones are the cards whose numbers I know.
+        let known_cards = cards
+            .iter()
+            .map(CardInstance::get)
+            .filter(|card| card.priv_info.is_some())
+            .copied()
+            .collect::<Vec<_>>();
+
+        if let Ok(target) = cards.get(selected) {
+            let candidates = guess_candidates(
+                &known_cards,
+                selected,
+                target.get().pub_info.color,
+                &guess_log,
+            );
+            show_guess_odds(&mut commands, "You", candidates.len());
+        }
+    }
+
     // Block interaction
     for entity in &selectable_cards {
         commands
@@ -455,7 +522,10 @@ fn attack_target_selected(
     commands
         .entity(selected)
         .insert(AttackTarget)
-        .trigger(SpawnNumSelector)
+        .trigger(SpawnNumSelector {
+            numbers: GameSettings::default().card_numbers(),
+            disabled: default(),
+        })
         .trigger(ObserveOnce::<NumSelected>::new(Observer::new(num_selected)));
 
     // Move Attacker
@@ -471,11 +541,18 @@ fn num_selected(
     trigger: Trigger<NumSelected>,
     query: Query<&HiddenCardPrivInfo>,
     mut my_turn_state: ResMut<NextState<MyTurnState>>,
+    mut guess_log: ResMut<GuessLog>,
 ) {
     let attacked = trigger.entity();
     let guess = trigger.event().0;
     let hidden_num = query.get(attacked).unwrap().number;
 
+    guess_log.push(GuessRecord {
+        target_card: attacked,
+        guess,
+        succeeded: guess == hidden_num,
+    });
+
     my_turn_state.set(if guess == hidden_num {
         MyTurnState::AttackSucceeded
     } else {
@@ -541,6 +618,26 @@ fn check_win_condition(
     });
 }
 
+#[cfg(feature = "dev")]
+fn reveal_all(
+    mut commands: Commands,
+    hidden_cards: Query<(Entity, &HiddenCardPrivInfo), With<OpponentCard>>,
+) {
+    info!("revealing all the opponent's cards");
+
+    for (entity, hidden_info) in &hidden_cards {
+        commands
+            .entity(entity)
+            .remove::<(HiddenCardPrivInfo, Selectable)>()
+            .trigger(card_instance::RevealWith(CardPrivInfo::new(
+                hidden_info.0.number,
+            )))
+            .trigger(observer_controller::Remove::<Pointer<Click>>::new());
+    }
+
+    commands.trigger(SetTimeout::new(0.5).with_state(MyTurnState::CheckWinCondition));
+}
+
 fn choose_attack_or_stay(mut commands: Commands) {
     commands.spawn((
         Dialog::new(
@@ -550,14 +647,17 @@ fn choose_attack_or_stay(mut commands: Commands) {
                     "Attack",
                     |commands| commands.set_state(MyTurnState::Attack),
                     default(),
-                ),
+                )
+                .with_hotkey(KeyCode::KeyA),
                 DialogButton::new(
                     "Stay",
                     |commands| commands.set_state(MyTurnState::Stay),
                     default(),
-                ),
+                )
+                .with_hotkey(KeyCode::KeyS),
             ],
-        ),
+        )
+        .with_default(0),
         Transform::from_xyz(0.0, -80.0, 0.0),
     ));
 }
@@ -705,17 +805,56 @@ fn opponent_win(mut commands: Commands) {
     commands.trigger(SetTimeout::new(duration_secs).with_state(AppState::Home));
 }
 
+/// How well the [`OpponentSimulator`] plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
+pub(crate) enum AiDifficulty {
+    /// Guesses any number at random, ignoring what is known.
+    Easy,
+    /// Guesses from the numbers that can be on the target card.
+    #[default]
+    Normal,
+    /// Also deduces from the order of the cards,
+    /// and attacks again more often when close to winning.
+    Hard,
+}
+
+impl AiDifficulty {
+    /// Returns the next difficulty, going back to the easiest after the hardest.
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::Easy => Self::Normal,
+            Self::Normal => Self::Hard,
+            Self::Hard => Self::Easy,
+        }
+    }
+}
+
+impl FromStr for AiDifficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Self::Easy),
+            "normal" => Ok(Self::Normal),
+            "hard" => Ok(Self::Hard),
+            _ => Err(format!("unknown AI difficulty: {}", s)),
+        }
+    }
+}
+
 /// This simulator guesses numbers using only the information  
 /// from the cards visible to the simulated player.  
 ///
 /// If the guess is correct, there is a 50% chance that the simulator  
 /// will attack again.  
 ///
-/// More advanced simulators may also utilize the following information  
-/// for number predictions:  
-/// - The history of actions taken by both players (e.g., guessed numbers)
+/// Numbers that have already been guessed wrongly on the target card
+/// are excluded, using the [`GuessLog`].
+///
+/// The behavior above is for [`AiDifficulty::Normal`].
 struct OpponentSimulator {
     rng: ThreadRng,
+    difficulty: AiDifficulty,
     attacker: Option<Entity>,
     attack_target: Option<Entity>,
 }
@@ -724,6 +863,7 @@ impl OpponentSimulator {
     fn new() -> Self {
         Self {
             rng: rand::rng(),
+            difficulty: AiDifficulty::default(),
             attacker: None,
             attack_target: None,
         }
@@ -733,34 +873,62 @@ impl OpponentSimulator {
         mut this: NonSendMut<OpponentSimulator>,
         mut commands: Commands,
         cards: Query<(Entity, &CardInstance, Option<&HiddenCardPrivInfo>)>,
+        my_field: Single<&CardField, With<MyCardField>>,
+        mut guess_log: ResMut<GuessLog>,
+        app_args: Res<AppArgs>,
     ) {
         let mut attack_targets = Vec::new();
-        let mut numbers = BTreeMap::from([
-            (CardColor::Black, BTreeSet::from_iter(0..12)),
-            (CardColor::White, BTreeSet::from_iter(0..12)),
-        ]);
+        let all_numbers = BTreeSet::from_iter(GameSettings::default().card_numbers());
+        // The cards whose numbers the simulated player knows.
+        let mut known_cards = Vec::new();
 
         for (entity, card, hidden_info) in &cards {
             let card = card.get();
             let CardPubInfo { color, revealed } = card.pub_info;
 
             if let Some(hidden_info) = hidden_info {
-                numbers
-                    .get_mut(&color)
-                    .unwrap()
-                    .remove(&hidden_info.number.0);
+                known_cards.push(CardView::from_props(color, Some(hidden_info.number), false));
             } else if revealed {
-                numbers
-                    .get_mut(&color)
-                    .unwrap()
-                    .remove(&card.priv_info.unwrap().number.0);
+                known_cards.push(*card);
             } else if card.priv_info.is_some() {
                 attack_targets.push((entity, *card));
             }
         }
 
-        // Choose attack target;
-        let (attack_target_entity, target_card) = *attack_targets.choose(&mut this.rng).unwrap();
+        let candidates_of = |entity: Entity, card: &CardView| {
+            guess_candidates(&known_cards, entity, card.pub_info.color, &guess_log)
+        };
+
+        // Choose attack target and candidate numbers
+        let (attack_target_entity, target_card, candidates) = match this.difficulty {
+            AiDifficulty::Easy => {
+                let (entity, card) = *attack_targets.choose(&mut this.rng).unwrap();
+                (entity, card, all_numbers)
+            }
+            AiDifficulty::Normal => {
+                let (entity, card) = *attack_targets.choose(&mut this.rng).unwrap();
+                (entity, card, candidates_of(entity, &card))
+            }
+            AiDifficulty::Hard => {
+                // Attack the card with the fewest candidates, breaking ties at random.
+                attack_targets.shuffle(&mut this.rng);
+                attack_targets
+                    .iter()
+                    .map(|(entity, card)| {
+                        let mut candidates = candidates_of(*entity, card);
+                        let (lower, upper) = order_bounds(&my_field, *entity, &cards);
+                        let color = card.pub_info.color;
+                        candidates.retain(|n| {
+                            lower.is_none_or(|v| (*n, color) > v)
+                                && upper.is_none_or(|v| (*n, color) < v)
+                        });
+                        (*entity, *card, candidates)
+                    })
+                    .min_by_key(|(_, _, candidates)| candidates.len())
+                    .unwrap()
+            }
+        };
+
         commands.trigger_targets(
             AttackTo {
                 target_card: attack_target_entity,
@@ -768,10 +936,12 @@ impl OpponentSimulator {
             this.attacker.unwrap(),
         );
 
+        if app_args.guess_odds {
+            show_guess_odds(&mut commands, "Opponent", candidates.len());
+        }
+
         // Choose number
-        let guess = **numbers
-            .remove(&target_card.pub_info.color)
-            .unwrap()
+        let guess = **candidates
             .iter()
             .collect::<Vec<_>>()
             .choose(&mut this.rng)
@@ -789,7 +959,14 @@ impl OpponentSimulator {
         }));
 
         // Compare attack result
-        let next_state = if target_card.priv_info.unwrap().number == guess {
+        let succeeded = target_card.priv_info.unwrap().number == guess;
+        guess_log.push(GuessRecord {
+            target_card: attack_target_entity,
+            guess: CardNumber(guess),
+            succeeded,
+        });
+
+        let next_state = if succeeded {
             this.attack_target = Some(attack_target_entity);
             OpponentTurnState::AttackSucceeded
         } else {
@@ -798,8 +975,26 @@ impl OpponentSimulator {
         commands.trigger(SetTimeout::new(1.0).with_state(next_state));
     }
 
-    fn choose_attack_or_stay(mut this: NonSendMut<OpponentSimulator>, mut commands: Commands) {
-        let (next_state, msg) = if this.rng.random() {
+    fn choose_attack_or_stay(
+        mut this: NonSendMut<OpponentSimulator>,
+        mut commands: Commands,
+        my_cards: Query<&CardInstance, With<MyCard>>,
+    ) {
+        let attack_again = match this.difficulty {
+            AiDifficulty::Easy | AiDifficulty::Normal => this.rng.random(),
+            AiDifficulty::Hard => {
+                // The closer to revealing all the cards, the more likely to attack again.
+                let total = my_cards.iter().count().max(1);
+                let revealed = my_cards
+                    .iter()
+                    .filter(|card| card.get().pub_info.revealed)
+                    .count();
+                let p = (revealed as f64 / total as f64).clamp(0.25, 0.9);
+                this.rng.random_bool(p)
+            }
+        };
+
+        let (next_state, msg) = if attack_again {
             (OpponentTurnState::Attack, "Attack Again")
         } else {
             (OpponentTurnState::Stay, "Stay")
@@ -815,3 +1010,206 @@ impl OpponentSimulator {
         commands.trigger(SetTimeout::new(0.5).with_state(next_state));
     }
 }
+
+/// Returns the numbers that a hidden card of `color` can have,
+/// given the cards whose numbers are known.
+fn candidate_numbers<'a>(
+    known_cards: impl IntoIterator<Item = &'a CardView>,
+    color: CardColor,
+) -> BTreeSet<CardNumberType> {
+    let mut candidates = BTreeSet::from_iter(GameSettings::default().card_numbers());
+
+    for card in known_cards {
+        if card.pub_info.color != color {
+            continue;
+        }
+        if let Some(priv_info) = card.priv_info {
+            candidates.remove(&priv_info.number.0);
+        }
+    }
+
+    candidates
+}
+
+/// Returns the numbers that the hidden card can have, excluding the ones
+/// that have already been guessed wrongly on it.
+fn guess_candidates<'a>(
+    known_cards: impl IntoIterator<Item = &'a CardView>,
+    target_card: Entity,
+    color: CardColor,
+    guess_log: &GuessLog,
+) -> BTreeSet<CardNumberType> {
+    let mut candidates = candidate_numbers(known_cards, color);
+    for wrong_guess in guess_log.wrong_guesses(target_card) {
+        candidates.remove(&wrong_guess.0);
+    }
+
+    candidates
+}
+
+/// Shows the chance of guessing the attacked card correctly, as a learning aid.
+fn show_guess_odds(commands: &mut Commands, who: &str, candidates: usize) {
+    commands
+        .spawn((
+            StateScoped(SANDBOX_CTX_STATE),
+            Transform::from_xyz(0.0, 80.0, 0.0),
+        ))
+        .insert_popup_message(guess_odds_message(who, candidates), 1.5);
+}
+
+/// Describes the chance of picking the right number out of the candidates.
+fn guess_odds_message(who: &str, candidates: usize) -> String {
+    let percent = 100.0 / candidates.max(1) as f32;
+    format!("{}: 1 of {} candidates -> {:.0}%", who, candidates, percent)
+}
+
+/// Returns the nearest revealed cards on both sides of the target card,
+/// which bound the number of the target card.
+fn order_bounds(
+    field: &CardField,
+    target: Entity,
+    cards: &Query<(Entity, &CardInstance, Option<&HiddenCardPrivInfo>)>,
+) -> (
+    Option<(CardNumberType, CardColor)>,
+    Option<(CardNumberType, CardColor)>,
+) {
+    let revealed = |entity: &Entity| {
+        let card = cards.get(*entity).ok()?.1.get();
+        card.pub_info
+            .revealed
+            .then(|| (card.priv_info?.number.0, card.pub_info.color))
+    };
+
+    let Some(idx) = field.cards().iter().position(|e| *e == target) else {
+        return (None, None);
+    };
+    let (left, right) = field.cards().split_at(idx);
+
+    (
+        left.iter().rev().find_map(revealed),
+        right[1..].iter().find_map(revealed),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, seq::IndexedRandom as _, Rng as _, SeedableRng as _};
+
+    const NO_CARDS: &[CardView] = &[];
+
+    #[test]
+    fn guess_odds_message_rounds_percent() {
+        assert_eq!(
+            guess_odds_message("You", 1),
+            "You: 1 of 1 candidates -> 100%"
+        );
+        assert_eq!(
+            guess_odds_message("You", 4),
+            "You: 1 of 4 candidates -> 25%"
+        );
+        assert_eq!(
+            guess_odds_message("Opponent", 3),
+            "Opponent: 1 of 3 candidates -> 33%"
+        );
+        assert_eq!(
+            guess_odds_message("You", 6),
+            "You: 1 of 6 candidates -> 17%"
+        );
+        assert_eq!(
+            guess_odds_message("You", 12),
+            "You: 1 of 12 candidates -> 8%"
+        );
+    }
+
+    #[test]
+    fn guess_odds_on_known_board() {
+        // Two black numbers are known: one revealed, one in my hand.
+        let cards = ["black-0", "black-(3)", "black-?", "white-5", "white-(7)"]
+            .map(|s| CardView::from_str(s).unwrap());
+
+        let candidates = candidate_numbers(&cards, CardColor::Black);
+        let expected = GameSettings::default()
+            .card_numbers()
+            .filter(|n| ![0, 3].contains(n))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(candidates, expected);
+
+        // A wrong guess on the target narrows it down further.
+        let target = Entity::from_raw(1);
+        let mut guess_log = GuessLog::default();
+        guess_log.push(GuessRecord {
+            target_card: target,
+            guess: CardNumber(5),
+            succeeded: false,
+        });
+        let candidates = guess_candidates(&cards, target, CardColor::Black, &guess_log);
+
+        // 12 numbers by default, less 0 and 3 known, less 5 guessed wrongly.
+        assert_eq!(
+            guess_odds_message("You", candidates.len()),
+            "You: 1 of 9 candidates -> 11%"
+        );
+    }
+
+    #[test]
+    fn wrong_guess_is_not_repeated_on_the_card() {
+        let target = Entity::from_raw(1);
+        let other = Entity::from_raw(2);
+        let mut guess_log = GuessLog::default();
+        guess_log.push(GuessRecord {
+            target_card: target,
+            guess: CardNumber(5),
+            succeeded: false,
+        });
+
+        let all = GameSettings::default().card_numbers().count();
+
+        let candidates = guess_candidates(NO_CARDS, target, CardColor::Black, &guess_log);
+        assert!(!candidates.contains(&5));
+        assert_eq!(candidates.len(), all - 1);
+
+        // The wrong guess says nothing about the other cards.
+        let candidates = guess_candidates(NO_CARDS, other, CardColor::Black, &guess_log);
+        assert!(candidates.contains(&5));
+    }
+
+    #[test]
+    fn guess_log_improves_accuracy() {
+        const RUNS: u64 = 2000;
+        const ATTEMPTS: usize = 6;
+
+        // Counts the runs where the hidden number is guessed within the attempts.
+        let hits = |use_guess_log: bool| {
+            let target = Entity::from_raw(1);
+            let no_log = GuessLog::default();
+
+            (0..RUNS)
+                .filter(|seed| {
+                    let mut rng = StdRng::seed_from_u64(*seed);
+                    let hidden = rng.random_range(GameSettings::default().card_numbers());
+                    let mut guess_log = GuessLog::default();
+
+                    (0..ATTEMPTS).any(|_| {
+                        let log = if use_guess_log { &guess_log } else { &no_log };
+                        let candidates = guess_candidates(NO_CARDS, target, CardColor::Black, log);
+                        let guess = **candidates
+                            .iter()
+                            .collect::<Vec<_>>()
+                            .choose(&mut rng)
+                            .unwrap();
+
+                        guess_log.push(GuessRecord {
+                            target_card: target,
+                            guess: CardNumber(guess),
+                            succeeded: guess == hidden,
+                        });
+                        guess == hidden
+                    })
+                })
+                .count()
+        };
+
+        assert!(hits(true) > hits(false));
+    }
+}
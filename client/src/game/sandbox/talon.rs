@@ -1,8 +1,11 @@
 use crate::{
-    game::{card::instance::CardInstance, CARD_DEPTH},
+    game::{card::instance::CardInstance, talon_card_offset},
     AppState,
 };
-use algo_core::card::{CardColor, CardNumber, CardPrivInfo, CardPubInfo, CardView};
+use algo_core::{
+    card::{CardColor, CardNumber, CardPrivInfo, CardPubInfo, CardView},
+    settings::GameSettings,
+};
 use bevy::prelude::*;
 use itertools::Itertools as _;
 use rand::{
@@ -71,7 +74,7 @@ impl Messy {
     fn get_messy_card(&mut self) -> CardView {
         let rng = &mut self.rng;
 
-        let color = *[CardColor::White, CardColor::Black].choose(rng).unwrap();
+        let color = *CardColor::ALL.choose(rng).unwrap();
         let number = rng.random_range(0..=87);
         let revealed = rng.random();
 
@@ -105,9 +108,10 @@ pub struct Real;
 
 impl SpawnCards for Real {
     fn produce_cards(&mut self) -> Vec<CardView> {
-        let mut cards = (0..=11)
-            .cartesian_product([CardColor::Black, CardColor::White])
-            .map(|(n, c)| CardView::from_props(c, Some(CardNumber(n)), false))
+        let settings = GameSettings::default();
+        let mut cards = CardNumber::all(settings.card_numbers())
+            .cartesian_product(settings.card_colors)
+            .map(|(n, c)| CardView::from_props(c, Some(n), false))
             .collect::<Vec<_>>();
 
         cards.shuffle(&mut rand::rng());
@@ -156,10 +160,14 @@ fn spawn_cards_impl<'a, 'w, 's, T>(
 ) -> impl Iterator<Item = Entity> + use<'a, 'w, 's, T>
 where
     T: IntoIterator<Item = CardView>,
+    T::IntoIter: ExactSizeIterator,
 {
-    cards.into_iter().enumerate().map(move |(i, card)| {
+    let cards = cards.into_iter();
+    let len = cards.len();
+
+    cards.enumerate().map(move |(i, card)| {
         let mut transform = at;
-        transform.translation.y += i as f32 * CARD_DEPTH;
+        transform.translation += talon_card_offset(i, len);
 
         let id = commands
             .spawn((
@@ -1,4 +1,7 @@
-use crate::{AppArgs, AppState, JoinedPlayers};
+use crate::{
+    game::{AiDifficulty, GameMode},
+    AppArgs, AppState, JoinedPlayers, JoinedServer, LoadedReplay, WatchingReplay,
+};
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 use bevy_simple_text_input::{
     TextInput, TextInputInactive, TextInputPlaceholder, TextInputSettings, TextInputTextColor,
@@ -17,7 +20,7 @@ use client::{
         AddObserverExt as _,
     },
 };
-use protocol::server_to_client::JoinInfo;
+use protocol::server_to_client::{JoinInfo, ServerError};
 use std::net::IpAddr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, SubStates)]
@@ -64,6 +67,7 @@ impl ComputedStates for ConnectedToServer {
 // TODO: use CONSTs instead of magic numbers for UI
 const POPUP_HEIGHT_PERCENT: f32 = 60.0;
 const POPUP_BG_COLOR_RGBA: [u8; 4] = [43, 43, 43, 240];
+const SERVER_FULL_NOTICE_COLOR: Color = Color::srgb(1.0, 0.8, 0.3);
 
 pub fn home_plugin(app: &mut App) {
     app.add_plugins((client_connection_plugin, common_button_plugin))
@@ -74,16 +78,15 @@ pub fn home_plugin(app: &mut App) {
         .add_computed_state::<ConnectedToServer>()
         .enable_state_scoped_entities::<ConnectedToServer>()
         .add_systems(OnEnter(AppState::Home), setup_home)
-        .add_systems(
-            Update,
-            start_sandbox_conditioned
-                .run_if(in_state(HomeState::Menu).and(input_just_pressed(KeyCode::Enter))),
-        )
         .add_systems(OnEnter(HomeState::JoiningServer), JoinedPlayers::setup)
         .add_systems(
             Update,
             (
                 button_system::<JoinServerButton>,
+                button_system::<SpectateButton>,
+                button_system::<PracticeButton>,
+                button_system::<DifficultyButton>,
+                button_system::<ReplayButton>,
                 button_system::<QuitButton>,
                 focus_text_input,
                 unfocus_text_input.run_if(input_just_pressed(MouseButton::Left)),
@@ -91,6 +94,10 @@ pub fn home_plugin(app: &mut App) {
                 .run_if(in_state(HomeState::Menu)),
         )
         .add_state_scoped_observer_named(HomeState::Menu, on_click_join_server_button)
+        .add_state_scoped_observer_named(HomeState::Menu, on_click_spectate_button)
+        .add_state_scoped_observer_named(HomeState::Menu, on_click_practice_button)
+        .add_state_scoped_observer_named(HomeState::Menu, on_click_difficulty_button)
+        .add_state_scoped_observer_named(HomeState::Menu, on_click_replay_button)
         .add_state_scoped_observer_named(HomeState::Menu, on_click_quit_button)
         .add_systems(OnEnter(HomeState::JoiningServer), setup_join_server_ui)
         .add_systems(OnEnter(JoiningServerState::Setup), setup_join_server)
@@ -117,6 +124,7 @@ pub fn home_plugin(app: &mut App) {
         .add_systems(OnEnter(JoiningServerState::Failed), modify_button_text)
         .add_state_scoped_observer_named(JoiningServerState::Joining, check_response_to_join)
         .add_state_scoped_observer_named(ConnectedToServer, check_if_disconnected)
+        .add_state_scoped_observer_named(ConnectedToServer, check_protocol_version)
         .add_state_scoped_observer_named(
             JoiningServerState::WaitingForOtherPlayers,
             check_new_players,
@@ -139,15 +147,38 @@ struct IpAddrTextInput;
 #[derive(Component)]
 struct JoinServerButton;
 
+#[derive(Component)]
+struct SpectateButton;
+
+/// Starts a game against the AI, without a server.
+#[derive(Component)]
+struct PracticeButton;
+
+/// Cycles the difficulty of the AI in the practice game.
+#[derive(Component)]
+struct DifficultyButton;
+
+#[derive(Component)]
+struct ReplayButton;
+
 #[derive(Component)]
 struct PopupCenterButton;
 
 #[derive(Component)]
 struct QuitButton;
 
-fn setup_home(mut commands: Commands, args: Res<AppArgs>) {
+fn setup_home(
+    mut commands: Commands,
+    args: Res<AppArgs>,
+    loaded_replay: Option<Res<LoadedReplay>>,
+    difficulty: Option<Res<AiDifficulty>>,
+) {
     let server_ip_text = args.server_ip.clone().unwrap_or_default();
 
+    // Keep the difficulty chosen last time.
+    let difficulty = difficulty.map_or_else(default, |v| *v);
+    commands.insert_resource(difficulty);
+
     commands
         .spawn((
             HomeWidget,
@@ -202,9 +233,24 @@ fn setup_home(mut commands: Commands, args: Res<AppArgs>) {
                         TextInputTextColor(Color::BLACK.into()),
                     ));
 
-                    spawn_common_button(parent, "Join", JoinServerButton);
+                    spawn_common_button(parent, "Play Online", JoinServerButton);
+                    spawn_common_button(parent, "Spectate", SpectateButton);
                 });
 
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(5.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_common_button(parent, "Practice", PracticeButton);
+                    spawn_common_button(parent, &difficulty_label(difficulty), DifficultyButton);
+                });
+
+            if loaded_replay.is_some() {
+                spawn_common_button(parent, "Watch Replay", ReplayButton);
+            }
+
             spawn_common_button(parent, "Quit", QuitButton);
         });
 }
@@ -243,10 +289,75 @@ fn on_click_quit_button(_trigger: Trigger<ButtonPressed<QuitButton>>, mut comman
 fn on_click_join_server_button(
     _trigger: Trigger<ButtonPressed<JoinServerButton>>,
     mut home_state: ResMut<NextState<HomeState>>,
+    mut commands: Commands,
 ) {
+    commands.insert_resource(JoinAs::Player);
     home_state.set(HomeState::JoiningServer);
 }
 
+fn on_click_spectate_button(
+    _trigger: Trigger<ButtonPressed<SpectateButton>>,
+    mut home_state: ResMut<NextState<HomeState>>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(JoinAs::Spectator);
+    home_state.set(HomeState::JoiningServer);
+}
+
+fn on_click_practice_button(
+    _trigger: Trigger<ButtonPressed<PracticeButton>>,
+    mut commands: Commands,
+) {
+    commands.set_state(AppState::Game);
+    commands.set_state(GameMode::Sandbox);
+}
+
+fn on_click_difficulty_button(
+    _trigger: Trigger<ButtonPressed<DifficultyButton>>,
+    mut difficulty: ResMut<AiDifficulty>,
+    button: Single<&Children, With<DifficultyButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    *difficulty = difficulty.next();
+
+    if let Some(mut text) = button.first().and_then(|e| texts.get_mut(*e).ok()) {
+        text.0 = difficulty_label(*difficulty);
+    }
+}
+
+fn difficulty_label(difficulty: AiDifficulty) -> String {
+    format!("AI: {:?}", difficulty)
+}
+
+fn on_click_replay_button(
+    _trigger: Trigger<ButtonPressed<ReplayButton>>,
+    loaded_replay: Res<LoadedReplay>,
+    mut joined_players: ResMut<JoinedPlayers>,
+    mut commands: Commands,
+) {
+    let replay = loaded_replay.0.clone();
+
+    // Watch from the recorded viewer's side, without making any decisions.
+    let (a, b) = replay.players;
+    let players = if replay.viewer == Some(b) {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    *joined_players = default();
+    joined_players.set_spectated_players(players);
+
+    commands.insert_resource(WatchingReplay(replay));
+    commands.set_state(AppState::Game);
+}
+
+/// How the client takes part in the game on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+enum JoinAs {
+    Player,
+    Spectator,
+}
+
 fn setup_join_server_ui(mut commands: Commands) {
     commands
         .spawn((
@@ -308,7 +419,19 @@ fn setup_join_server(
 
     display_info!(commands, "joining the server...\nIP address: {}", addr);
 
-    spawn_client(&mut commands, addr, app_args.server_port);
+    commands.insert_resource(JoinedServer {
+        addr,
+        port: app_args.server_port,
+    });
+
+    spawn_client(
+        &mut commands,
+        addr,
+        app_args.server_port,
+        app_args.connect_retry_settings(),
+        app_args.wire_format,
+        app_args.read_buf_size,
+    );
     state.set(JoiningServerState::Connecting);
 }
 
@@ -317,6 +440,8 @@ fn wait_for_connection(
     mut reader: EventReader<SpawnClientResult>,
     mut state: ResMut<NextState<JoiningServerState>>,
     ev_handler: Option<ResMut<client::EventHandler>>,
+    join_as: Res<JoinAs>,
+    app_args: Res<AppArgs>,
 ) {
     if reader.is_empty() {
         return;
@@ -327,10 +452,19 @@ fn wait_for_connection(
         Ok(_) => {
             display_success!(commands, "connected to the server");
 
-            // Send RequestJoin to the server.
+            // Send RequestJoin or RequestSpectate to the server.
+            let request = match *join_as {
+                JoinAs::Player => OutboundEvent::RequestJoin {
+                    settings: app_args.game_settings(),
+                    name: app_args.name.clone(),
+                    vs_bot: app_args.vs_bot,
+                    auto_ack: true,
+                },
+                JoinAs::Spectator => OutboundEvent::RequestSpectate,
+            };
             let id = match ev_handler
                 .expect("event handler should be available at this point")
-                .send_request(OutboundEvent::RequestJoin)
+                .send_request(request)
             {
                 Ok(id) => id,
                 Err(e) => {
@@ -381,6 +515,8 @@ fn check_response_to_join(
     mut state: ResMut<NextState<JoiningServerState>>,
     mut commands: Commands,
     mut joined_players: ResMut<JoinedPlayers>,
+    join_as: Res<JoinAs>,
+    mut home_state: ResMut<NextState<HomeState>>,
 ) {
     let ev_id = join_request_ev_id.0;
 
@@ -397,12 +533,18 @@ fn check_response_to_join(
         InboundEvent::RequestJoinAccepted(JoinInfo {
             joined_player,
             room_size,
+            names,
+            reconnect_token,
+            auto_ack,
         }) => {
             let player_id = joined_player.assigned_player_id();
             let join_position = joined_player.join_position();
 
             // Store PlayerId
             joined_players.set_my_player(player_id);
+            joined_players.set_names(&names);
+            joined_players.set_reconnect_token(reconnect_token);
+            joined_players.auto_ack = auto_ack;
 
             // Log
             display_success!(
@@ -426,16 +568,51 @@ fn check_response_to_join(
             };
             state.set(next_state);
         }
-        InboundEvent::Error(e) => {
-            display_error!(commands, "{}", e);
+        InboundEvent::RequestSpectateAccepted { players } => {
+            joined_players.set_spectated_players(players);
+
+            display_success!(commands, "started spectating the game");
+            display_debug!(commands, "{:?}", players);
+
+            state.set(JoiningServerState::TransitionToGame);
+        }
+        InboundEvent::Error {
+            error: ServerError::RoomFull,
+            ..
+        } => {
+            // There is nothing to wait for; let the player try again from the menu.
+            commands.spawn(server_full_notice());
+            home_state.set(HomeState::Menu);
+        }
+        InboundEvent::Error { error, detail } => {
+            match detail {
+                Some(detail) => display_error!(commands, "{}: {}", error, detail),
+                None => display_error!(commands, "{}", error),
+            }
             state.set(JoiningServerState::Failed);
         }
         unexp => {
-            panic!("unexpected response to RequestJoin: {:?}", unexp);
+            panic!("unexpected response to {:?}: {:?}", *join_as, unexp);
         }
     }
 }
 
+/// Tells the player on the menu that the server has no room to join.
+fn server_full_notice() -> impl Bundle {
+    (
+        StateScoped(HomeState::Menu),
+        Text::new("The server is full. Please try again later."),
+        TextColor(SERVER_FULL_NOTICE_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+    )
+}
+
 fn on_click_cancel_conn_button(
     _trigger: Trigger<ButtonPressed<PopupCenterButton>>,
     mut commands: Commands,
@@ -477,6 +654,30 @@ fn check_if_disconnected(
     }
 }
 
+/// The server answers the handshake only if it rejects the client's protocol version.
+fn check_protocol_version(
+    trigger: Trigger<ReceivedResponse>,
+    mut ev_handler: ResMut<client::EventHandler>,
+    mut state: ResMut<NextState<JoiningServerState>>,
+    mut commands: Commands,
+) {
+    if trigger.event().id() != protocol::HANDSHAKE_EV_ID {
+        return;
+    }
+
+    if let Some(InboundEvent::IncompatibleVersion { server, client }) =
+        ev_handler.storage.take_response(protocol::HANDSHAKE_EV_ID)
+    {
+        display_error!(
+            commands,
+            "incompatible version: the server speaks protocol v{}, but this client speaks v{}",
+            server,
+            client
+        );
+        state.set(JoiningServerState::Failed);
+    }
+}
+
 fn check_new_players(
     trigger: Trigger<ReceivedRequest>,
     mut ev_handler: ResMut<client::EventHandler>,
@@ -489,11 +690,14 @@ fn check_new_players(
     if let Some(InboundEvent::PlayerJoined(JoinInfo {
         joined_player,
         room_size,
+        names,
+        ..
     })) = ev_handler.storage.get_request(id)
     {
         let player_id = joined_player.assigned_player_id();
         let join_position = joined_player.join_position();
         let room_size = *room_size;
+        joined_players.set_names(names);
 
         // Consume this event
         ev_handler.storage.take_request(id);
@@ -504,7 +708,8 @@ fn check_new_players(
         // Log
         display_info!(
             commands,
-            "new player joined the lobby ( {} / {} )",
+            "{} joined the lobby ( {} / {} )",
+            joined_players.name(player_id).unwrap_or("new player"),
             join_position,
             room_size
         );
@@ -515,20 +720,3 @@ fn check_new_players(
         }
     }
 }
-
-fn start_sandbox_conditioned(
-    mut commands: Commands,
-    query: Single<(&TextInputValue, &TextInputInactive), With<IpAddrTextInput>>,
-) {
-    use crate::game::GameMode;
-
-    let (text, is_inactive) = *query;
-    if is_inactive.0 {
-        return;
-    }
-
-    if text.0.eq_ignore_ascii_case("sandbox") {
-        commands.set_state(AppState::Game);
-        commands.set_state(GameMode::Sandbox);
-    }
-}
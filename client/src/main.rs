@@ -3,16 +3,33 @@
 // #![allow(unused)]
 // #![warn(unused_mut, unused_must_use)]
 
-use algo_core::player::PlayerId;
+use algo_core::{card::CardColor, player::PlayerId, replay::Replay, settings::GameSettings};
+use anyhow::Context as _;
 use bevy::{log::LogPlugin, prelude::*};
 use bevy_simple_text_input::TextInputPlugin;
-use client::utils::{
-    add_observer_ext::AddObserverExtPlugin, log_display::log_display_plugin,
-    scrollable::scrollable_plugin,
+use client::{
+    client::ConnectRetrySettings,
+    utils::{
+        add_observer_ext::AddObserverExtPlugin,
+        log_display::{log_display_plugin, ClearLogOnExit},
+        scrollable::scrollable_plugin,
+        set_timeout::AnimationSpeed,
+    },
+};
+use protocol::server_to_client::ReconnectToken;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::BufReader,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
+mod frame_metrics;
 mod game;
 mod home;
+mod window;
 
 #[cfg(feature = "dev")]
 mod inspector;
@@ -24,9 +41,119 @@ struct AppArgs {
     #[argh(option)]
     server_ip: Option<String>,
 
+    /// name shown to the other players
+    #[argh(option)]
+    name: Option<String>,
+
     /// server port number
     #[argh(option, default = "protocol::DEFAULT_SERVER_PORT")]
     server_port: u16,
+
+    /// number of cards each player draws at the start, if you are the first to join
+    #[argh(option)]
+    initial_draw_num: Option<u32>,
+
+    /// maximum card number, if you are the first to join
+    #[argh(option)]
+    max_card_number: Option<u8>,
+
+    /// number of identical cards for each number and color, if you are the first to join
+    #[argh(option)]
+    copies_per_card: Option<u8>,
+
+    /// card color to include in the deck, if you are the first to join (repeatable)
+    #[argh(option)]
+    card_color: Vec<CardColor>,
+
+    /// number of attempts to connect to the server before giving up
+    #[argh(option, default = "ConnectRetrySettings::default().max_attempts")]
+    connect_max_attempts: u32,
+
+    /// maximum interval between attempts to connect to the server, in seconds
+    #[argh(
+        option,
+        default = "ConnectRetrySettings::default().max_backoff.as_secs()"
+    )]
+    connect_max_backoff: u64,
+
+    /// path to a replay file to watch
+    #[argh(option)]
+    replay: Option<PathBuf>,
+
+    /// multiplier for the pace of the game sequences, e.g. 2.0 makes them twice as fast
+    #[argh(option, default = "1.0")]
+    animation_speed: f32,
+
+    /// disable the numbers that cannot be guessed, as a learning aid (toggle in game with F2)
+    #[argh(switch)]
+    guess_hint: bool,
+
+    /// show your initial cards and wait for your confirmation before the first turn
+    #[argh(switch)]
+    peek_initial_cards: bool,
+
+    /// show the chance of guessing the attacked card correctly in the sandbox, as a learning aid
+    #[argh(switch)]
+    guess_odds: bool,
+
+    /// play against a bot controlled by the server, instead of waiting for another player
+    #[argh(switch)]
+    vs_bot: bool,
+
+    /// title of the window, e.g. to tell clients apart when running several
+    #[argh(option, default = "window::DEFAULT_WINDOW_TITLE.to_string()")]
+    window_title: String,
+
+    /// tint the card colors with distinct hues, for players who find them hard to tell apart
+    #[argh(switch)]
+    colorblind_cards: bool,
+
+    /// directory to cache the generated card textures in
+    #[argh(option)]
+    texture_cache_dir: Option<PathBuf>,
+
+    /// how messages are serialized: bincode or json, which must match the server
+    #[argh(option, default = "bincode_io::Format::default()")]
+    wire_format: bincode_io::Format,
+
+    /// initial size of the buffer that messages from the server are read into, in bytes
+    #[argh(option, default = "protocol::DEFAULT_READ_BUF_SIZE")]
+    read_buf_size: usize,
+}
+
+impl AppArgs {
+    /// Returns the game settings to request, or `None` to use the server's defaults.
+    fn game_settings(&self) -> Option<GameSettings> {
+        if self.initial_draw_num.is_none()
+            && self.max_card_number.is_none()
+            && self.copies_per_card.is_none()
+            && self.card_color.is_empty()
+        {
+            return None;
+        }
+
+        let mut settings = GameSettings::default();
+        if let Some(v) = self.initial_draw_num {
+            settings.initial_draw_num = v;
+        }
+        if let Some(v) = self.max_card_number {
+            settings.max_card_number = v;
+        }
+        if let Some(v) = self.copies_per_card {
+            settings.copies_per_card = v;
+        }
+        if !self.card_color.is_empty() {
+            settings.card_colors.clone_from(&self.card_color);
+        }
+        Some(settings)
+    }
+
+    fn connect_retry_settings(&self) -> ConnectRetrySettings {
+        ConnectRetrySettings {
+            max_attempts: self.connect_max_attempts,
+            max_backoff: Duration::from_secs(self.connect_max_backoff),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, States, Reflect)]
@@ -40,28 +167,72 @@ fn main() {
 
     let args: AppArgs = argh::from_env();
 
-    App::new()
-        .add_plugins((
-            DefaultPlugins.set(LogPlugin {
+    let replay = match args.replay.as_deref().map(load_replay).transpose() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if !(args.animation_speed > 0.0 && args.animation_speed.is_finite()) {
+        eprintln!("animation speed must be a positive number");
+        std::process::exit(1);
+    }
+
+    let mut app = App::new();
+    if let Some(replay) = replay {
+        app.insert_resource(LoadedReplay(replay));
+    }
+    if let Some(dir) = args.texture_cache_dir.clone() {
+        app.insert_resource(game::CardTextureCache(dir));
+    }
+    if args.colorblind_cards {
+        app.insert_resource(game::CardPalette::Colorblind);
+    }
+
+    app.add_plugins((
+        DefaultPlugins
+            .set(LogPlugin {
                 filter: "client=debug,wgpu=error".into(),
                 ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: args.window_title.clone(),
+                    ..default()
+                }),
+                ..default()
             }),
-            TextInputPlugin,
-            log_display_plugin,
-            AddObserverExtPlugin,
-            scrollable_plugin,
-            home::home_plugin,
-            game::game_plugin,
-            #[cfg(feature = "dev")]
-            inspector::inspector_plugin,
-        ))
-        .insert_resource(args)
-        .init_resource::<JoinedPlayers>()
-        .insert_state(AppState::Home)
-        .enable_state_scoped_entities::<AppState>()
-        .register_type::<StateScoped<AppState>>()
-        .add_systems(Startup, setup_camera)
-        .run();
+        TextInputPlugin,
+        window::window_icon_plugin,
+        log_display_plugin,
+        // Start each game with an empty log, without the messages of joining it.
+        ClearLogOnExit(AppState::Home),
+        AddObserverExtPlugin,
+        scrollable_plugin,
+        home::home_plugin,
+        game::game_plugin,
+        frame_metrics::frame_metrics_plugin,
+        #[cfg(feature = "dev")]
+        inspector::inspector_plugin,
+    ))
+    .insert_resource(AnimationSpeed(args.animation_speed))
+    .insert_resource(args)
+    .init_resource::<JoinedPlayers>()
+    .insert_state(AppState::Home)
+    .enable_state_scoped_entities::<AppState>()
+    .register_type::<StateScoped<AppState>>()
+    .add_systems(Startup, setup_camera)
+    .run();
+}
+
+fn load_replay(path: &Path) -> anyhow::Result<Replay> {
+    let file = File::open(path)
+        .with_context(|| format!("failed to open the replay: {}", path.display()))?;
+
+    bincode::deserialize_from(BufReader::new(file))
+        .with_context(|| format!("failed to read the replay: {}", path.display()))
 }
 
 fn setup_camera(mut commands: Commands) {
@@ -77,8 +248,16 @@ fn setup_camera(mut commands: Commands) {
 
 #[derive(Debug, Default, Resource)]
 struct JoinedPlayers {
+    /// While spectating, the player shown on the near side.
     my_player: Option<PlayerId>,
     opponent_player: Option<PlayerId>,
+    spectating: bool,
+    /// The display names received from the server.
+    names: BTreeMap<PlayerId, String>,
+    /// The token to present when rejoining the game.
+    reconnect_token: Option<ReconnectToken>,
+    /// Whether the server acknowledges the `GameEvent`s requiring no decision for me.
+    auto_ack: bool,
 }
 
 impl JoinedPlayers {
@@ -93,4 +272,38 @@ impl JoinedPlayers {
     fn set_opponent_player(&mut self, id: PlayerId) {
         self.opponent_player = Some(id);
     }
+
+    fn set_reconnect_token(&mut self, token: Option<ReconnectToken>) {
+        self.reconnect_token = token;
+    }
+
+    fn set_names(&mut self, names: &BTreeMap<PlayerId, String>) {
+        self.names.clone_from(names);
+    }
+
+    /// Returns the display name of the player, if known.
+    fn name(&self, id: PlayerId) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    fn set_spectated_players(&mut self, (near, far): (PlayerId, PlayerId)) {
+        self.my_player = Some(near);
+        self.opponent_player = Some(far);
+        self.spectating = true;
+    }
+}
+
+/// The replay specified by the launch arguments.
+#[derive(Debug, Resource)]
+struct LoadedReplay(Replay);
+
+/// The replay being watched, which exists while in the game instead of a live match.
+#[derive(Debug, Deref, Resource)]
+struct WatchingReplay(Replay);
+
+/// The address of the server the client has joined.
+#[derive(Debug, Clone, Copy, Resource)]
+struct JoinedServer {
+    addr: IpAddr,
+    port: u16,
 }
@@ -1,4 +1,4 @@
-use bevy::{ecs::system::IntoObserverSystem, prelude::*};
+use bevy::{ecs::system::IntoObserverSystem, prelude::*, utils::all_tuples};
 use std::borrow::Cow;
 
 pub struct AddObserverExtPlugin;
@@ -51,8 +51,41 @@ pub trait AddObserverExt {
     {
         self.add_state_scoped_observer_with(state, observer, name_components::<I>())
     }
+
+    /// Registers a tuple of observers, each as [`add_state_scoped_observer_named`] does.
+    ///
+    /// [`add_state_scoped_observer_named`]: AddObserverExt::add_state_scoped_observer_named
+    fn add_state_scoped_observers_named<S, M, O>(&mut self, state: S, observers: O) -> &mut Self
+    where
+        S: States,
+        O: StateScopedObservers<M>;
+}
+
+/// A tuple of observers registered by [`AddObserverExt::add_state_scoped_observers_named`].
+pub trait StateScopedObservers<Marker> {
+    fn add_named<S: States>(self, app: &mut App, state: S);
+}
+
+macro_rules! impl_state_scoped_observers {
+    ($(($I:ident, $o:ident, $E:ident, $B:ident, $M:ident)),*) => {
+        impl<$($I, $E, $B, $M),*> StateScopedObservers<($(($E, $B, $M),)*)> for ($($I,)*)
+        where
+            $(
+                $E: Event,
+                $B: Bundle,
+                $I: IntoObserverSystem<$E, $B, $M> + Sync + Clone,
+            )*
+        {
+            fn add_named<S: States>(self, app: &mut App, state: S) {
+                let ($($o,)*) = self;
+                $(app.add_state_scoped_observer_named(state.clone(), $o);)*
+            }
+        }
+    };
 }
 
+all_tuples!(impl_state_scoped_observers, 1, 16, I, o, E, B, M);
+
 impl AddObserverExt for App {
     fn add_state_scoped_observer<S, E, B, M, I>(&mut self, state: S, observer: I) -> &mut Self
     where
@@ -87,4 +120,13 @@ impl AddObserverExt for App {
             ));
         })
     }
+
+    fn add_state_scoped_observers_named<S, M, O>(&mut self, state: S, observers: O) -> &mut Self
+    where
+        S: States,
+        O: StateScopedObservers<M>,
+    {
+        observers.add_named(self, state);
+        self
+    }
 }
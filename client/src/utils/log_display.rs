@@ -5,16 +5,37 @@ use super::scrollable::ScrollEvent;
 
 // TODO: make LogDisplay scrollable
 
+/// The line height relative to the font size, which matches the one used by `bevy_text`.
+const LINE_HEIGHT_SCALE: f32 = 1.2;
+
 pub fn log_display_plugin(app: &mut App) {
     app.add_event::<LogEvent>()
         .add_systems(Update, (relay_event, handle_log_display).chain())
         .add_observer(LogDisplay::on_add_log_display);
 }
 
+/// Clears the log on leaving the state, so that its messages don't carry over to the next screen.
+pub struct ClearLogOnExit<S>(pub S);
+
+impl<S: States> Plugin for ClearLogOnExit<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnExit(self.0.clone()),
+            |mut writer: EventWriter<LogEvent>| {
+                writer.send(LogEvent::Clear);
+            },
+        );
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LogDisplaySettings {
     pub max_lines: usize,
     pub font: TextFont,
+    /// Hides the messages below this level.
+    ///
+    /// Messages without a level are always shown.
+    pub min_level: Option<MessageLevel>,
 }
 
 impl Default for LogDisplaySettings {
@@ -22,6 +43,7 @@ impl Default for LogDisplaySettings {
         Self {
             max_lines: 20,
             font: TextFont::default(),
+            min_level: None,
         }
     }
 }
@@ -35,6 +57,10 @@ pub struct LogDisplay {
     lines: Vec<Option<Entity>>,
     scroll: usize,
     prev_scroll: usize,
+    /// Pixel scroll not yet converted into whole lines.
+    pending_pixels: f32,
+    /// Whether the lines must be re-rendered, even without new events.
+    dirty: bool,
 }
 
 impl LogDisplay {
@@ -48,6 +74,23 @@ impl LogDisplay {
             lines,
             scroll: 0,
             prev_scroll: 0,
+            pending_pixels: 0.0,
+            dirty: false,
+        }
+    }
+
+    pub fn set_min_level(&mut self, min_level: Option<MessageLevel>) {
+        if self.settings.min_level != min_level {
+            self.settings.min_level = min_level;
+            self.scroll = 0;
+            self.dirty = true;
+        }
+    }
+
+    fn is_visible(&self, msg: &Message) -> bool {
+        match (self.settings.min_level, msg.level) {
+            (Some(min_level), Some(level)) => level >= min_level,
+            _ => true,
         }
     }
 
@@ -98,7 +141,7 @@ impl LogDisplay {
         if lines < 0 {
             self.scroll = self.scroll.saturating_sub(lines.unsigned_abs() as usize);
         } else {
-            let len = self.logs.len();
+            let len = self.logs.iter().filter(|v| self.is_visible(v)).count();
             let max_lines = self.settings.max_lines;
             if len > max_lines {
                 self.scroll = (self.scroll + lines as usize).min(len - max_lines);
@@ -106,34 +149,50 @@ impl LogDisplay {
         }
     }
 
+    /// Accumulates a pixel scroll, and scrolls by the number of whole lines accrued.
+    ///
+    /// The remainder is carried over to the next call.
+    pub fn queue_scroll_pixels(&mut self, dy: f32) {
+        let line_height = self.line_height();
+        if line_height <= 0.0 {
+            return;
+        }
+
+        self.pending_pixels += dy;
+
+        let lines = (self.pending_pixels / line_height).trunc();
+        if lines != 0.0 {
+            self.pending_pixels -= lines * line_height;
+            self.queue_scroll(lines as i32);
+        }
+    }
+
+    fn line_height(&self) -> f32 {
+        self.settings.font.font_size * LINE_HEIGHT_SCALE
+    }
+
     fn on_scroll(trigger: Trigger<ScrollEvent>, mut query: Query<&mut LogDisplay>) {
         let event = trigger.event().0;
+        let mut log_display = query.get_mut(trigger.entity()).unwrap();
 
-        let (_dx, dy) = match event.unit {
-            MouseScrollUnit::Line => (event.x, event.y),
-            MouseScrollUnit::Pixel => {
-                warn!("unsupported MouseScrollUnit for LogDisplay: Pixel");
-                return; // TODO
-            }
-        };
-
-        query
-            .get_mut(trigger.entity())
-            .unwrap()
-            .queue_scroll(dy as i32);
+        match event.unit {
+            MouseScrollUnit::Line => log_display.queue_scroll(event.y as i32),
+            MouseScrollUnit::Pixel => log_display.queue_scroll_pixels(event.y),
+        }
     }
 
     fn update(&mut self, self_id: Entity, world_cmds: &mut Commands) {
-        if self.events.is_empty() && self.scroll == self.prev_scroll {
+        if self.events.is_empty() && self.scroll == self.prev_scroll && !self.dirty {
             return;
         }
         self.prev_scroll = self.scroll;
 
-        let prev_len = self.logs.len();
+        let prev_len = self.logs.iter().filter(|v| self.is_visible(v)).count();
         let has_clear_cmd = match self.events.front() {
             Some(ev) => ev.is_clear(),
             None => false,
         };
+        let dirty = std::mem::take(&mut self.dirty);
 
         for log_cmd in self.events.drain(..) {
             match log_cmd {
@@ -151,7 +210,8 @@ impl LogDisplay {
             }
         }
 
-        let len = self.logs.len();
+        let logs: Vec<_> = self.logs.iter().filter(|v| self.is_visible(v)).collect();
+        let len = logs.len();
         let max_lines = self.settings.max_lines;
         let mut start = len.saturating_sub(max_lines);
 
@@ -161,7 +221,7 @@ impl LogDisplay {
 
         let stop = (start + max_lines).min(len);
 
-        for (msg, entity) in self.logs[start..stop].iter().zip(&mut self.lines) {
+        for (msg, entity) in logs[start..stop].iter().zip(&mut self.lines) {
             let components = (msg.to_components(), self.settings.font.clone());
 
             if let Some(entity) = entity {
@@ -175,7 +235,8 @@ impl LogDisplay {
             });
         }
 
-        if has_clear_cmd && prev_len >= stop {
+        // Blank out the lines no longer in use.
+        if (has_clear_cmd && prev_len >= stop) || dirty {
             for entity in self.lines.iter().skip(stop) {
                 let Some(entity) = entity else {
                     continue;
@@ -282,7 +343,7 @@ impl Message {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum MessageLevel {
     Debug,
     Info,
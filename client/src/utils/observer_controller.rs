@@ -32,6 +32,11 @@ pub struct ObserverControllerSettings {
     pub removable: bool,
     pub pausable: bool,
     pub once: bool,
+    /// Pauses the controlled observer after it is triggered once,
+    /// so that it can be activated again without being reinserted.
+    ///
+    /// Requires `pausable`.
+    pub pause_after_first: bool,
 }
 
 impl ObserverControllerSettings {
@@ -40,6 +45,7 @@ impl ObserverControllerSettings {
             removable: false,
             pausable: false,
             once: true,
+            pause_after_first: false,
         }
     }
 }
@@ -50,6 +56,7 @@ impl Default for ObserverControllerSettings {
             removable: true,
             pausable: true,
             once: true,
+            pause_after_first: false,
         }
     }
 }
@@ -91,12 +98,16 @@ where
             removable,
             pausable,
             once,
+            pause_after_first,
         } = self.settings;
 
         if removable {
             ObserverController::<E, B>::minimal_plugin(app);
             if pausable {
                 ObserverController::<E, B>::pausable_plugin(app);
+                if pause_after_first {
+                    app.add_observer(ObserverController::<E, B>::pause_after_trigger);
+                }
             }
         }
         if once {
@@ -124,6 +135,7 @@ where
             removable,
             pausable,
             once,
+            pause_after_first,
         } = self.plugin.settings;
         let state = &self.state;
 
@@ -131,6 +143,12 @@ where
             ObserverController::<E, B>::minimal_plugin_state_scoped(app, state.clone());
             if pausable {
                 ObserverController::<E, B>::pausable_plugin_state_scoped(app, state.clone());
+                if pause_after_first {
+                    app.add_state_scoped_observer_named(
+                        state.clone(),
+                        ObserverController::<E, B>::pause_after_trigger,
+                    );
+                }
             }
         }
         if once {
@@ -249,6 +267,26 @@ where
             commands.entity(observer_entity).despawn();
         }
     }
+
+    fn pause_after_trigger(
+        trigger: Trigger<E, B>,
+        mut query: Query<&mut Self>,
+        mut commands: Commands,
+    ) {
+        let entity = trigger.entity();
+        let Ok(mut this) = query.get_mut(entity) else {
+            return;
+        };
+
+        // Despawning is deferred, so the observer still handles this trigger.
+        if let Some(observer_entity) = this.observer_entity.take() {
+            debug!(
+                "pausing observer after trigger: observer={}, target={}",
+                observer_entity, entity
+            );
+            commands.entity(observer_entity).despawn();
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -330,12 +368,14 @@ where
 
     fn plugin(app: &mut App) {
         app.add_observer(Self::handle_trigger)
-            .add_observer(Self::cleanup);
+            .add_observer(Self::cleanup)
+            .add_observer(Self::despawn_observer);
     }
 
     fn plugin_state_scoped<S: States + Clone>(app: &mut App, state: S) {
         app.add_state_scoped_observer_named(state.clone(), Self::handle_trigger)
-            .add_state_scoped_observer_named(state, Self::cleanup);
+            .add_state_scoped_observer_named(state.clone(), Self::cleanup)
+            .add_state_scoped_observer_named(state, Self::despawn_observer);
     }
 
     fn handle_trigger(mut trigger: Trigger<Self>, mut commands: Commands) {
@@ -355,11 +395,31 @@ where
     ) {
         let entity = trigger.entity();
 
-        if let Ok(observed) = query.get(entity) {
-            commands.entity(observed.observer_entity).despawn();
+        if query.contains(entity) {
+            // The observer is despawned by `despawn_observer`.
             commands.entity(entity).remove::<ObservedByOnce<E, B>>();
         }
     }
+
+    /// Despawns the observer when `ObservedByOnce` is removed or replaced,
+    /// including when the target is despawned without the event being triggered.
+    fn despawn_observer(
+        trigger: Trigger<OnReplace, ObservedByOnce<E, B>>,
+        query: Query<&ObservedByOnce<E, B>>,
+        mut commands: Commands,
+    ) {
+        let Ok(observed) = query.get(trigger.entity()) else {
+            return;
+        };
+
+        // The observer may have been despawned along with the target already.
+        let observer_entity = observed.observer_entity;
+        commands.queue(move |world: &mut World| {
+            if world.entities().contains(observer_entity) {
+                world.despawn(observer_entity);
+            }
+        });
+    }
 }
 
 #[derive(Component)]
@@ -376,3 +436,73 @@ impl<E, B> ObservedByOnce<E, B> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Event)]
+    struct Ping;
+
+    #[derive(Default, Resource)]
+    struct PingCount(u32);
+
+    fn count_ping(_: Trigger<Ping>, mut count: ResMut<PingCount>) {
+        count.0 += 1;
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<PingCount>()
+            .add_plugins(ObserverControllerPlugin::<Ping>::new(
+                ObserverControllerSettings::once(),
+            ));
+        app
+    }
+
+    fn observer_count(app: &mut App) -> usize {
+        let world = app.world_mut();
+        world.query::<&Observer>().iter(world).count()
+    }
+
+    fn observe_once(app: &mut App, target: Entity) {
+        let world = app.world_mut();
+        world.trigger_targets(ObserveOnce::<Ping>::new(Observer::new(count_ping)), target);
+        world.flush();
+    }
+
+    #[test]
+    fn observer_runs_once() {
+        let mut app = app();
+        let target = app.world_mut().spawn_empty().id();
+        let before = observer_count(&mut app);
+
+        observe_once(&mut app, target);
+        assert_eq!(observer_count(&mut app), before + 1);
+
+        for _ in 0..2 {
+            app.world_mut().trigger_targets(Ping, target);
+            app.world_mut().flush();
+        }
+
+        assert_eq!(app.world().resource::<PingCount>().0, 1);
+        assert_eq!(observer_count(&mut app), before);
+        assert!(app.world().get_entity(target).is_ok());
+    }
+
+    #[test]
+    fn observer_is_despawned_with_untriggered_target() {
+        let mut app = app();
+        let target = app.world_mut().spawn_empty().id();
+        let before = observer_count(&mut app);
+
+        observe_once(&mut app, target);
+        assert_eq!(observer_count(&mut app), before + 1);
+
+        app.world_mut().despawn(target);
+        app.world_mut().flush();
+
+        assert_eq!(observer_count(&mut app), before);
+        assert_eq!(app.world().resource::<PingCount>().0, 0);
+    }
+}
@@ -14,12 +14,18 @@ pub fn scrollable_plugin(app: &mut App) {
 #[derive(Component)]
 pub struct Scrollable;
 
+/// The distance to scroll per line, in pixels, along both axes.
 #[derive(Deref, DerefMut, Debug, Clone, Copy, Component)]
 pub struct ScrollLineHeight(pub f32);
 
+/// Holding either key turns the vertical wheel into horizontal scrolling,
+/// for mice without a horizontal wheel.
+const HORIZONTAL_SCROLL_KEYS: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+
 // Ref: https://bevyengine.org/examples/ui-user-interface/ui/
 fn update_scroll_position(
     mut mouse_wheel_events: EventReader<MouseWheel>,
+    keys: Res<ButtonInput<KeyCode>>,
     hover_map: Res<HoverMap>,
     mut scrollable_query: Query<(
         Option<&ScrollLineHeight>,
@@ -49,7 +55,11 @@ fn update_scroll_position(
                 };
 
                 let (dx, dy) = {
-                    let (dx, dy) = (mouse_wheel_event.x, mouse_wheel_event.y);
+                    let (mut dx, mut dy) = (mouse_wheel_event.x, mouse_wheel_event.y);
+                    if keys.any_pressed(HORIZONTAL_SCROLL_KEYS) {
+                        std::mem::swap(&mut dx, &mut dy);
+                    }
+
                     match mouse_wheel_event.unit {
                         MouseScrollUnit::Line => (dx * height.0, dy * height.0),
                         MouseScrollUnit::Pixel => (dx, dy),
@@ -8,6 +8,7 @@ pub struct SetTimeoutPlugin<S> {
 impl<S: States + Clone> Plugin for SetTimeoutPlugin<S> {
     fn build(&self, app: &mut App) {
         app.init_resource::<EventsOnTimedout>()
+            .init_resource::<AnimationSpeed>()
             .add_state_scoped_observer_named(self.ctx_state.clone(), SetTimeout::handle_trigger)
             .add_state_scoped_observer_named(
                 self.ctx_state.clone(),
@@ -16,6 +17,30 @@ impl<S: States + Clone> Plugin for SetTimeoutPlugin<S> {
     }
 }
 
+/// A multiplier for the pace of timed sequences.
+///
+/// All [`SetTimeout`] durations are divided by this value,
+/// e.g. `2.0` halves them and `0.5` doubles them.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct AnimationSpeed(pub f32);
+
+impl Default for AnimationSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl AnimationSpeed {
+    /// Returns the duration adjusted to the speed.
+    pub fn scale(&self, duration_secs: f32) -> f32 {
+        if self.0 > 0.0 {
+            duration_secs / self.0
+        } else {
+            duration_secs
+        }
+    }
+}
+
 #[derive(Event)]
 pub struct SetTimeout {
     duration_secs: f32,
@@ -67,6 +92,7 @@ impl SetTimeout {
         mut clips: ResMut<Assets<AnimationClip>>,
         mut graphs: ResMut<Assets<AnimationGraph>>,
         mut storage: ResMut<EventsOnTimedout>,
+        speed: Res<AnimationSpeed>,
         mut commands: Commands,
     ) {
         let event = trigger.event_mut();
@@ -75,7 +101,8 @@ impl SetTimeout {
             return;
         };
 
-        if event.duration_secs <= 0.0 {
+        let duration_secs = speed.scale(event.duration_secs);
+        if duration_secs <= 0.0 {
             (on_timedout)(&mut commands);
             return;
         }
@@ -84,7 +111,7 @@ impl SetTimeout {
 
         let animator_entity = {
             let mut clip = AnimationClip::default();
-            clip.add_event(event.duration_secs, NotifyTimedout(entry.key()));
+            clip.add_event(duration_secs, NotifyTimedout(entry.key()));
 
             let (graph, node_idx) = AnimationGraph::from_clip(clips.add(clip));
 
@@ -0,0 +1,38 @@
+use bevy::{prelude::*, window::PrimaryWindow, winit::WinitWindows};
+use winit::window::Icon;
+
+/// The title of the window, unless specified by the launch arguments.
+pub const DEFAULT_WINDOW_TITLE: &str = "Algo";
+
+const WINDOW_ICON: &[u8] = include_bytes!("../assets/icon.png");
+
+pub fn window_icon_plugin(app: &mut App) {
+    app.add_systems(Update, set_window_icon);
+}
+
+/// Sets the icon once the window is created.
+fn set_window_icon(
+    mut done: Local<bool>,
+    windows: NonSend<WinitWindows>,
+    primary_window: Option<Single<Entity, With<PrimaryWindow>>>,
+) {
+    if *done {
+        return;
+    }
+
+    let Some(window) = primary_window.and_then(|v| windows.get_window(*v)) else {
+        return;
+    };
+    *done = true;
+
+    match load_icon() {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(e) => warn!("failed to load the window icon: {}", e),
+    }
+}
+
+fn load_icon() -> anyhow::Result<Icon> {
+    let image = image::load_from_memory(WINDOW_ICON)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(Icon::from_rgba(image.into_raw(), width, height)?)
+}
@@ -1,10 +1,76 @@
-use algo_core::event::GameEvent;
+use crate::server_to_client::ReconnectToken;
+use algo_core::{event::GameEvent, player::PlayerId, settings::GameSettings};
 use bevy_ecs::event::Event;
 use serde::{Deserialize, Serialize};
 
+/// The maximum number of characters in a chat message.
+///
+/// The server truncates longer messages.
+pub const CHAT_MAX_LEN: usize = 200;
+
+/// The maximum number of characters in a player's display name.
+///
+/// The server truncates longer names.
+pub const PLAYER_NAME_MAX_LEN: usize = 16;
+
 /// An event that clients send to the server.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Event)]
 pub enum ClientToServerEvent {
-    RequestJoin,
+    /// The first message of a connection, telling the server which protocol the client speaks.
+    ///
+    /// Kept as the first variant so that it is encoded the same in every version.
+    Hello {
+        /// The client's [`PROTOCOL_VERSION`].
+        ///
+        /// [`PROTOCOL_VERSION`]: `crate::PROTOCOL_VERSION`
+        version: u32,
+    },
+    RequestJoin {
+        /// Settings of the game to play, which are used only if the player is the first to join.
+        ///
+        /// `None` requests the default settings.
+        settings: Option<GameSettings>,
+        /// The name to show to the other players.
+        ///
+        /// `None` lets the server choose a default name.
+        name: Option<String>,
+        /// Requests a practice game against a bot controlled by the server,
+        /// instead of waiting for another player.
+        vs_bot: bool,
+        /// Declares that the client can do without responding to `GameEvent`s
+        /// that require no decision, if the server acknowledges them on its behalf.
+        ///
+        /// Whether the server does is told by [`JoinInfo::auto_ack`].
+        ///
+        /// [`JoinInfo::auto_ack`]: `crate::server_to_client::JoinInfo::auto_ack`
+        auto_ack: bool,
+    },
+    /// Requests to take back the seat of a game in progress after losing connection.
+    Rejoin {
+        player: PlayerId,
+        /// The token received when joining the game.
+        token: ReconnectToken,
+    },
+    /// Requests to watch the game in progress.
+    RequestSpectate,
     GameEventResponse(GameEvent),
+    /// Asks to resend the `GameEvent`s following the given sequence number,
+    /// as the client has found a gap in the ones it received.
+    RequestResync {
+        last_seq: u32,
+    },
+    /// A chat message to the other player.
+    Chat(String),
+    /// Concedes the game in progress.
+    Surrender,
+    /// Asks to play again with the same players after the game has ended.
+    RequestRematch,
+    /// Refuses to play again, which closes the game.
+    RematchDeclined,
+    /// Checks that the server is still responding.
+    Ping,
+    /// An answer to [`ServerToClientEvent::Ping`].
+    ///
+    /// [`ServerToClientEvent::Ping`]: `crate::server_to_client::ServerToClientEvent::Ping`
+    Pong,
 }
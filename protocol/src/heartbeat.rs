@@ -0,0 +1,83 @@
+use crate::EventId;
+use std::time::{Duration, Instant};
+
+/// The ID of `Ping` and `Pong` events.
+///
+/// They are handled by the connections, and never reach the game.
+pub const HEARTBEAT_EV_ID: EventId = EventId::from_raw(u32::MAX - 1);
+
+/// Settings for detecting a peer that silently stopped responding.
+#[derive(Debug, Clone, Copy)]
+pub struct HeartbeatSettings {
+    /// How often to ping the peer.
+    pub interval: Duration,
+    /// The number of pings in a row the peer can leave unanswered before it is considered lost.
+    pub max_missed: u32,
+}
+
+impl Default for HeartbeatSettings {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            max_missed: 3,
+        }
+    }
+}
+
+/// Counts the pings the peer has not answered.
+///
+/// Any message from the peer counts as an answer.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    settings: HeartbeatSettings,
+    missed: u32,
+    /// When the latest ping was sent, until it is answered.
+    last_ping: Option<Instant>,
+}
+
+impl Heartbeat {
+    pub fn new(settings: HeartbeatSettings) -> Self {
+        Self {
+            settings,
+            missed: 0,
+            last_ping: None,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.settings.interval
+    }
+
+    /// Records that a ping is about to be sent.
+    ///
+    /// Returns `Err` if the peer has left too many pings unanswered.
+    pub fn ping(&mut self) -> Result<(), HeartbeatTimeout> {
+        if self.missed >= self.settings.max_missed {
+            return Err(HeartbeatTimeout {
+                elapsed: self.settings.interval * self.missed,
+            });
+        }
+
+        self.missed += 1;
+        self.last_ping = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Records that a message was received from the peer.
+    pub fn received(&mut self) {
+        self.missed = 0;
+    }
+
+    /// Records that the peer answered the latest ping.
+    ///
+    /// Returns the round-trip time, or `None` if no ping is waiting for an answer.
+    pub fn pong(&mut self) -> Option<Duration> {
+        self.last_ping.take().map(|sent_at| sent_at.elapsed())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("no response from the peer for {elapsed:?}")]
+pub struct HeartbeatTimeout {
+    pub elapsed: Duration,
+}
@@ -4,4 +4,26 @@ pub mod server_to_client;
 pub mod events;
 pub use events::*;
 
+pub mod heartbeat;
+
 pub const DEFAULT_SERVER_PORT: u16 = 54345;
+
+/// The version of the messages exchanged between clients and the server.
+///
+/// Bump this whenever the encoding of any message changes,
+/// so that mismatched peers are rejected instead of misreading each other.
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// The ID of the `Hello` event that a client sends first on connecting,
+/// and of the server's answer to it.
+pub const HANDSHAKE_EV_ID: EventId = EventId::from_raw(u32::MAX - 2);
+
+/// The initial size of the buffer that messages are read into, in bytes.
+///
+/// The buffer grows to fit longer messages, up to [`MAX_FRAME_LEN`].
+pub const DEFAULT_READ_BUF_SIZE: usize = 1024;
+
+/// The maximum length of a single message in bytes.
+///
+/// A peer sending a longer message is disconnected.
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
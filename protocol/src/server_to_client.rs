@@ -1,63 +1,166 @@
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
 
 use algo_core::{event::GameEvent, player::PlayerId};
 use bevy_ecs::event::Event;
 use serde::{Deserialize, Serialize};
 
+use crate::EventId;
+
+/// The ID of the `ServerShutdown` request that the server sends before closing connections.
+pub const SERVER_SHUTDOWN_EV_ID: EventId = EventId::PLACEHOLDER;
+
 /// An event that a server sends to clients.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Event)]
 pub enum ServerToClientEvent {
+    /// The client speaks a different protocol version, and the connection is closed.
+    ///
+    /// Kept as the first variant so that clients of any version can decode it.
+    IncompatibleVersion {
+        server: u32,
+        client: u32,
+    },
     RequestJoinAccepted(JoinInfo),
+    RejoinAccepted,
+    RequestSpectateAccepted {
+        players: (PlayerId, PlayerId),
+    },
     PlayerJoined(JoinInfo),
     PlayerDisconnected(PlayerId),
-    GameEvent(GameEvent),
+    /// The player lost connection and the seat is reserved for a while.
+    PlayerReconnecting(PlayerId),
+    PlayerReconnected(PlayerId),
+    GameEvent {
+        /// Numbers the `GameEvent`s sent to the client in the current game, starting from 1.
+        ///
+        /// A client can tell from it the events it has missed or already applied.
+        seq: u32,
+        event: GameEvent,
+    },
+    Chat {
+        from: PlayerId,
+        text: String,
+    },
+    /// The player asked for a rematch.
+    RematchRequested(PlayerId),
+    /// The player refused a rematch, and the game is closed.
+    RematchDeclined(PlayerId),
+    /// All players accepted a rematch, and a new game follows.
+    RematchStarted,
     ServerShutdown,
-    Error(Box<str>),
+    /// The game was terminated by an error on the server, unlike a clean shutdown.
+    GameAborted {
+        reason: Box<str>,
+    },
+    /// The server refused a request.
+    Error {
+        error: ServerError,
+        /// Details for the user, if any.
+        detail: Option<Box<str>>,
+    },
+    /// Checks that the client is still responding.
+    Ping,
+    /// An answer to [`ClientToServerEvent::Ping`].
+    ///
+    /// [`ClientToServerEvent::Ping`]: `crate::client_to_server::ClientToServerEvent::Ping`
+    Pong,
 }
 
 impl ServerToClientEvent {
+    pub fn error(error: ServerError, detail: impl Into<Box<str>>) -> Self {
+        Self::Error {
+            error,
+            detail: Some(detail.into()),
+        }
+    }
+
     pub fn is_game_event(&self) -> bool {
-        matches!(self, Self::GameEvent(_))
+        matches!(self, Self::GameEvent { .. })
+    }
+
+    /// Returns the sequence number of a `GameEvent`.
+    pub fn game_event_seq(&self) -> Option<u32> {
+        match self {
+            Self::GameEvent { seq, .. } => Some(*seq),
+            _ => None,
+        }
     }
 
     /// # Panics
     /// Panics if `self` is not a `GameEvent`.
     pub fn into_game_event(self) -> GameEvent {
         match self {
-            Self::GameEvent(game_event) => game_event,
+            Self::GameEvent { event, .. } => event,
             v => panic!("not a game event: {:?}", v),
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Deserialize, Serialize, Event)]
+/// Why the server refused a request, so that clients can react to each reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, thiserror::Error)]
+pub enum ServerError {
+    /// No room can take another player.
+    #[error("the server is full")]
+    RoomFull,
+    #[error("invalid game settings")]
+    InvalidSettings,
+    /// The client did not start with the handshake expected by this protocol version.
+    #[error("incompatible protocol version")]
+    VersionMismatch,
+    /// There is no seat to take back after losing connection.
+    #[error("cannot rejoin the game")]
+    SeatUnavailable,
+    #[error("no game is in progress")]
+    NoGameInProgress,
+    #[error("server internal error")]
+    Internal,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize, Event)]
 pub struct JoinInfo {
     pub joined_player: JoinedPlayerInfo,
     pub room_size: u8,
+    /// The display names of the players in the room.
+    pub names: BTreeMap<PlayerId, String>,
+    /// The secret to present when rejoining the game after losing connection.
+    ///
+    /// Only the joining player receives it; it is `None` when notifying the other players.
+    pub reconnect_token: Option<ReconnectToken>,
+    /// Whether the server acknowledges the `GameEvent`s that require no decision
+    /// on behalf of the joining player, who must not respond to them.
+    ///
+    /// Always `false` when notifying the other players.
+    pub auto_ack: bool,
 }
 
+/// A secret proving that a rejoining client is the player it claims to be.
+pub type ReconnectToken = [u8; 16];
+
 impl fmt::Debug for JoinInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.joined_player)
-        // Ignoring `room_size`, as it is always 2 currently.
+        write!(
+            f,
+            "{:?} {:?} ( {} / {} )",
+            self.joined_player,
+            self.names,
+            self.joined_player.join_position(),
+            self.room_size
+        )
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Deserialize, Serialize, Event)]
-pub enum JoinedPlayerInfo {
-    First(PlayerId),
-    Second {
-        just_joined: PlayerId,
-        waiting_player: PlayerId,
-    },
+#[derive(Clone, PartialEq, Deserialize, Serialize, Event)]
+pub struct JoinedPlayerInfo {
+    pub just_joined: PlayerId,
+    /// The players already in the room, in the order they joined.
+    pub waiting_players: Vec<PlayerId>,
 }
 
 impl fmt::Debug for JoinedPlayerInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut map = f.debug_map();
         map.entry(&"joined", &self.assigned_player_id());
-        if let Some(id) = self.waiting_player_id() {
-            map.entry(&"waiting", &id);
+        if !self.waiting_players.is_empty() {
+            map.entry(&"waiting", &self.waiting_players);
         }
         map.finish()
     }
@@ -65,26 +168,16 @@ impl fmt::Debug for JoinedPlayerInfo {
 
 impl JoinedPlayerInfo {
     pub fn assigned_player_id(&self) -> PlayerId {
-        match self {
-            Self::First(id) => *id,
-            Self::Second { just_joined, .. } => *just_joined,
-        }
+        self.just_joined
     }
 
+    /// Returns the player who joined first, if any other than the new player.
     pub fn waiting_player_id(&self) -> Option<PlayerId> {
-        match self {
-            Self::First(_) => None,
-            Self::Second {
-                just_joined: _,
-                waiting_player,
-            } => Some(*waiting_player),
-        }
+        self.waiting_players.first().copied()
     }
 
+    /// Returns the 1-based position of the new player in the room.
     pub fn join_position(&self) -> u8 {
-        match self {
-            Self::First(_) => 1,
-            Self::Second { .. } => 2,
-        }
+        self.waiting_players.len() as u8 + 1
     }
 }
@@ -0,0 +1,91 @@
+use super::lobby::RoomId;
+use algo_core::{event::GameEvent, player::PlayerId};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{LineWriter, Write as _},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+/// The environment variable holding the path of the event log.
+///
+/// The log is disabled if it is not set.
+pub const EVENT_LOG_ENV: &str = "ALGO_EVENT_LOG";
+
+/// A line of the event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLogRecord {
+    /// Milliseconds since the UNIX epoch.
+    pub timestamp_ms: u64,
+    /// The room the game was played in.
+    pub room: RoomId,
+    /// The seed of the game, which tells apart the games of the same room.
+    pub seed: u64,
+    /// The player the event was sent to.
+    pub player: PlayerId,
+    pub event: GameEvent,
+}
+
+/// Records the dispatched `GameEvent`s as JSON lines, for later analysis.
+///
+/// The events of all rooms and successive games are appended to the same file;
+/// each record tells which game it belongs to.
+#[derive(Debug)]
+pub struct EventLog {
+    room: RoomId,
+    path: PathBuf,
+    writer: LineWriter<File>,
+}
+
+impl EventLog {
+    /// Opens the log of the room at the path given by [`EVENT_LOG_ENV`], if any.
+    ///
+    /// Failures are only logged, as the log is not essential to the game.
+    pub fn from_env(room: RoomId) -> Option<Self> {
+        let path = std::env::var_os(EVENT_LOG_ENV)?;
+
+        match Self::open(room, &path) {
+            Ok(v) => {
+                info!("recording events to {}", v.path.display());
+                Some(v)
+            }
+            Err(e) => {
+                warn!(
+                    "failed to open the event log {}: {}",
+                    Path::new(&path).display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn open(room: RoomId, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            room,
+            path,
+            writer: LineWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, seed: u64, player: PlayerId, event: &GameEvent) -> anyhow::Result<()> {
+        let record = EventLogRecord {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            room: self.room,
+            seed,
+            player,
+            event: event.clone(),
+        };
+
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use super::{ServerInternalEvent, WaitingRoom};
+use algo_core::player::{AssignPlayerId, PlayerId};
+use anyhow::anyhow;
+use protocol::server_to_client::ServerError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{info, warn};
+
+/// Identifies a room on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RoomId(u32);
+
+/// What a room needs to know about the lobby hosting it.
+#[derive(Debug, Clone)]
+pub(super) struct RoomContext {
+    pub id: RoomId,
+    lobby_tx: UnboundedSender<ServerInternalEvent>,
+    /// Shared by all rooms, so that the lobby can tell the players apart.
+    player_ids: Arc<Mutex<AssignPlayerId>>,
+    /// Where to save the replays of the games, if at all.
+    pub replay_dir: Option<PathBuf>,
+}
+
+impl RoomContext {
+    /// Returns `Err` if no more IDs can be handed out.
+    pub fn assign_player_id(&self) -> anyhow::Result<PlayerId> {
+        let mut player_ids = self
+            .player_ids
+            .lock()
+            .map_err(|_| anyhow!("player ID counter is poisoned"))?;
+        Ok(player_ids.assign()?)
+    }
+
+    /// Sends the event to the lobby.
+    ///
+    /// Failures are ignored, as the lobby outlives all rooms.
+    pub fn send_to_lobby(&self, event: ServerInternalEvent) {
+        self.lobby_tx.send(event).ok();
+    }
+}
+
+#[cfg(test)]
+impl RoomContext {
+    /// Creates the context of a room that is not hosted by a [`Lobby`],
+    /// whose reports are sent to `lobby_tx`.
+    pub fn detached(lobby_tx: UnboundedSender<ServerInternalEvent>) -> Self {
+        Self {
+            id: RoomId(0),
+            lobby_tx,
+            player_ids: Arc::default(),
+            replay_dir: None,
+        }
+    }
+}
+
+/// Routes the connections to the rooms, and runs each room on its own task.
+///
+/// New players fill the open room first, and a new room is opened
+/// when the previous one has started its game.
+/// A player who asks for a bot gets a room of their own, leaving the open room as is.
+#[derive(Debug)]
+pub struct Lobby {
+    rx: UnboundedReceiver<ServerInternalEvent>,
+    tx: UnboundedSender<ServerInternalEvent>,
+    max_rooms: u16,
+    rooms: BTreeMap<RoomId, UnboundedSender<ServerInternalEvent>>,
+    /// The room waiting for more players, if any.
+    open_room: Option<RoomId>,
+    /// The room each player has been seated in.
+    players: BTreeMap<PlayerId, RoomId>,
+    next_room_id: u32,
+    player_ids: Arc<Mutex<AssignPlayerId>>,
+    replay_dir: Option<PathBuf>,
+}
+
+impl Lobby {
+    /// `tx` must send to `rx`, so that the rooms can report back to the lobby.
+    pub fn new(
+        rx: UnboundedReceiver<ServerInternalEvent>,
+        tx: UnboundedSender<ServerInternalEvent>,
+        max_rooms: u16,
+    ) -> Self {
+        Self {
+            rx,
+            tx,
+            max_rooms,
+            rooms: BTreeMap::new(),
+            open_room: None,
+            players: BTreeMap::new(),
+            next_room_id: 0,
+            player_ids: Arc::default(),
+            replay_dir: None,
+        }
+    }
+
+    /// Sets the directory the rooms save the replays of their games in.
+    pub fn with_replay_dir(mut self, replay_dir: Option<PathBuf>) -> Self {
+        self.replay_dir = replay_dir;
+        self
+    }
+
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        // `self.tx` keeps the channel open.
+        while let Some(ev) = self.rx.recv().await {
+            match ev {
+                ServerInternalEvent::RequestJoin { vs_bot, ref tx, .. } => {
+                    let room_id = if vs_bot {
+                        self.start_room()
+                    } else {
+                        self.open_room.or_else(|| self.open_new_room())
+                    };
+                    let Some(room_id) = room_id else {
+                        tx.try_send(ServerInternalEvent::RequestJoinRejected(
+                            ServerError::RoomFull,
+                            "all rooms are in use".into(),
+                        ))
+                        .ok();
+                        continue;
+                    };
+                    self.send_to_room(room_id, ev);
+                }
+                ServerInternalEvent::RequestRejoin(player_id, _, ref tx) => {
+                    let Some(room_id) = self.players.get(&player_id).copied() else {
+                        tx.try_send(ServerInternalEvent::RequestRejoinRejected(
+                            ServerError::SeatUnavailable,
+                            "no reserved seat for the player".into(),
+                        ))
+                        .ok();
+                        continue;
+                    };
+                    self.send_to_room(room_id, ev);
+                }
+                ServerInternalEvent::RequestSpectate(ref tx) => {
+                    // Watch the oldest game in progress.
+                    let Some(room_id) = self
+                        .rooms
+                        .keys()
+                        .copied()
+                        .find(|id| Some(*id) != self.open_room)
+                    else {
+                        tx.try_send(ServerInternalEvent::RequestSpectateRejected(
+                            ServerError::NoGameInProgress,
+                            "no game is in progress".into(),
+                        ))
+                        .ok();
+                        continue;
+                    };
+                    self.send_to_room(room_id, ev);
+                }
+                ServerInternalEvent::In(player_id, _)
+                | ServerInternalEvent::ConnectionLost(player_id) => {
+                    let Some(room_id) = self.players.get(&player_id).copied() else {
+                        warn!("event from a player without a room: {:?}", ev);
+                        continue;
+                    };
+                    self.send_to_room(room_id, ev);
+                }
+                ServerInternalEvent::PlayerSeated(room_id, player_id) => {
+                    self.players.insert(player_id, room_id);
+                }
+                ServerInternalEvent::PlayerLeft(room_id, player_id) => {
+                    if self.players.get(&player_id) == Some(&room_id) {
+                        self.players.remove(&player_id);
+                    }
+                }
+                ServerInternalEvent::RoomStarted(room_id) => {
+                    if self.open_room == Some(room_id) {
+                        self.open_room = None;
+                    }
+                }
+                ServerInternalEvent::RoomClosed(room_id) => {
+                    info!("closed {:?}", room_id);
+
+                    self.rooms.remove(&room_id);
+                    self.players.retain(|_, id| *id != room_id);
+                    if self.open_room == Some(room_id) {
+                        self.open_room = None;
+                    }
+                }
+                unexpected => {
+                    warn!("unexpected event: {:?}", unexpected);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts a new room that the next players join,
+    /// unless the server already hosts as many as it can.
+    fn open_new_room(&mut self) -> Option<RoomId> {
+        let room_id = self.start_room()?;
+        self.open_room = Some(room_id);
+        Some(room_id)
+    }
+
+    /// Starts a new room, unless the server already hosts as many as it can.
+    fn start_room(&mut self) -> Option<RoomId> {
+        if self.rooms.len() >= self.max_rooms as usize {
+            return None;
+        }
+
+        let room_id = RoomId(self.next_room_id);
+        let Some(next_room_id) = self.next_room_id.checked_add(1) else {
+            warn!("ran out of room IDs");
+            return None;
+        };
+        self.next_room_id = next_room_id;
+
+        let ctx = RoomContext {
+            id: room_id,
+            lobby_tx: self.tx.clone(),
+            player_ids: self.player_ids.clone(),
+            replay_dir: self.replay_dir.clone(),
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            if let Err(e) = WaitingRoom::new(rx, ctx.clone()).run().await {
+                warn!("{:?} closed with an error: {}", room_id, e);
+            }
+            ctx.send_to_lobby(ServerInternalEvent::RoomClosed(room_id));
+        });
+
+        info!("opened {:?}", room_id);
+
+        self.rooms.insert(room_id, tx);
+        Some(room_id)
+    }
+
+    fn send_to_room(&self, room_id: RoomId, event: ServerInternalEvent) {
+        let Some(room) = self.rooms.get(&room_id) else {
+            warn!("no such room: {:?}", room_id);
+            return;
+        };
+
+        // The room may have just ended; dropping the event closes the requester's channel.
+        room.send(event).ok();
+    }
+}
@@ -1,71 +1,243 @@
 use crate::{InboundEvent, OutboundEvent};
 use algo_core::{
-    player::{AssignPlayerId, PlayerId},
+    event::GameEvent,
+    player::PlayerId,
     settings::GameSettings,
+    sim::{self, Policy, RandomPolicy},
     Game, NextEventError,
 };
 use anyhow::{bail, Context as _};
 use protocol::{
-    server_to_client::{JoinInfo, JoinedPlayerInfo},
+    client_to_server::{CHAT_MAX_LEN, PLAYER_NAME_MAX_LEN},
+    server_to_client::{JoinInfo, JoinedPlayerInfo, ReconnectToken, ServerError},
     WithMetadata,
 };
-use std::collections::BTreeMap;
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    future::Future,
+    io::BufWriter,
+    task::Poll,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::mpsc::{OwnedPermit, Sender, UnboundedReceiver},
+    time::Instant,
+};
 use tracing::{debug, info, warn};
 
+mod event_log;
+use event_log::EventLog;
+
+mod lobby;
+pub use lobby::Lobby;
+use lobby::{RoomContext, RoomId};
+
 mod player_handler;
 use player_handler::PlayerHandler;
 
+/// The number of players to wait for before starting the game.
+const ROOM_SIZE: u8 = 2;
+
+/// How long the seat of a disconnected player is reserved.
+const RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// How long the players have to agree on a rematch after the game has ended.
+const REMATCH_WINDOW: Duration = Duration::from_secs(30);
+
+/// The display name of the bot.
+const BOT_NAME: &str = "Bot";
+
 #[derive(Debug, Clone)]
 pub enum ServerInternalEvent {
     // inbound
     In(PlayerId, WithMetadata<InboundEvent>),
-    RequestJoin(UnboundedSender<Self>),
+    RequestJoin {
+        settings: Option<GameSettings>,
+        name: Option<String>,
+        /// Whether the player requested a game against a bot.
+        vs_bot: bool,
+        /// Whether the server is to acknowledge the events requiring no decision for the player.
+        auto_ack: bool,
+        tx: Sender<Self>,
+    },
+    RequestRejoin(PlayerId, ReconnectToken, Sender<Self>),
+    RequestSpectate(Sender<Self>),
     ConnectionLost(PlayerId),
 
+    // lobby
+    /// A player has been seated in the room, and is to be routed to it from now on.
+    PlayerSeated(RoomId, PlayerId),
+    /// A player has left the room before its game started, and is no longer routed to it.
+    PlayerLeft(RoomId, PlayerId),
+    /// The room has started its game, and takes no more players.
+    RoomStarted(RoomId),
+    RoomClosed(RoomId),
+
     // outbound
     Out(WithMetadata<OutboundEvent>),
     RequestJoinAccepted(JoinInfo),
+    RequestJoinRejected(ServerError, Box<str>),
+    RequestRejoinAccepted,
+    RequestRejoinRejected(ServerError, Box<str>),
+    RequestSpectateAccepted((PlayerId, PlayerId)),
+    RequestSpectateRejected(ServerError, Box<str>),
 }
 
 #[derive(Debug)]
-pub struct WaitingRoom {
+struct WaitingRoom {
     rx: UnboundedReceiver<ServerInternalEvent>,
+    ctx: RoomContext,
+    /// The seed of the first game, which is random if `None`.
+    seed: Option<u64>,
 }
 
 impl WaitingRoom {
-    pub fn new(rx: UnboundedReceiver<ServerInternalEvent>) -> Self {
-        Self { rx }
+    fn new(rx: UnboundedReceiver<ServerInternalEvent>, ctx: RoomContext) -> Self {
+        Self {
+            rx,
+            ctx,
+            seed: None,
+        }
+    }
+
+    /// Fixes the seed of the first game, so that it can be played again exactly.
+    #[cfg(test)]
+    fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
     }
 
     pub async fn run(mut self) -> anyhow::Result<()> {
         let mut player_handlers = BTreeMap::<_, PlayerHandler>::new();
-        let mut room = WaitingRoomSeats::default();
-        let mut new_player_id = AssignPlayerId::default();
+        let mut room = Seats::new(ROOM_SIZE);
+        let mut settings = GameSettings::default();
+        let mut names = BTreeMap::new();
+        let mut reconnect_tokens = BTreeMap::new();
+        let mut bot_player = None;
 
         while !room.is_full() {
             let Some(ev) = self.rx.recv().await else {
                 bail!("server internal error: channel closed");
             };
             match ev {
-                ServerInternalEvent::RequestJoin(tx) => {
-                    let player_id = new_player_id.assign();
+                ServerInternalEvent::RequestJoin {
+                    settings: requested_settings,
+                    name,
+                    vs_bot,
+                    auto_ack,
+                    tx,
+                } => {
+                    // The lobby gives such players a room of their own.
+                    if vs_bot && !player_handlers.is_empty() {
+                        tx.try_send(ServerInternalEvent::RequestJoinRejected(
+                            ServerError::RoomFull,
+                            "another player is already waiting".into(),
+                        ))
+                        .ok();
+                        continue;
+                    }
+
+                    // The first player to join decides the settings.
+                    if player_handlers.is_empty() {
+                        let requested_settings = requested_settings.unwrap_or_default();
+                        if let Err(e) = requested_settings.validate() {
+                            tx.try_send(ServerInternalEvent::RequestJoinRejected(
+                                ServerError::InvalidSettings,
+                                e.to_string().into(),
+                            ))
+                            .ok();
+                            continue;
+                        }
+                        settings = requested_settings;
+                    } else if requested_settings.is_some() {
+                        debug!("ignoring settings requested by the second player");
+                    }
+
+                    let ids = self.ctx.assign_player_id().and_then(|player_id| {
+                        let bot_id = vs_bot.then(|| self.ctx.assign_player_id()).transpose()?;
+                        Ok((player_id, bot_id))
+                    });
+                    let (player_id, bot_id) = match ids {
+                        Ok(v) => v,
+                        Err(e) => {
+                            warn!("cannot seat a new player: {}", e);
+                            tx.try_send(ServerInternalEvent::RequestJoinRejected(
+                                ServerError::Internal,
+                                e.to_string().into(),
+                            ))
+                            .ok();
+                            continue;
+                        }
+                    };
+
+                    let mut join_info = room.try_claim(player_id)?;
+
+                    let name = name
+                        .map(|v| sanitize_text(&v, PLAYER_NAME_MAX_LEN))
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or_else(|| {
+                            format!("Player {}", join_info.joined_player.join_position())
+                        });
+                    names.insert(player_id, name);
+                    join_info.names = names.clone();
+
+                    let reconnect_token = rand::random();
+                    reconnect_tokens.insert(player_id, reconnect_token);
 
-                    let join_info = room.try_claim(player_id)?;
-                    tx.send(ServerInternalEvent::RequestJoinAccepted(join_info))?;
+                    // Route the player's events here before they can send any.
+                    self.ctx
+                        .send_to_lobby(ServerInternalEvent::PlayerSeated(self.ctx.id, player_id));
+
+                    tx.try_send(ServerInternalEvent::RequestJoinAccepted(JoinInfo {
+                        reconnect_token: Some(reconnect_token),
+                        auto_ack,
+                        ..join_info.clone()
+                    }))?;
 
                     // Notify that the new player joined the server to waiting players.
                     for handler in player_handlers.values_mut() {
+                        handler.send_message(OutboundEvent::PlayerJoined(join_info.clone()))?;
+                    }
+
+                    let mut handler = PlayerHandler::new(tx).with_auto_ack(auto_ack);
+
+                    if let Some(bot_id) = bot_id {
+                        let mut join_info = room.try_claim(bot_id)?;
+                        names.insert(bot_id, BOT_NAME.to_string());
+                        join_info.names = names.clone();
+
+                        info!("a bot joined as {:?}", bot_id);
+
                         handler.send_message(OutboundEvent::PlayerJoined(join_info))?;
+                        bot_player = Some(bot_id);
                     }
 
-                    player_handlers.insert(player_id, PlayerHandler::new(tx));
+                    player_handlers.insert(player_id, handler);
                 }
                 ServerInternalEvent::ConnectionLost(player_id) => {
                     info!("player {:?} left the waiting room", player_id);
 
                     room.remove(player_id);
                     player_handlers.remove(&player_id);
+                    names.remove(&player_id);
+                    reconnect_tokens.remove(&player_id);
+                    self.ctx
+                        .send_to_lobby(ServerInternalEvent::PlayerLeft(self.ctx.id, player_id));
+                }
+                ServerInternalEvent::RequestRejoin(_, _, tx) => {
+                    tx.try_send(ServerInternalEvent::RequestRejoinRejected(
+                        ServerError::SeatUnavailable,
+                        "the game has not started yet".into(),
+                    ))
+                    .ok();
+                }
+                ServerInternalEvent::RequestSpectate(tx) => {
+                    tx.try_send(ServerInternalEvent::RequestSpectateRejected(
+                        ServerError::NoGameInProgress,
+                        "the game has not started yet".into(),
+                    ))
+                    .ok();
                 }
                 unexpected => {
                     warn!("unexpected event: {:?}", unexpected);
@@ -73,97 +245,272 @@ impl WaitingRoom {
             }
         }
 
-        let player_ids = {
-            let mut keys = player_handlers.keys().cloned();
-            (keys.next().unwrap(), keys.next().unwrap())
+        let &[first, second] = room.players.as_slice() else {
+            bail!(
+                "only 2-player games are supported: {} players are seated",
+                room.players.len()
+            );
         };
+        let player_ids = (first, second);
+
+        info!("starting the game in {:?}: {:?}", self.ctx.id, settings);
+        self.ctx
+            .send_to_lobby(ServerInternalEvent::RoomStarted(self.ctx.id));
 
-        let game = Game::for_2_players(player_ids, GameSettings::default())?;
-        GameInstance::new(self.rx, game, player_handlers)
-            .run()
-            .await
+        let bots = bot_player
+            .into_iter()
+            .map(|id| {
+                let policy: Box<dyn Policy + Send> =
+                    Box::new(RandomPolicy::new(&settings, rand::random()));
+                (id, policy)
+            })
+            .collect();
+
+        let game = match self.seed {
+            Some(seed) => Game::for_2_players_seeded(player_ids, settings, seed)?,
+            None => Game::for_2_players(player_ids, settings)?,
+        };
+        GameInstance::new(
+            self.rx,
+            self.ctx,
+            game,
+            player_handlers,
+            bots,
+            reconnect_tokens,
+        )
+        .run()
+        .await
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-enum WaitingRoomSeats {
-    #[default]
-    Empty,
-    One(PlayerId),
-    Two(PlayerId, PlayerId),
+/// The seats of the waiting room, in the order the players joined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Seats {
+    capacity: u8,
+    players: Vec<PlayerId>,
 }
 
-impl WaitingRoomSeats {
-    const ROOM_SIZE: u8 = 2;
+impl Seats {
+    fn new(capacity: u8) -> Self {
+        Self {
+            capacity,
+            players: Vec::with_capacity(capacity as usize),
+        }
+    }
 
     fn is_full(&self) -> bool {
-        matches!(self, Self::Two(..))
+        self.players.len() >= self.capacity as usize
     }
 
     fn try_claim(&mut self, new_player: PlayerId) -> anyhow::Result<JoinInfo> {
-        *self = match self {
-            Self::Empty => Self::One(new_player),
-            Self::One(id) => Self::Two(*id, new_player),
-            Self::Two(..) => bail!("room is full"),
-        };
+        if self.is_full() {
+            bail!("room is full");
+        }
 
         let ret = JoinInfo {
-            joined_player: match self {
-                Self::One(id) => JoinedPlayerInfo::First(*id),
-                Self::Two(id, id2) => JoinedPlayerInfo::Second {
-                    just_joined: *id2,
-                    waiting_player: *id,
-                },
-                Self::Empty => unreachable!(),
+            joined_player: JoinedPlayerInfo {
+                just_joined: new_player,
+                waiting_players: self.players.clone(),
             },
-            room_size: Self::ROOM_SIZE,
+            room_size: self.capacity,
+            names: BTreeMap::new(),
+            reconnect_token: None,
+            auto_ack: false,
         };
+        self.players.push(new_player);
+
         Ok(ret)
     }
 
     fn remove(&mut self, player: PlayerId) {
-        match self {
-            Self::Empty => (),
-            Self::One(player_id) if *player_id == player => {
-                *self = Self::Empty;
-            }
-            Self::Two(a, b) => {
-                if *a == player {
-                    *self = Self::One(*b);
-                } else if *b == player {
-                    *self = Self::One(*a);
-                }
-            }
-            _ => (),
-        }
+        self.players.retain(|id| *id != player);
     }
 }
 
 struct GameInstance {
     rx: UnboundedReceiver<ServerInternalEvent>,
+    ctx: RoomContext,
     game: Game,
     player_handlers: BTreeMap<PlayerId, PlayerHandler>,
+    /// The players controlled by the server, which respond to the events in-process.
+    bots: BTreeMap<PlayerId, Box<dyn Policy + Send>>,
+    spectators: Vec<PlayerHandler>,
+    /// The tokens the players must present to rejoin.
+    reconnect_tokens: BTreeMap<PlayerId, ReconnectToken>,
+    event_log: Option<EventLog>,
 }
 
 impl GameInstance {
     fn new(
         rx: UnboundedReceiver<ServerInternalEvent>,
+        ctx: RoomContext,
         game: Game,
         player_handlers: BTreeMap<PlayerId, PlayerHandler>,
+        bots: BTreeMap<PlayerId, Box<dyn Policy + Send>>,
+        reconnect_tokens: BTreeMap<PlayerId, ReconnectToken>,
     ) -> Self {
-        debug!("created GameInstance: handlers={:?}", player_handlers);
+        debug!(
+            "created GameInstance: handlers={:?}, seed={}",
+            player_handlers,
+            game.seed()
+        );
 
+        let event_log = EventLog::from_env(ctx.id);
         Self {
             rx,
+            ctx,
             game,
             player_handlers,
+            bots,
+            spectators: Vec::new(),
+            reconnect_tokens,
+            event_log,
         }
     }
 
     async fn run(mut self) -> anyhow::Result<()> {
-        while self.run_inner().await? == GameInstanceStatus::KeepAlive {}
+        loop {
+            let status = match self.run_inner().await {
+                Ok(GameInstanceStatus::KeepAlive) => continue,
+                Ok(status) => status,
+                Err(e) => {
+                    self.notify_aborted(&e);
+                    return Err(e);
+                }
+            };
 
-        Ok(())
+            self.save_replays();
+
+            if status == GameInstanceStatus::ShouldShutdown || !self.wait_for_rematch().await? {
+                return Ok(());
+            }
+
+            info!("starting a rematch");
+
+            let settings = self.game.settings().clone();
+            self.game = Game::for_2_players(self.player_ids(), settings)?;
+            for handler in self
+                .player_handlers
+                .values_mut()
+                .chain(&mut self.spectators)
+            {
+                handler.start_new_game();
+            }
+            self.notify_all(OutboundEvent::RematchStarted)?;
+        }
+    }
+
+    /// Waits for all players to accept a rematch.
+    ///
+    /// Returns `false` if any player declines, leaves, or does not answer in time.
+    async fn wait_for_rematch(&mut self) -> anyhow::Result<bool> {
+        let deadline = Instant::now() + REMATCH_WINDOW;
+
+        // Bots are always up for another game.
+        let mut accepted = self.bots.keys().copied().collect::<BTreeSet<_>>();
+
+        loop {
+            let backlog_room = self.backlog_room();
+            let recv = tokio::select! {
+                v = self.rx.recv() => v,
+                (recipient, room) = backlog_room => {
+                    self.flush_backlog(recipient, room);
+                    continue;
+                }
+                _ = tokio::time::sleep_until(deadline) => {
+                    info!("no rematch was agreed in time");
+                    return Ok(false);
+                }
+            };
+            let Some(ev) = recv else {
+                bail!("server internal error: channel closed");
+            };
+
+            match ev {
+                ServerInternalEvent::In(player_id, ev) => {
+                    if let Err(e) = self.verify_player_id(player_id) {
+                        warn!("{}", e);
+                        continue;
+                    }
+
+                    match ev.event {
+                        InboundEvent::RequestRematch => {
+                            info!("player {:?} requested a rematch", player_id);
+                            accepted.insert(player_id);
+                            self.notify_others(
+                                player_id,
+                                OutboundEvent::RematchRequested(player_id),
+                            )?;
+
+                            if self.player_handlers.keys().all(|id| accepted.contains(id)) {
+                                return Ok(true);
+                            }
+                        }
+                        InboundEvent::RematchDeclined => {
+                            info!("player {:?} declined a rematch", player_id);
+                            self.notify_others(
+                                player_id,
+                                OutboundEvent::RematchDeclined(player_id),
+                            )?;
+                            return Ok(false);
+                        }
+                        InboundEvent::Chat(text) => {
+                            self.relay_chat(player_id, &text)?;
+                        }
+                        unexpected => {
+                            debug!("ignoring {:?} after the game", unexpected);
+                        }
+                    }
+                }
+                ServerInternalEvent::ConnectionLost(player_id) => {
+                    if self.verify_player_id(player_id).is_err() {
+                        continue;
+                    }
+
+                    info!("player {:?} left after the game", player_id);
+                    self.notify_player_disconnected(player_id)?;
+                    return Ok(false);
+                }
+                ev @ ServerInternalEvent::RequestJoin { .. } => {
+                    self.ctx.send_to_lobby(ev);
+                }
+                ServerInternalEvent::RequestRejoin(_, _, tx) => {
+                    tx.try_send(ServerInternalEvent::RequestRejoinRejected(
+                        ServerError::SeatUnavailable,
+                        "the game has ended".into(),
+                    ))
+                    .ok();
+                }
+                ServerInternalEvent::RequestSpectate(tx) => {
+                    tx.try_send(ServerInternalEvent::RequestSpectateRejected(
+                        ServerError::NoGameInProgress,
+                        "the game has ended".into(),
+                    ))
+                    .ok();
+                }
+                unexpected => {
+                    warn!("unexpected event: {:?}", unexpected);
+                }
+            }
+        }
+    }
+
+    /// Tells the players and spectators that the game is terminated by the error.
+    ///
+    /// Failures are ignored, as the game is being torn down anyway.
+    fn notify_aborted(&mut self, error: &anyhow::Error) {
+        warn!("aborting the game: {}", error);
+
+        let message = OutboundEvent::GameAborted {
+            reason: error.to_string().into(),
+        };
+        for handler in self
+            .player_handlers
+            .values_mut()
+            .chain(&mut self.spectators)
+        {
+            handler.send_message(message.clone()).ok();
+        }
     }
 
     /// # Lifecycle
@@ -181,31 +528,164 @@ impl GameInstance {
                 }
                 NextEventError::NoMoreEvent => {
                     info!("no more event to send to the clients");
-                    return Ok(GameInstanceStatus::ShouldShutdown);
+                    return Ok(GameInstanceStatus::Finished);
                 }
             },
         };
 
+        let event_for_each_player = event_for_each_player.collect::<Vec<_>>();
+        let mut decision_required = false;
+        let mut all_responded = false;
+
         for (player_id, game_ev) in event_for_each_player {
             debug!("new GameEvent for {:?}: {:?}", player_id, game_ev);
 
-            self.player_handlers
-                .get_mut(&player_id)
-                .context(format!(
-                    "server internal error: unknown player: {:?}",
-                    player_id
-                ))?
-                .send_game_event(game_ev)?;
+            decision_required |= game_ev.is_decision_required();
+
+            if let Some(event_log) = &mut self.event_log {
+                if let Err(e) = event_log.record(self.game.seed(), player_id, &game_ev) {
+                    // Disable the log so that the game can go on.
+                    warn!("failed to record an event; disabling the event log: {}", e);
+                    self.event_log = None;
+                }
+            }
+
+            if let Some(policy) = self.bots.get_mut(&player_id) {
+                let resp = sim::respond(&self.game, player_id, &game_ev, policy.as_mut())?;
+                debug!("bot {:?} responded: {:?}", player_id, resp);
+                all_responded = self.game.store_player_response(player_id, resp)?;
+                continue;
+            }
+
+            let handler = self.player_handlers.get_mut(&player_id).context(format!(
+                "server internal error: unknown player: {:?}",
+                player_id
+            ))?;
+            let auto_ack = handler.is_auto_acked(&game_ev);
+            handler.send_game_event(game_ev)?;
+
+            // The client moves on to the next event without waiting for a round trip.
+            if auto_ack {
+                all_responded = self
+                    .game
+                    .store_player_response(player_id, GameEvent::RespOk)?;
+            }
+        }
+
+        if let Some(game_ev) = self.game.spectator_event() {
+            for spectator in &mut self.spectators {
+                spectator.send_game_event(game_ev.clone())?;
+            }
+            self.spectators.retain(PlayerHandler::is_connected);
+        }
+
+        if all_responded {
+            self.game.process_event()?;
+            return Ok(GameInstanceStatus::KeepAlive);
+        }
+
+        // Only decisions are timed, as acknowledgements are sent back immediately.
+        let mut decision_timer = decision_required
+            .then_some(self.game.settings().decision_timeout)
+            .map(DecisionTimer::start);
+
+        // The game is paused while any seat is vacant.
+        if self.reconnect_deadline().is_some() {
+            decision_timer.as_mut().map(DecisionTimer::pause);
         }
 
         loop {
-            let Some(ev) = self.rx.recv().await else {
+            let reconnect_deadline = self.reconnect_deadline();
+            let decision_deadline = decision_timer.as_ref().and_then(DecisionTimer::deadline);
+            let backlog_room = self.backlog_room();
+
+            let recv = tokio::select! {
+                v = self.rx.recv() => v,
+                (recipient, room) = backlog_room => {
+                    self.flush_backlog(recipient, room);
+                    continue;
+                }
+                _ = sleep_until(reconnect_deadline) => {
+                    return self.forfeit_disconnected_players();
+                }
+                _ = sleep_until(decision_deadline) => {
+                    return self.forfeit_unresponsive_players();
+                }
+            };
+            let Some(ev) = recv else {
+                if self.is_abandoned() {
+                    return Ok(GameInstanceStatus::ShouldShutdown);
+                }
                 bail!("server internal error: channel closed");
             };
 
             match ev {
-                ServerInternalEvent::RequestJoin(_) => {
-                    warn!("invalid event: RequestJoin");
+                ev @ ServerInternalEvent::RequestJoin { .. } => {
+                    // Sent before the lobby learned that the room is full.
+                    self.ctx.send_to_lobby(ev);
+                }
+                ServerInternalEvent::RequestRejoin(player_id, token, tx) => {
+                    if self.reconnect_tokens.get(&player_id) != Some(&token) {
+                        warn!("invalid reconnect token for {:?}", player_id);
+                        tx.try_send(ServerInternalEvent::RequestRejoinRejected(
+                            ServerError::SeatUnavailable,
+                            "invalid reconnect token".into(),
+                        ))
+                        .ok();
+                        continue;
+                    }
+
+                    let Some(handler) = self
+                        .player_handlers
+                        .get_mut(&player_id)
+                        .filter(|handler| !handler.is_connected())
+                    else {
+                        tx.try_send(ServerInternalEvent::RequestRejoinRejected(
+                            ServerError::SeatUnavailable,
+                            "no reserved seat for the player".into(),
+                        ))
+                        .ok();
+                        continue;
+                    };
+
+                    info!("player {:?} reconnected", player_id);
+
+                    if tx
+                        .try_send(ServerInternalEvent::RequestRejoinAccepted)
+                        .is_err()
+                    {
+                        continue;
+                    }
+
+                    let resend = self.game.unresponsive_players().any(|id| id == player_id);
+                    handler.reattach(tx, resend)?;
+
+                    self.notify_others(player_id, OutboundEvent::PlayerReconnected(player_id))?;
+
+                    // The waiting player gets the full time again,
+                    // as the disconnection may have interrupted their thinking.
+                    if self.reconnect_deadline().is_none() {
+                        decision_timer.as_mut().map(DecisionTimer::restart);
+                    }
+                }
+                ServerInternalEvent::RequestSpectate(tx) => {
+                    if tx
+                        .try_send(ServerInternalEvent::RequestSpectateAccepted(
+                            self.player_ids(),
+                        ))
+                        .is_err()
+                    {
+                        continue;
+                    }
+
+                    info!("new spectator joined");
+
+                    // Catch up on the game so far, as the connection makes room.
+                    let mut spectator = PlayerHandler::new(tx);
+                    spectator.catch_up(self.game.spectator_history());
+                    if spectator.is_connected() {
+                        self.spectators.push(spectator);
+                    }
                 }
                 ServerInternalEvent::ConnectionLost(player_id) => {
                     if let Err(e) = self.verify_player_id(player_id) {
@@ -213,13 +693,21 @@ impl GameInstance {
                         continue;
                     }
 
-                    for (_, handler) in self
-                        .player_handlers
-                        .iter_mut()
-                        .filter(|(id, _)| **id != player_id)
-                    {
-                        handler.notify_player_disconnected(player_id)?;
+                    info!("player {:?} lost connection", player_id);
+
+                    self.player_handlers
+                        .get_mut(&player_id)
+                        .expect("should be `Some`; the ID is verified")
+                        .detach();
+
+                    if self.is_abandoned() {
+                        info!("all players left; closing the game");
+                        return Ok(GameInstanceStatus::ShouldShutdown);
                     }
+
+                    decision_timer.as_mut().map(DecisionTimer::pause);
+
+                    self.notify_others(player_id, OutboundEvent::PlayerReconnecting(player_id))?;
                 }
                 ServerInternalEvent::In(player_id, ev) => {
                     if let Err(e) = self.verify_player_id(player_id) {
@@ -227,6 +715,41 @@ impl GameInstance {
                         continue;
                     }
 
+                    if let InboundEvent::Chat(text) = ev.event {
+                        self.relay_chat(player_id, &text)?;
+                        continue;
+                    }
+
+                    if let InboundEvent::RequestResync { last_seq } = ev.event {
+                        info!(
+                            "player {:?} requested a resync after #{}",
+                            player_id, last_seq
+                        );
+                        if let Err(e) = self
+                            .player_handlers
+                            .get_mut(&player_id)
+                            .expect("should be `Some`; the ID is verified")
+                            .resync(last_seq)
+                        {
+                            warn!("rejected resync from {:?}: {}", player_id, e);
+                        }
+                        continue;
+                    }
+
+                    if ev.event == InboundEvent::Surrender {
+                        match self.game.surrender(player_id) {
+                            Ok(()) => {
+                                info!("player {:?} surrendered", player_id);
+                                // Start over with the surrender instead of the staged event.
+                                return Ok(GameInstanceStatus::KeepAlive);
+                            }
+                            Err(e) => {
+                                warn!("rejected surrender from {:?}: {}", player_id, e);
+                                continue;
+                            }
+                        }
+                    }
+
                     let Some(game_event_resp) = self
                         .player_handlers
                         .get_mut(&player_id)
@@ -239,7 +762,10 @@ impl GameInstance {
                     match self.game.store_player_response(player_id, game_event_resp) {
                         Ok(true) => break,
                         Ok(false) => continue,
-                        Err(e) => return Err(e),
+                        Err(e) => {
+                            warn!("rejected response from {:?}: {}", player_id, e);
+                            continue;
+                        }
                     }
                 }
                 unexpected => {
@@ -252,6 +778,209 @@ impl GameInstance {
         Ok(GameInstanceStatus::KeepAlive)
     }
 
+    /// Returns a future resolving when a connection with a backlog has room for it,
+    /// or has failed to make room in time.
+    ///
+    /// The future never resolves if there is no backlog.
+    fn backlog_room(
+        &self,
+    ) -> impl Future<Output = (Recipient, Option<OwnedPermit<ServerInternalEvent>>)> + 'static {
+        let mut rooms = self
+            .player_handlers
+            .iter()
+            .map(|(id, handler)| (Recipient::Player(*id), handler))
+            .chain(
+                self.spectators
+                    .iter()
+                    .enumerate()
+                    .map(|(i, handler)| (Recipient::Spectator(i), handler)),
+            )
+            .filter_map(|(recipient, handler)| {
+                let room = handler.backlog_room()?;
+                Some(Box::pin(async move { (recipient, room.await) }))
+            })
+            .collect::<Vec<_>>();
+
+        std::future::poll_fn(move |cx| {
+            rooms
+                .iter_mut()
+                .find_map(|room| match room.as_mut().poll(cx) {
+                    Poll::Ready(v) => Some(v),
+                    Poll::Pending => None,
+                })
+                .map_or(Poll::Pending, Poll::Ready)
+        })
+    }
+
+    /// Sends the backlog of the recipient through the room made for it.
+    ///
+    /// A spectator failing to make room is dropped, without affecting the game.
+    fn flush_backlog(
+        &mut self,
+        recipient: Recipient,
+        room: Option<OwnedPermit<ServerInternalEvent>>,
+    ) {
+        match recipient {
+            Recipient::Player(player_id) => {
+                if let Some(handler) = self.player_handlers.get_mut(&player_id) {
+                    handler.flush_backlog(room);
+                }
+            }
+            Recipient::Spectator(i) => {
+                self.spectators[i].flush_backlog(room);
+                self.spectators.retain(PlayerHandler::is_connected);
+            }
+        }
+    }
+
+    /// Returns the IDs of the players, including bots, in ascending order.
+    fn player_ids(&self) -> (PlayerId, PlayerId) {
+        let mut ids = self
+            .player_handlers
+            .keys()
+            .chain(self.bots.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        ids.sort();
+        (ids[0], ids[1])
+    }
+
+    /// Saves a replay of the game as seen by each player, if the server is configured to.
+    ///
+    /// The file names include the seed, so that the games of different rooms
+    /// and the rematches do not overwrite each other.
+    ///
+    /// Failures are only logged, as replays are not essential to the game.
+    fn save_replays(&self) {
+        let Some(replay_dir) = &self.ctx.replay_dir else {
+            return;
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        for (i, player_id) in self.player_handlers.keys().enumerate() {
+            let path = replay_dir.join(format!(
+                "{}-{:016x}-p{}.replay",
+                timestamp,
+                self.game.seed(),
+                i + 1
+            ));
+
+            let res = self
+                .game
+                .export_replay(Some(*player_id))
+                .and_then(|replay| {
+                    std::fs::create_dir_all(replay_dir)?;
+                    let file = BufWriter::new(File::create(&path)?);
+                    bincode::serialize_into(file, &replay)?;
+                    Ok(())
+                });
+
+            match res {
+                Ok(()) => info!("saved a replay: {}", path.display()),
+                Err(e) => warn!("failed to save a replay {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Returns `true` if no player is connected, so nobody is left to wait for the others.
+    ///
+    /// A game against bots is never abandoned, as the player may still come back.
+    fn is_abandoned(&self) -> bool {
+        self.bots.is_empty()
+            && !self
+                .player_handlers
+                .values()
+                .any(PlayerHandler::is_connected)
+    }
+
+    /// Returns when the earliest reserved seat is released, if any.
+    fn reconnect_deadline(&self) -> Option<Instant> {
+        self.player_handlers
+            .values()
+            .filter_map(PlayerHandler::disconnected_at)
+            .min()
+            .map(|t| t + RECONNECT_GRACE_PERIOD)
+    }
+
+    /// Makes the players who failed to respond in time lose the game.
+    ///
+    /// They are treated as disconnected from the opponents' point of view.
+    fn forfeit_unresponsive_players(&mut self) -> anyhow::Result<GameInstanceStatus> {
+        let unresponsive = self.game.unresponsive_players().collect::<Vec<_>>();
+
+        for player_id in unresponsive {
+            info!("player {:?} did not respond in time", player_id);
+            self.notify_player_disconnected(player_id)?;
+        }
+
+        Ok(GameInstanceStatus::ShouldShutdown)
+    }
+
+    /// Makes the players who did not reconnect in time lose the game.
+    fn forfeit_disconnected_players(&mut self) -> anyhow::Result<GameInstanceStatus> {
+        let disconnected = self
+            .player_handlers
+            .iter()
+            .filter(|(_, handler)| !handler.is_connected())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for player_id in disconnected {
+            info!("player {:?} did not reconnect in time", player_id);
+            self.notify_player_disconnected(player_id)?;
+        }
+
+        Ok(GameInstanceStatus::ShouldShutdown)
+    }
+
+    /// Notifies the other players that the specified player has left the game.
+    fn notify_player_disconnected(&mut self, player_id: PlayerId) -> anyhow::Result<()> {
+        self.notify_others(player_id, OutboundEvent::PlayerDisconnected(player_id))
+    }
+
+    /// Sends the message to all players except the specified one, and to all spectators.
+    fn notify_others(&mut self, player_id: PlayerId, message: OutboundEvent) -> anyhow::Result<()> {
+        for (_, handler) in self
+            .player_handlers
+            .iter_mut()
+            .filter(|(id, _)| **id != player_id)
+        {
+            handler.send_message(message.clone())?;
+        }
+        for spectator in &mut self.spectators {
+            spectator.send_message(message.clone())?;
+        }
+        self.spectators.retain(PlayerHandler::is_connected);
+        Ok(())
+    }
+
+    /// Sends the message to all players and spectators.
+    fn notify_all(&mut self, message: OutboundEvent) -> anyhow::Result<()> {
+        for handler in self.player_handlers.values_mut() {
+            handler.send_message(message.clone())?;
+        }
+        for spectator in &mut self.spectators {
+            spectator.send_message(message.clone())?;
+        }
+        self.spectators.retain(PlayerHandler::is_connected);
+        Ok(())
+    }
+
+    /// Sends the chat message to the other player and spectators.
+    ///
+    /// Control characters are removed and the message is truncated to [`CHAT_MAX_LEN`].
+    fn relay_chat(&mut self, from: PlayerId, text: &str) -> anyhow::Result<()> {
+        let text = sanitize_text(text, CHAT_MAX_LEN);
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        debug!("chat from {:?}: {}", from, text);
+        self.notify_others(from, OutboundEvent::Chat { from, text })
+    }
+
     fn verify_player_id(&self, player_id: PlayerId) -> anyhow::Result<()> {
         if !self.player_handlers.keys().any(|id| *id == player_id) {
             bail!("unknown PlayerId: {:?}", player_id);
@@ -260,8 +989,75 @@ impl GameInstance {
     }
 }
 
+/// A connection of the room, identified for the time of a single `select!`.
+#[derive(Debug, Clone, Copy)]
+enum Recipient {
+    Player(PlayerId),
+    /// The index in `GameInstance::spectators`.
+    Spectator(usize),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum GameInstanceStatus {
     KeepAlive,
+    /// The game has ended normally, and a rematch can follow.
+    Finished,
     ShouldShutdown,
 }
+
+/// A countdown for a player's decision, which can be paused.
+#[derive(Debug, Clone, Copy)]
+struct DecisionTimer {
+    timeout: Duration,
+    remaining: Duration,
+    /// `None` while paused.
+    resumed_at: Option<Instant>,
+}
+
+impl DecisionTimer {
+    fn start(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            remaining: timeout,
+            resumed_at: Some(Instant::now()),
+        }
+    }
+
+    /// Returns `None` while paused, or if the deadline is too far to be represented.
+    fn deadline(&self) -> Option<Instant> {
+        self.resumed_at.and_then(|t| t.checked_add(self.remaining))
+    }
+
+    fn pause(&mut self) {
+        if let Some(t) = self.resumed_at.take() {
+            self.remaining = self.remaining.saturating_sub(t.elapsed());
+        }
+    }
+
+    /// Starts the countdown over from the full timeout.
+    fn restart(&mut self) {
+        *self = Self::start(self.timeout);
+    }
+}
+
+/// Removes control characters and surrounding whitespace from the text,
+/// and truncates it to `max_len` characters.
+fn sanitize_text(text: &str, max_len: usize) -> String {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .take(max_len)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Sleeps until the deadline, or forever if there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -1,58 +1,245 @@
 use crate::{InboundEvent, OutboundEvent};
-use algo_core::{event::GameEvent, player::PlayerId};
-use protocol::{EventKind, NextEventId, WithMetadata};
-use tokio::sync::mpsc::UnboundedSender;
+use algo_core::event::GameEvent;
+use anyhow::bail;
+use protocol::{EventId, EventKind, NextEventId, WithMetadata};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    future::Future,
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{error::TrySendError, OwnedPermit, Sender},
+    time::Instant,
+};
 use tracing::{debug, warn};
 
 use super::ServerInternalEvent;
 
+/// How long a burst of events catching up a client may wait for its connection to make room.
+const CATCH_UP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a client may request a resync, as each one resends the game so far.
+const RESYNC_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
 pub struct PlayerHandler {
     // sender
-    tx: UnboundedSender<ServerInternalEvent>,
+    /// `None` while the player is disconnected.
+    tx: Option<Sender<ServerInternalEvent>>,
     next_id: NextEventId,
+    /// The last `GameEvent` sent, kept to be resent on reconnection.
+    last_game_event: Option<WithMetadata<OutboundEvent>>,
+    /// All `GameEvent`s sent in the current game; the sequence number of each is its index + 1.
+    game_events: Vec<GameEvent>,
+    disconnected_at: Option<Instant>,
+    /// Messages waiting for the connection to make room, to be sent before any newer one.
+    backlog: VecDeque<WithMetadata<OutboundEvent>>,
+    /// When the player is disconnected if the backlog has not been sent.
+    backlog_deadline: Option<Instant>,
+    /// When the client last requested a resync.
+    last_resync: Option<Instant>,
+    /// Whether the `GameEvent`s requiring no decision are acknowledged on the player's behalf.
+    auto_ack: bool,
 
     // receiver
     expected_response_id: Option<protocol::EventId>,
+    /// `GameEvent`s resent by [`resync`], whose responses are not needed.
+    ///
+    /// [`resync`]: `PlayerHandler::resync`
+    resynced_ids: BTreeSet<EventId>,
 }
 
 impl PlayerHandler {
-    pub fn new(tx: UnboundedSender<ServerInternalEvent>) -> Self {
+    pub fn new(tx: Sender<ServerInternalEvent>) -> Self {
         Self {
-            tx,
+            tx: Some(tx),
             next_id: NextEventId::default(),
+            last_game_event: None,
+            game_events: Vec::new(),
+            disconnected_at: None,
+            backlog: VecDeque::new(),
+            backlog_deadline: None,
+            last_resync: None,
+            auto_ack: false,
             expected_response_id: None,
+            resynced_ids: BTreeSet::new(),
         }
     }
 
+    /// Lets the server acknowledge the `GameEvent`s requiring no decision,
+    /// as the client has agreed not to respond to them.
+    pub fn with_auto_ack(mut self, auto_ack: bool) -> Self {
+        self.auto_ack = auto_ack;
+        self
+    }
+
+    /// Returns `true` if the server acknowledges the `GameEvent` for the player,
+    /// so that no response is expected from the client.
+    pub fn is_auto_acked(&self, event: &GameEvent) -> bool {
+        self.auto_ack && !event.is_decision_required()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    /// Returns when the player lost connection, if disconnected.
+    pub fn disconnected_at(&self) -> Option<Instant> {
+        self.disconnected_at
+    }
+
+    /// Detaches the lost connection, keeping the player's state.
+    ///
+    /// Messages sent while detached are discarded, as well as the backlog,
+    /// except for the last `GameEvent`, which can be resent by [`reattach`].
+    ///
+    /// [`reattach`]: `PlayerHandler::reattach`
+    pub fn detach(&mut self) {
+        self.tx = None;
+        self.disconnected_at.get_or_insert_with(Instant::now);
+        self.backlog.clear();
+        self.backlog_deadline = None;
+    }
+
+    /// Attaches a new connection to the player.
+    ///
+    /// If `resend_game_event` is `true`,
+    /// the last `GameEvent` is sent again through the new connection.
+    pub fn reattach(
+        &mut self,
+        tx: Sender<ServerInternalEvent>,
+        resend_game_event: bool,
+    ) -> anyhow::Result<()> {
+        self.tx = Some(tx);
+        self.disconnected_at = None;
+
+        if resend_game_event {
+            if let Some(event) = self.last_game_event.clone() {
+                debug!("resending {:?}", event);
+                self.send(event)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn send_message(&mut self, message: OutboundEvent) -> anyhow::Result<()> {
         let id = self.next_id.produce();
 
-        self.tx.send(ServerInternalEvent::Out(WithMetadata {
+        self.send(WithMetadata {
             kind: protocol::EventKind::Request,
             id,
             event: message,
-        }))?;
+        })
+    }
 
-        Ok(())
+    /// Starts the sequence numbers of `GameEvent`s over, as a new game follows.
+    pub fn start_new_game(&mut self) {
+        self.last_game_event = None;
+        self.game_events.clear();
+        self.expected_response_id = None;
+        self.resynced_ids.clear();
     }
 
     pub fn send_game_event(&mut self, event: GameEvent) -> anyhow::Result<()> {
+        let event = self.push_game_event(event);
+        self.send(event)
+    }
+
+    /// Sends the `GameEvent`s that the client has not seen, such as the game so far
+    /// to a spectator who has just joined.
+    ///
+    /// Unlike [`send_game_event`], the burst can be longer than the connection buffers.
+    /// What does not fit is kept in the backlog, to be sent through [`flush_backlog`]
+    /// once the connection makes room.
+    ///
+    /// [`send_game_event`]: `PlayerHandler::send_game_event`
+    /// [`flush_backlog`]: `PlayerHandler::flush_backlog`
+    pub fn catch_up(&mut self, events: impl IntoIterator<Item = GameEvent>) {
+        let events = events
+            .into_iter()
+            .map(|event| self.push_game_event(event))
+            .collect::<Vec<_>>();
+        self.queue_burst(events);
+    }
+
+    /// Resends the `GameEvent`s following `last_seq`, which the client has missed.
+    ///
+    /// Only the response to the last one is expected,
+    /// unless it has already been received.
+    ///
+    /// They are sent through the backlog, as [`catch_up`] does.
+    /// Returns `Err` without resending anything if the previous resync is still being sent,
+    /// or was requested less than [`RESYNC_INTERVAL`] ago.
+    ///
+    /// [`catch_up`]: `PlayerHandler::catch_up`
+    pub fn resync(&mut self, last_seq: u32) -> anyhow::Result<()> {
+        let now = Instant::now();
+        if !self.backlog.is_empty() {
+            bail!("the previous resync is still being sent");
+        }
+        if let Some(last) = self.last_resync.filter(|t| now < *t + RESYNC_INTERVAL) {
+            bail!(
+                "the previous resync was requested {:?} ago",
+                now.duration_since(last)
+            );
+        }
+        self.last_resync = Some(now);
+
+        let first = (last_seq as usize).min(self.game_events.len());
+        let missed = self.game_events[first..].to_vec();
+        let response_pending = self.expected_response_id.is_some();
+
+        debug!("resyncing {} events after #{}", missed.len(), last_seq);
+
+        let mut resent = Vec::with_capacity(missed.len());
+        for (i, event) in missed.into_iter().enumerate() {
+            let seq = (first + i + 1) as u32;
+
+            resent.push(
+                if response_pending && seq as usize == self.game_events.len() {
+                    self.sequenced_game_event(seq, event)
+                } else {
+                    let id = self.next_id.produce();
+                    self.resynced_ids.insert(id);
+                    WithMetadata {
+                        kind: protocol::EventKind::Request,
+                        id,
+                        event: OutboundEvent::GameEvent { seq, event },
+                    }
+                },
+            );
+        }
+        self.queue_burst(resent);
+
+        Ok(())
+    }
+
+    /// Numbers the `GameEvent` as the next one of the game, and returns the message to send.
+    fn push_game_event(&mut self, event: GameEvent) -> WithMetadata<OutboundEvent> {
+        self.game_events.push(event.clone());
+        let seq = self.game_events.len() as u32;
+
+        self.sequenced_game_event(seq, event)
+    }
+
+    /// Returns the message to send the `GameEvent` with, expecting the response to it.
+    fn sequenced_game_event(&mut self, seq: u32, event: GameEvent) -> WithMetadata<OutboundEvent> {
         let id = self.next_id.produce();
+        let auto_acked = self.is_auto_acked(&event);
 
         let event = WithMetadata {
             kind: protocol::EventKind::Request,
             id,
-            event: OutboundEvent::GameEvent(event),
+            event: OutboundEvent::GameEvent { seq, event },
         };
 
         debug!("{:?}", event);
 
-        self.tx.send(ServerInternalEvent::Out(event))?;
+        self.last_game_event = Some(event.clone());
+        self.expected_response_id = (!auto_acked).then_some(id);
 
-        self.expected_response_id = Some(id);
-
-        Ok(())
+        event
     }
 
     pub fn check_for_game_event_response(
@@ -66,8 +253,13 @@ impl PlayerHandler {
             return None;
         }
 
+        if self.resynced_ids.remove(&id) {
+            debug!("ignoring response to a resynced event: id={:?}", id);
+            return None;
+        }
+
         let Some(expected_id) = self.expected_response_id else {
-            warn!("invalid player handler state");
+            warn!("no response is expected: id={:?}, event={:?}", id, event);
             return None;
         };
 
@@ -77,7 +269,11 @@ impl PlayerHandler {
         }
 
         match event {
-            InboundEvent::GameEventResponse(game_event) => Some(game_event),
+            InboundEvent::GameEventResponse(game_event) => {
+                // Accept only one response for each `GameEvent`.
+                self.expected_response_id = None;
+                Some(game_event)
+            }
             unexpected => {
                 warn!(
                     "ignoring unexpected InboundEvent: id={:?}, event={:?}",
@@ -88,15 +284,191 @@ impl PlayerHandler {
         }
     }
 
-    pub fn notify_player_disconnected(&mut self, player_id: PlayerId) -> anyhow::Result<()> {
-        let id = self.next_id.produce();
+    /// Returns a future resolving to the room for the next message of the backlog,
+    /// or to `None` if the connection does not make room in time.
+    ///
+    /// Returns `None` if there is no backlog.
+    pub fn backlog_room(
+        &self,
+    ) -> Option<impl Future<Output = Option<OwnedPermit<ServerInternalEvent>>> + Send + 'static>
+    {
+        if self.backlog.is_empty() {
+            return None;
+        }
+        let tx = self.tx.clone()?;
+        let deadline = self.backlog_deadline?;
 
-        self.tx.send(ServerInternalEvent::Out(WithMetadata {
-            kind: EventKind::Request,
-            id,
-            event: OutboundEvent::PlayerDisconnected(player_id),
-        }))?;
+        Some(async move {
+            tokio::time::timeout_at(deadline, tx.reserve_owned())
+                .await
+                .ok()?
+                .ok()
+        })
+    }
+
+    /// Sends the backlog through the room made by [`backlog_room`],
+    /// or disconnects the player if there is none.
+    ///
+    /// [`backlog_room`]: `PlayerHandler::backlog_room`
+    pub fn flush_backlog(&mut self, room: Option<OwnedPermit<ServerInternalEvent>>) {
+        let Some(room) = room else {
+            warn!("the client is not keeping up with the catch-up; disconnecting");
+            self.detach();
+            return;
+        };
+
+        if let Some(event) = self.backlog.pop_front() {
+            room.send(ServerInternalEvent::Out(event));
+        }
+        self.send_backlog();
+    }
+
+    /// Queues the messages after the backlog, and sends as many of them as the connection takes.
+    fn queue_burst(&mut self, events: impl IntoIterator<Item = WithMetadata<OutboundEvent>>) {
+        if !self.is_connected() {
+            return;
+        }
+
+        self.backlog.extend(events);
+        self.backlog_deadline
+            .get_or_insert_with(|| Instant::now() + CATCH_UP_TIMEOUT);
+        self.send_backlog();
+    }
+
+    /// Sends as much of the backlog as the connection has room for.
+    fn send_backlog(&mut self) {
+        while !self.backlog.is_empty() {
+            let Some(ref tx) = self.tx else {
+                return;
+            };
+
+            let room = match tx.try_reserve() {
+                Ok(room) => room,
+                Err(TrySendError::Full(())) => return,
+                Err(TrySendError::Closed(())) => break,
+            };
+            room.send(ServerInternalEvent::Out(
+                self.backlog.pop_front().expect("should not be empty"),
+            ));
+        }
+
+        if self.backlog.is_empty() {
+            self.backlog_deadline = None;
+        } else {
+            warn!("connection closed while sending an event");
+            self.detach();
+        }
+    }
+
+    fn send(&mut self, event: WithMetadata<OutboundEvent>) -> anyhow::Result<()> {
+        let Some(ref tx) = self.tx else {
+            debug!("player is disconnected. discarding: {:?}", event);
+            return Ok(());
+        };
+
+        if !self.backlog.is_empty() {
+            // Sent after the backlog, to keep the order.
+            self.backlog.push_back(event);
+            return Ok(());
+        }
+
+        match tx.try_send(ServerInternalEvent::Out(event)) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => {
+                // Dropping the sender closes the connection,
+                // which then reports `ConnectionLost` as usual.
+                warn!("the client is not keeping up with the events; disconnecting");
+                self.detach();
+            }
+            Err(TrySendError::Closed(_)) => {
+                // The connection is closed, but `ConnectionLost` has not been handled yet.
+                warn!("connection closed while sending an event");
+                self.detach();
+            }
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn events(n: u32) -> impl Iterator<Item = GameEvent> {
+        (0..n).map(|_| GameEvent::TurnPlayerDrewCard)
+    }
+
+    /// Sends the backlog as the game loop does, until it is sent or given up.
+    async fn flush(handler: &mut PlayerHandler) {
+        while let Some(room) = handler.backlog_room() {
+            let room = room.await;
+            handler.flush_backlog(room);
+        }
+    }
+
+    #[tokio::test]
+    async fn catch_up_outgrows_the_buffer() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let mut handler = PlayerHandler::new(tx);
+
+        let reader = tokio::spawn(async move {
+            let mut seqs = Vec::new();
+            while let Some(ServerInternalEvent::Out(message)) = rx.recv().await {
+                if let OutboundEvent::GameEvent { seq, .. } = message.event {
+                    seqs.push(seq);
+                }
+            }
+            seqs
+        });
+
+        handler.catch_up(events(100));
+        // Newer messages wait for the backlog.
+        handler.send_game_event(GameEvent::TurnEnded).unwrap();
+        flush(&mut handler).await;
+        handler.resync(10).unwrap();
+        flush(&mut handler).await;
+        assert!(handler.is_connected());
+
+        drop(handler);
+        let seqs = reader.await.unwrap();
+        assert_eq!(seqs, (1..=101).chain(11..=101).collect::<Vec<_>>());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resync_is_rate_limited() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let mut handler = PlayerHandler::new(tx);
+        handler.catch_up(events(3));
+        while rx.try_recv().is_ok() {}
+
+        handler.resync(0).unwrap();
+        handler.resync(0).unwrap_err();
+
+        // Still refused while the previous one is being sent.
+        handler.catch_up(events(10));
+        tokio::time::advance(RESYNC_INTERVAL).await;
+        handler.resync(0).unwrap_err();
+
+        while let Some(room) = handler.backlog_room() {
+            while rx.try_recv().is_ok() {}
+            let room = room.await;
+            handler.flush_backlog(room);
+        }
+        handler.resync(0).unwrap();
+        assert!(handler.is_connected());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn catch_up_gives_up_on_stalled_client() {
+        let (tx, _rx) = mpsc::channel(4);
+        let mut handler = PlayerHandler::new(tx);
+
+        handler.catch_up(events(100));
+        assert!(handler.is_connected());
+
+        flush(&mut handler).await;
+        assert!(!handler.is_connected());
+    }
+}
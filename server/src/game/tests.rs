@@ -0,0 +1,245 @@
+//! Plays games through the rooms over in-memory channels, without networking.
+
+use super::{
+    lobby::{Lobby, RoomContext},
+    ServerInternalEvent, WaitingRoom,
+};
+use crate::{InboundEvent, OutboundEvent};
+use algo_core::{
+    event::GameEvent,
+    player::PlayerId,
+    settings::GameSettings,
+    sim::{self, RandomPolicy},
+    Game, NextEventError,
+};
+use protocol::{EventKind, WithMetadata};
+use std::{collections::BTreeMap, time::Duration};
+use tokio::{
+    sync::mpsc::{self, Receiver, UnboundedSender},
+    time::timeout,
+};
+
+/// How long to wait for a message before the room is considered stuck.
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A client connected to the room through a channel.
+struct MockClient {
+    player_id: PlayerId,
+    rx: Receiver<ServerInternalEvent>,
+    policy: RandomPolicy,
+    /// The sequence number of the last `GameEvent` received.
+    last_seq: u32,
+}
+
+impl MockClient {
+    async fn join(
+        room_tx: &UnboundedSender<ServerInternalEvent>,
+        policy: RandomPolicy,
+        vs_bot: bool,
+    ) -> Self {
+        let (tx, mut rx) = mpsc::channel(1024);
+        room_tx
+            .send(ServerInternalEvent::RequestJoin {
+                settings: None,
+                name: None,
+                vs_bot,
+                auto_ack: false,
+                tx,
+            })
+            .unwrap();
+
+        let ServerInternalEvent::RequestJoinAccepted(info) = recv(&mut rx).await else {
+            panic!("the join request was not accepted");
+        };
+
+        Self {
+            player_id: info.joined_player.just_joined,
+            rx,
+            policy,
+            last_seq: 0,
+        }
+    }
+
+    /// Receives messages until the next `GameEvent`, skipping the other ones.
+    async fn recv_game_event(&mut self) -> WithMetadata<GameEvent> {
+        loop {
+            let ServerInternalEvent::Out(message) = recv(&mut self.rx).await else {
+                continue;
+            };
+            if let OutboundEvent::GameEvent { seq, event } = message.event {
+                assert_eq!(
+                    seq,
+                    self.last_seq + 1,
+                    "{:?} skipped an event",
+                    self.player_id
+                );
+                self.last_seq = seq;
+
+                return WithMetadata {
+                    kind: message.kind,
+                    id: message.id,
+                    event,
+                };
+            }
+        }
+    }
+
+    /// Receives messages until one that is not a `GameEvent`.
+    async fn recv_message(&mut self) -> OutboundEvent {
+        loop {
+            if let ServerInternalEvent::Out(message) = recv(&mut self.rx).await {
+                return message.event;
+            }
+        }
+    }
+}
+
+async fn recv(rx: &mut Receiver<ServerInternalEvent>) -> ServerInternalEvent {
+    timeout(RECV_TIMEOUT, rx.recv())
+        .await
+        .expect("timed out waiting for the room")
+        .expect("the room closed the channel")
+}
+
+fn policies(settings: &GameSettings, seed: u64) -> [RandomPolicy; 2] {
+    [
+        RandomPolicy::new(settings, seed),
+        RandomPolicy::new(settings, !seed),
+    ]
+}
+
+/// Plays the game with the policies alone, returning the winner.
+fn winner_of(player_ids: (PlayerId, PlayerId), seed: u64) -> Option<PlayerId> {
+    let settings = GameSettings::default();
+    let [a, b] = policies(&settings, seed);
+    let policies = BTreeMap::from([
+        (player_ids.0, Box::new(a) as Box<dyn sim::Policy>),
+        (player_ids.1, Box::new(b)),
+    ]);
+
+    sim::run_game(settings, seed, policies).unwrap().winner
+}
+
+#[tokio::test]
+async fn plays_seeded_game_to_end() {
+    // The IDs handed out by a fresh room, in the order of joining.
+    let player_ids = (PlayerId::from(1), PlayerId::from(2));
+    // Most random games run out of cards, so find one that is won.
+    let seed = (0..)
+        .find(|seed| winner_of(player_ids, *seed).is_some())
+        .unwrap();
+
+    let (lobby_tx, _lobby_rx) = mpsc::unbounded_channel();
+    let (room_tx, room_rx) = mpsc::unbounded_channel();
+    let room = tokio::spawn(
+        WaitingRoom::new(room_rx, RoomContext::detached(lobby_tx))
+            .with_seed(seed)
+            .run(),
+    );
+
+    let settings = GameSettings::default();
+    let [a, b] = policies(&settings, seed);
+    let mut clients = [
+        MockClient::join(&room_tx, a, false).await,
+        MockClient::join(&room_tx, b, false).await,
+    ];
+    assert_eq!((clients[0].player_id, clients[1].player_id), player_ids);
+
+    // A spectator who never reads must not hold up the game.
+    let (spectator_tx, _spectator_rx) = mpsc::channel(1);
+    room_tx
+        .send(ServerInternalEvent::RequestSpectate(spectator_tx))
+        .unwrap();
+
+    // The same game played locally tells what each client should receive.
+    let mut expected = Game::for_2_players_seeded(player_ids, settings, seed).unwrap();
+    let mut last_event = None;
+
+    loop {
+        let events = match expected.next_event() {
+            Ok(v) => v.collect::<BTreeMap<_, _>>(),
+            Err(NextEventError::NoMoreEvent) => break,
+            Err(e) => panic!("{}", e),
+        };
+
+        for client in &mut clients {
+            let received = client.recv_game_event().await;
+            assert_eq!(received.kind, EventKind::Request);
+            assert_eq!(received.event, events[&client.player_id]);
+            last_event = Some(received.event.clone());
+
+            let resp = sim::respond(
+                &expected,
+                client.player_id,
+                &received.event,
+                &mut client.policy,
+            )
+            .unwrap();
+            expected
+                .store_player_response(client.player_id, resp.clone())
+                .unwrap();
+
+            room_tx
+                .send(ServerInternalEvent::In(
+                    client.player_id,
+                    WithMetadata {
+                        kind: EventKind::Response,
+                        id: received.id,
+                        event: InboundEvent::GameEventResponse(resp),
+                    },
+                ))
+                .unwrap();
+        }
+
+        expected.process_event().unwrap();
+    }
+
+    assert_eq!(last_event, Some(GameEvent::GameEnded));
+    assert!(expected.winner().is_some());
+
+    // Closing the room after the game.
+    let [first, second] = &mut clients;
+    room_tx
+        .send(ServerInternalEvent::In(
+            first.player_id,
+            WithMetadata {
+                kind: EventKind::Request,
+                id: protocol::EventId::from_raw(0),
+                event: InboundEvent::RematchDeclined,
+            },
+        ))
+        .unwrap();
+    assert_eq!(
+        second.recv_message().await,
+        OutboundEvent::RematchDeclined(first.player_id)
+    );
+
+    timeout(RECV_TIMEOUT, room)
+        .await
+        .expect("the room did not close")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn bot_game_gets_a_room_of_its_own() {
+    let (lobby_tx, lobby_rx) = mpsc::unbounded_channel();
+    tokio::spawn(Lobby::new(lobby_rx, lobby_tx.clone(), 2).run());
+
+    let settings = GameSettings::default();
+    let [a, b] = policies(&settings, 0);
+    let mut waiting = MockClient::join(&lobby_tx, a, false).await;
+    let mut vs_bot = MockClient::join(&lobby_tx, b, true).await;
+
+    // The bot takes the second seat, instead of the player already waiting.
+    let OutboundEvent::PlayerJoined(bot) = vs_bot.recv_message().await else {
+        panic!("the bot did not join");
+    };
+    assert_ne!(bot.joined_player.just_joined, waiting.player_id);
+    assert!(matches!(
+        vs_bot.recv_game_event().await.event,
+        GameEvent::GameStarted { .. }
+    ));
+
+    assert!(waiting.rx.try_recv().is_err());
+}
@@ -1,32 +1,120 @@
 // #![allow(unused)]
 // #![warn(unused_mut, unused_must_use)]
-use tracing_subscriber::EnvFilter;
+use anyhow::Context as _;
+use std::path::PathBuf;
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 type InboundEvent = protocol::client_to_server::ClientToServerEvent;
 type OutboundEvent = protocol::server_to_client::ServerToClientEvent;
 
 const ADDR: &str = "0.0.0.0";
 
-const SERVER_MAX_CONNECTION: u16 = 2;
+/// 2 players and up to 6 spectators.
+const CONNECTIONS_PER_ROOM: u16 = 8;
+
+/// The environment variable selecting how messages are serialized: `bincode` (default) or `json`.
+///
+/// Clients must be started with the same format.
+const WIRE_FORMAT_ENV: &str = "ALGO_WIRE_FORMAT";
 
 mod server;
 use server::Server;
 
 mod game;
 
+/// Arguments for launching the server.
+#[derive(argh::FromArgs, Debug)]
+struct ServerArgs {
+    /// path to a log file, rotated daily, written in addition to the console
+    #[argh(option)]
+    log_file: Option<PathBuf>,
+
+    /// number of games that can be played at the same time
+    #[argh(option, default = "1")]
+    max_rooms: u16,
+
+    /// initial size of the buffer that each connection reads messages into, in bytes
+    #[argh(option, default = "protocol::DEFAULT_READ_BUF_SIZE")]
+    read_buf_size: usize,
+
+    /// acknowledge the events requiring no decision on behalf of the clients that support it
+    #[argh(switch)]
+    auto_ack: bool,
+
+    /// directory to save a replay of each finished game in; no replays are saved unless given
+    #[argh(option)]
+    replay_dir: Option<PathBuf>,
+}
+
+/// Sets up the console logger, and the file logger if `log_file` is given.
+///
+/// The returned guard must be held until exit so that the file logs are flushed.
+fn init_tracing(log_file: Option<&PathBuf>) -> anyhow::Result<Option<WorkerGuard>> {
+    fn event_format<S, N>() -> impl tracing_subscriber::fmt::FormatEvent<S, N>
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+        N: for<'a> tracing_subscriber::fmt::FormatFields<'a> + 'static,
+    {
+        tracing_subscriber::fmt::format()
+            .compact()
+            .with_source_location(true)
+    }
+
+    let console_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .event_format(event_format());
+
+    let (file_layer, guard) = match log_file {
+        Some(path) => {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("invalid log file path: {}", path.display()))?;
+            let dir = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(std::path::Path::new("."));
+
+            let (writer, guard) = tracing_appender::non_blocking(rolling::daily(dir, file_name));
+            let layer = tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .event_format(event_format());
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::new("debug"))
+        .with(console_layer.and_then(file_layer))
+        .init();
+
+    Ok(guard)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::new("debug"))
-        .fmt_fields(tracing_subscriber::fmt::format::DefaultFields::new())
-        .event_format(
-            tracing_subscriber::fmt::format()
-                .compact()
-                .with_source_location(true),
-        )
-        .init();
+    let args: ServerArgs = argh::from_env();
+    let _log_guard = init_tracing(args.log_file.as_ref())?;
+
+    let format = match std::env::var(WIRE_FORMAT_ENV) {
+        Ok(v) => v.parse()?,
+        Err(_) => bincode_io::Format::default(),
+    };
+
+    anyhow::ensure!(args.max_rooms > 0, "--max-rooms must be at least 1");
+    let max_connections = args
+        .max_rooms
+        .checked_mul(CONNECTIONS_PER_ROOM)
+        .context("--max-rooms is too large")?;
 
-    Server::new(ADDR, protocol::DEFAULT_SERVER_PORT, SERVER_MAX_CONNECTION)?
+    Server::new(ADDR, protocol::DEFAULT_SERVER_PORT, max_connections)?
+        .with_max_rooms(args.max_rooms)
+        .with_format(format)
+        .with_read_buf_size(args.read_buf_size)
+        .with_auto_ack(args.auto_ack)
+        .with_replay_dir(args.replay_dir)
         .run()
         .await
 }
@@ -1,21 +1,53 @@
 use super::{InboundEvent, OutboundEvent};
-use crate::game::{ServerInternalEvent, WaitingRoom};
+use crate::game::{Lobby, ServerInternalEvent};
 use algo_core::player::PlayerId;
 use anyhow::{bail, Context};
-use protocol::WithMetadata;
-use std::{net::SocketAddr, sync::Arc};
+use bincode_io::Format;
+use protocol::{
+    heartbeat::{Heartbeat, HeartbeatSettings, HEARTBEAT_EV_ID},
+    server_to_client::{ServerError, SERVER_SHUTDOWN_EV_ID},
+    EventKind, WithMetadata, PROTOCOL_VERSION,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tokio::{
     net::{TcpListener, TcpSocket, TcpStream},
     sync::{
-        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        broadcast,
+        mpsc::{self, Receiver, UnboundedSender},
         Semaphore,
     },
+    time::{Instant, Interval, MissedTickBehavior},
 };
 use tracing::{debug, error, info, warn};
 
 type TcpStreamWrapper =
     bincode_io::TcpStreamWrapper<WithMetadata<InboundEvent>, WithMetadata<OutboundEvent>>;
 
+/// How long to wait for a new connection to send its protocol version.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The number of messages that can wait to be written to a connection.
+///
+/// A client falling further behind is disconnected,
+/// so that it cannot make the server buffer events without limit.
+/// The bursts catching up a client, which can be longer than this, wait for room instead.
+const OUTBOUND_CAPACITY: usize = 256;
+
+/// How long to wait for the connections to send the shutdown notice.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+const SHUTDOWN_NOTICE: WithMetadata<OutboundEvent> = WithMetadata {
+    kind: EventKind::Request,
+    id: SERVER_SHUTDOWN_EV_ID,
+    event: OutboundEvent::ServerShutdown,
+};
+
+const PING: WithMetadata<OutboundEvent> = WithMetadata {
+    kind: EventKind::Request,
+    id: HEARTBEAT_EV_ID,
+    event: OutboundEvent::Ping,
+};
+
 macro_rules! unexpected_event {
     ($event:expr $(,)?) => {
         bail!("server internal error: unexpected event: {:?}", $event)
@@ -25,7 +57,13 @@ macro_rules! unexpected_event {
 pub struct Server {
     socket: Option<TcpSocket>,
     port: u16,
+    max_connections: u16,
+    max_rooms: u16,
     semaphore: Arc<Semaphore>,
+    format: Format,
+    read_buf_size: usize,
+    auto_ack: bool,
+    replay_dir: Option<PathBuf>,
 }
 
 impl Server {
@@ -37,11 +75,50 @@ impl Server {
         let ret = Self {
             socket: Some(socket),
             port,
+            max_connections,
+            max_rooms: 1,
             semaphore: Arc::new(Semaphore::new(max_connections.into())),
+            format: Format::default(),
+            read_buf_size: protocol::DEFAULT_READ_BUF_SIZE,
+            auto_ack: false,
+            replay_dir: None,
         };
         Ok(ret)
     }
 
+    /// Sets how many games can be played at the same time.
+    pub fn with_max_rooms(mut self, max_rooms: u16) -> Self {
+        self.max_rooms = max_rooms;
+        self
+    }
+
+    /// Sets how messages are serialized on all connections.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the initial size of the buffer that each connection reads messages into.
+    pub fn with_read_buf_size(mut self, read_buf_size: usize) -> Self {
+        self.read_buf_size = read_buf_size;
+        self
+    }
+
+    /// Lets the server acknowledge the `GameEvent`s that require no decision
+    /// on behalf of the clients that support it, saving a round trip for each of them.
+    pub fn with_auto_ack(mut self, auto_ack: bool) -> Self {
+        self.auto_ack = auto_ack;
+        self
+    }
+
+    /// Sets the directory to save a replay of each finished game in.
+    ///
+    /// No replays are saved if `None`, which is the default.
+    pub fn with_replay_dir(mut self, replay_dir: Option<PathBuf>) -> Self {
+        self.replay_dir = replay_dir;
+        self
+    }
+
     pub async fn run(&mut self) -> anyhow::Result<()> {
         let listener: TcpListener = self
             .socket
@@ -51,30 +128,53 @@ impl Server {
 
         // Start the game server
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut game_server_task = tokio::spawn(async move { WaitingRoom::new(rx).run().await });
+        let lobby =
+            Lobby::new(rx, tx.clone(), self.max_rooms).with_replay_dir(self.replay_dir.clone());
+        let mut game_server_task = tokio::spawn(lobby.run());
+
+        let (shutdown_tx, _) = broadcast::channel(1);
 
-        info!("Server listening on port {}", self.port);
+        info!(
+            "Server listening on port {} ({}, up to {} rooms)",
+            self.port, self.format, self.max_rooms
+        );
 
         loop {
             tokio::select! {
                 res = &mut game_server_task => {
                     let res = res?;
                     warn!("game server closed: {:?}", res);
+                    self.shutdown(&shutdown_tx).await;
                     return res;
                 }
+                _ = tokio::signal::ctrl_c() => {
+                    info!("received Ctrl-C");
+                    self.shutdown(&shutdown_tx).await;
+                    return Ok(());
+                }
                 Ok((stream, socket_addr)) = listener.accept(), if self.semaphore.available_permits() > 0 => {
                     info!("connected to: {}", socket_addr);
 
                     let semaphore = self.semaphore.clone();
                     let tx_cloned = tx.clone();
+                    let shutdown_rx = shutdown_tx.subscribe();
+                    let format = self.format;
+                    let read_buf_size = self.read_buf_size;
+                    let auto_ack = self.auto_ack;
 
                     tokio::spawn(async move {
                         let _permit = semaphore.acquire().await.unwrap();
 
-                        if let Err(e) = PendingConnection::new(stream, socket_addr, tx_cloned)
-                            .run()
-                            .await
-                        {
+                        let conn = PendingConnection::new(
+                            stream,
+                            socket_addr,
+                            tx_cloned,
+                            shutdown_rx,
+                            format,
+                            read_buf_size,
+                            auto_ack,
+                        );
+                        if let Err(e) = conn.run().await {
                             warn!("disconnected from peer {}: {}", socket_addr, e);
                         }
                     });
@@ -82,12 +182,32 @@ impl Server {
             }
         }
     }
+
+    /// Tells all connections to notify the clients of the shutdown,
+    /// and waits for them to close.
+    async fn shutdown(&self, shutdown_tx: &broadcast::Sender<()>) {
+        if shutdown_tx.send(()).is_err() {
+            // No connections to close.
+            return;
+        }
+
+        let all_closed = self.semaphore.acquire_many(self.max_connections.into());
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, all_closed)
+            .await
+            .is_err()
+        {
+            warn!("timed out waiting for connections to close");
+        }
+    }
 }
 
 struct PendingConnection {
     stream: TcpStreamWrapper,
     socket_addr: SocketAddr,
     internal_tx: UnboundedSender<ServerInternalEvent>,
+    shutdown_rx: broadcast::Receiver<()>,
+    /// Whether the server acknowledges events for the clients that support it.
+    auto_ack: bool,
 }
 
 impl PendingConnection {
@@ -95,19 +215,36 @@ impl PendingConnection {
         stream: TcpStream,
         socket_addr: SocketAddr,
         internal_tx: UnboundedSender<ServerInternalEvent>,
+        shutdown_rx: broadcast::Receiver<()>,
+        format: Format,
+        read_buf_size: usize,
+        auto_ack: bool,
     ) -> Self {
         Self {
-            stream: TcpStreamWrapper::new(stream, 1024),
+            stream: TcpStreamWrapper::new(stream, read_buf_size, protocol::MAX_FRAME_LEN, format),
             socket_addr,
             internal_tx,
+            shutdown_rx,
+            auto_ack,
         }
     }
 
     async fn run(mut self) -> anyhow::Result<()> {
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, self.handshake())
+            .await
+            .context("timed out waiting for the handshake")??;
+
         let stream = &mut self.stream;
 
         loop {
-            let Ok(_) = stream.readable().await else {
+            let readable = tokio::select! {
+                v = stream.readable() => v,
+                _ = self.shutdown_rx.recv() => {
+                    stream.write(&SHUTDOWN_NOTICE).await?;
+                    return Ok(());
+                }
+            };
+            let Ok(_) = readable else {
                 break;
             };
 
@@ -126,15 +263,33 @@ impl PendingConnection {
                     }
 
                     match &data.event {
-                        InboundEvent::RequestJoin => {
+                        InboundEvent::RequestJoin {
+                            settings,
+                            name,
+                            vs_bot,
+                            auto_ack,
+                        } => {
                             // Create a channel to communicate with the game server.
-                            let (tx, mut rx) = mpsc::unbounded_channel();
+                            let (tx, mut rx) = mpsc::channel(OUTBOUND_CAPACITY);
 
                             // Send a request to join the game.
-                            self.internal_tx
-                                .send(ServerInternalEvent::RequestJoin(tx))?;
+                            self.internal_tx.send(ServerInternalEvent::RequestJoin {
+                                settings: settings.clone(),
+                                name: name.clone(),
+                                vs_bot: *vs_bot,
+                                auto_ack: *auto_ack && self.auto_ack,
+                                tx,
+                            })?;
 
-                            let resp = rx.recv().await.context("server internal error")?;
+                            let Some(resp) = rx.recv().await else {
+                                stream
+                                    .write(&data.response_to(OutboundEvent::Error {
+                                        error: ServerError::Internal,
+                                        detail: None,
+                                    }))
+                                    .await?;
+                                bail!("server internal error: channel closed");
+                            };
                             match resp {
                                 ServerInternalEvent::RequestJoinAccepted(info) => {
                                     let player_id = info.joined_player.assigned_player_id();
@@ -146,13 +301,104 @@ impl PendingConnection {
                                         )
                                         .await?;
 
-                                    return Connection::from_pending(self, rx, player_id)
+                                    return Connection::from_pending(self, rx, Some(player_id))
                                         .relay_events()
                                         .await;
                                 }
+                                ServerInternalEvent::RequestJoinRejected(error, reason) => {
+                                    warn!("rejected join of {}: {}", self.socket_addr, reason);
+                                    stream
+                                        .write(
+                                            &data.response_to(OutboundEvent::error(error, reason)),
+                                        )
+                                        .await?;
+                                }
                                 unexpected => unexpected_event!(unexpected),
                             }
                         }
+                        InboundEvent::Rejoin { player, token } => {
+                            let player_id = *player;
+
+                            // Create a channel to communicate with the game server.
+                            let (tx, mut rx) = mpsc::channel(OUTBOUND_CAPACITY);
+
+                            // Send a request to take back the seat.
+                            self.internal_tx
+                                .send(ServerInternalEvent::RequestRejoin(player_id, *token, tx))?;
+
+                            let Some(resp) = rx.recv().await else {
+                                stream
+                                    .write(&data.response_to(OutboundEvent::Error {
+                                        error: ServerError::Internal,
+                                        detail: None,
+                                    }))
+                                    .await?;
+                                bail!("server internal error: channel closed");
+                            };
+                            match resp {
+                                ServerInternalEvent::RequestRejoinAccepted => {
+                                    stream
+                                        .write(&data.response_to(OutboundEvent::RejoinAccepted))
+                                        .await?;
+
+                                    return Connection::from_pending(self, rx, Some(player_id))
+                                        .relay_events()
+                                        .await;
+                                }
+                                ServerInternalEvent::RequestRejoinRejected(error, reason) => {
+                                    warn!("rejected rejoin of {:?}: {}", player_id, reason);
+                                    stream
+                                        .write(
+                                            &data.response_to(OutboundEvent::error(error, reason)),
+                                        )
+                                        .await?;
+                                }
+                                unexpected => unexpected_event!(unexpected),
+                            }
+                        }
+                        InboundEvent::RequestSpectate => {
+                            // Create a channel to communicate with the game server.
+                            let (tx, mut rx) = mpsc::channel(OUTBOUND_CAPACITY);
+
+                            // Send a request to watch the game.
+                            self.internal_tx
+                                .send(ServerInternalEvent::RequestSpectate(tx))?;
+
+                            let Some(resp) = rx.recv().await else {
+                                stream
+                                    .write(&data.response_to(OutboundEvent::Error {
+                                        error: ServerError::Internal,
+                                        detail: None,
+                                    }))
+                                    .await?;
+                                bail!("server internal error: channel closed");
+                            };
+                            match resp {
+                                ServerInternalEvent::RequestSpectateAccepted(players) => {
+                                    stream
+                                        .write(&data.response_to(
+                                            OutboundEvent::RequestSpectateAccepted { players },
+                                        ))
+                                        .await?;
+
+                                    return Connection::from_pending(self, rx, None)
+                                        .relay_events()
+                                        .await;
+                                }
+                                ServerInternalEvent::RequestSpectateRejected(error, reason) => {
+                                    warn!("rejected spectator {}: {}", self.socket_addr, reason);
+                                    stream
+                                        .write(
+                                            &data.response_to(OutboundEvent::error(error, reason)),
+                                        )
+                                        .await?;
+                                }
+                                unexpected => unexpected_event!(unexpected),
+                            }
+                        }
+                        InboundEvent::Ping => {
+                            stream.write(&data.response_to(OutboundEvent::Pong)).await?;
+                        }
                         unexpected => {
                             warn!("ignoring unexpected event: {:?}", unexpected);
                             continue;
@@ -165,32 +411,75 @@ impl PendingConnection {
         info!("disconnected from: {}", self.socket_addr);
         Ok(())
     }
+
+    /// Checks that the client speaks the same protocol version as the server.
+    ///
+    /// A mismatched client is told both versions before the connection is closed.
+    async fn handshake(&mut self) -> anyhow::Result<()> {
+        let data = self
+            .stream
+            .read()
+            .await?
+            .context("connection closed before the handshake")?;
+
+        let InboundEvent::Hello { version } = data.event else {
+            self.stream
+                .write(&data.response_to(OutboundEvent::error(
+                    ServerError::VersionMismatch,
+                    "expected a handshake first",
+                )))
+                .await?;
+            bail!("expected a handshake, but received {:?}", data.event);
+        };
+
+        if version != PROTOCOL_VERSION {
+            self.stream
+                .write(&data.response_to(OutboundEvent::IncompatibleVersion {
+                    server: PROTOCOL_VERSION,
+                    client: version,
+                }))
+                .await?;
+            bail!(
+                "incompatible protocol version: {} (server: {})",
+                version,
+                PROTOCOL_VERSION
+            );
+        }
+
+        Ok(())
+    }
 }
 
 struct Connection {
     stream: TcpStreamWrapper,
     _socket_addr: SocketAddr,
     internal_tx: UnboundedSender<ServerInternalEvent>,
-    internal_rx: UnboundedReceiver<ServerInternalEvent>,
-    player_id: PlayerId,
+    internal_rx: Receiver<ServerInternalEvent>,
+    shutdown_rx: broadcast::Receiver<()>,
+    /// `None` for spectators.
+    player_id: Option<PlayerId>,
 }
 
 impl Connection {
     fn from_pending(
         conn: PendingConnection,
-        internal_rx: UnboundedReceiver<ServerInternalEvent>,
-        player_id: PlayerId,
+        internal_rx: Receiver<ServerInternalEvent>,
+        player_id: Option<PlayerId>,
     ) -> Self {
         Self {
             stream: conn.stream,
             _socket_addr: conn.socket_addr,
             internal_tx: conn.internal_tx,
             internal_rx,
+            shutdown_rx: conn.shutdown_rx,
             player_id,
         }
     }
 
     async fn relay_events(mut self) -> anyhow::Result<()> {
+        let mut heartbeat = Heartbeat::new(HeartbeatSettings::default());
+        let mut ping_timer = ping_timer(heartbeat.interval());
+
         loop {
             tokio::select! {
                 Ok(_) = self.stream.readable() => {
@@ -203,25 +492,86 @@ impl Connection {
                             return Err(e.into());
                         },
                         Ok(ev) => {
+                            heartbeat.received();
+
+                            match ev.event {
+                                InboundEvent::Ping => {
+                                    self.stream.write(&ev.response_to(OutboundEvent::Pong)).await?;
+                                    continue;
+                                }
+                                InboundEvent::Pong => continue,
+                                _ => (),
+                            }
+
                             debug!("from {} {:?}", self._socket_addr, ev);
-                            self.internal_tx.send(ServerInternalEvent::In(self.player_id, ev))?;
+
+                            // Spectators cannot affect the game.
+                            if let Some(player_id) = self.player_id {
+                                self.internal_tx.send(ServerInternalEvent::In(player_id, ev))?;
+                            }
                         }
                     }
                 }
-                Some(ev) = self.internal_rx.recv() => {
+                _ = self.shutdown_rx.recv() => {
+                    // Deliver what the game server sent before shutting down, e.g. `GameAborted`.
+                    while let Ok(ServerInternalEvent::Out(ev)) = self.internal_rx.try_recv() {
+                        self.stream.write(&ev).await?;
+                    }
+                    self.stream.write(&SHUTDOWN_NOTICE).await?;
+                    return Ok(());
+                }
+                _ = ping_timer.tick() => {
+                    if let Err(e) = heartbeat.ping() {
+                        self.notify_disconnected()?;
+                        return Err(e.into());
+                    }
+                    self.stream.write(&PING).await?;
+                }
+                ev = self.internal_rx.recv() => {
                     match ev {
-                        ServerInternalEvent::Out(ev) => {
+                        Some(ServerInternalEvent::Out(ev)) => {
                             self.stream.write(&ev).await?;
                         }
-                        unexpected => unexpected_event!(unexpected),
+                        Some(unexpected) => unexpected_event!(unexpected),
+                        None => return self.on_dropped_by_game_server().await,
                     }
                 }
             }
         }
     }
 
+    /// Closes the connection, as the game server no longer sends anything to it.
+    ///
+    /// This happens either when the server is shutting down,
+    /// or when the client fell too far behind the events.
+    async fn on_dropped_by_game_server(&mut self) -> anyhow::Result<()> {
+        let shutting_down = tokio::time::timeout(SHUTDOWN_TIMEOUT, self.shutdown_rx.recv())
+            .await
+            .is_ok();
+        if shutting_down {
+            self.stream.write(&SHUTDOWN_NOTICE).await?;
+            return Ok(());
+        }
+
+        // The game server may already be gone.
+        self.notify_disconnected().ok();
+        bail!("dropped by the game server");
+    }
+
     fn notify_disconnected(&self) -> Result<(), mpsc::error::SendError<ServerInternalEvent>> {
+        // Spectators are removed when the game server fails to send events to them.
+        let Some(player_id) = self.player_id else {
+            return Ok(());
+        };
+
         self.internal_tx
-            .send(ServerInternalEvent::ConnectionLost(self.player_id))
+            .send(ServerInternalEvent::ConnectionLost(player_id))
     }
 }
+
+/// Returns a timer that ticks every `period`, starting after the first period.
+fn ping_timer(period: Duration) -> Interval {
+    let mut timer = tokio::time::interval_at(Instant::now() + period, period);
+    timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    timer
+}
@@ -1,5 +1,10 @@
+use anyhow::bail;
+#[cfg(feature = "compression")]
+use anyhow::{ensure, Context as _};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{collections::VecDeque, future::Future, marker::PhantomData};
+use std::{
+    borrow::Cow, collections::VecDeque, fmt, future::Future, marker::PhantomData, str::FromStr,
+};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
@@ -24,20 +29,31 @@ impl ReadBincodeExt for TcpStream {
 
 #[derive(Debug, Clone)]
 pub struct BincodeReader<T: DeserializeOwned> {
+    /// Grows to fit the longest frame received so far.
     bytes: Vec<u8>,
+    /// The number of bytes at the start of `bytes` not processed yet,
+    /// which belong to a frame that has not been fully received.
+    filled: usize,
+    /// Frames declaring a longer payload are rejected.
+    max_frame_len: usize,
+    format: Format,
     pub data: VecDeque<T>,
 }
 
 impl<T: DeserializeOwned> BincodeReader<T> {
-    pub fn new(buf_size: usize) -> Self {
+    /// `buf_size` is the initial size of the read buffer.
+    pub fn new(buf_size: usize, max_frame_len: usize, format: Format) -> Self {
         Self {
-            bytes: vec![0; buf_size],
+            bytes: vec![0; buf_size.max(HEADER_LEN)],
+            filled: 0,
+            max_frame_len,
+            format,
             data: VecDeque::new(),
         }
     }
 
     fn try_read(&mut self, stream: &mut TcpStream) -> Result<(), TryReadBincodeError> {
-        match stream.try_read(&mut self.bytes) {
+        match stream.try_read(&mut self.bytes[self.filled..]) {
             Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                 Err(TryReadBincodeError::WouldBlock)
             }
@@ -50,22 +66,56 @@ impl<T: DeserializeOwned> BincodeReader<T> {
         }
     }
 
-    fn process_read_bytes(&mut self, n: usize) -> anyhow::Result<()> {
+    /// Processes `n` bytes newly read into the buffer.
+    ///
+    /// A frame split across reads is kept until the rest of it arrives.
+    fn process_read_bytes(&mut self, n: usize) -> Result<(), TryReadBincodeError> {
         let prev_len = self.data.len();
+        self.filled += n;
 
         let mut i = 0;
-        while i < n {
-            let len = u32::from_be_bytes(self.bytes[i..i + 4].try_into().unwrap()) as usize;
+        while let Some(header) = self.bytes[i..self.filled].first_chunk::<HEADER_LEN>() {
+            let codec = Codec::try_from(header[0])?;
+            let len = u32::from_be_bytes(header[1..].try_into().unwrap()) as usize;
+
+            // Checked before touching the payload,
+            // so that a corrupted header never causes an allocation.
+            if len > self.max_frame_len {
+                return Err(TryReadBincodeError::FrameTooLarge {
+                    len,
+                    max: self.max_frame_len,
+                });
+            }
+
+            let frame_len = HEADER_LEN + len;
+            if self.filled - i < frame_len {
+                // Wait for the rest, making room for the whole frame.
+                if self.bytes.len() < frame_len {
+                    debug!("growing the read buffer to {} bytes", frame_len);
+                    self.bytes.resize(frame_len, 0);
+                }
+                break;
+            }
 
-            i += 4;
+            i += HEADER_LEN;
+            let payload = &self.bytes[i..i + len];
 
-            debug!("deserializing bytes: range={}..{}", i, i + len);
-            self.data
-                .push_back(bincode::deserialize(&self.bytes[i..i + len])?);
+            debug!(
+                "deserializing bytes: range={}..{}, codec={:?}",
+                i,
+                i + len,
+                codec
+            );
+            let payload = codec.decode(payload, self.max_frame_len)?;
+            self.data.push_back(self.format.deserialize(&payload)?);
 
             i += len;
         }
 
+        // Move the incomplete frame, if any, to the start of the buffer.
+        self.bytes.copy_within(i..self.filled, 0);
+        self.filled -= i;
+
         info!("processed {} event(s)", self.data.len() - prev_len);
         Ok(())
     }
@@ -77,6 +127,8 @@ pub enum TryReadBincodeError {
     WouldBlock,
     #[error("read 0 bytes")]
     Read0Bytes,
+    #[error("frame too large: {len} bytes (max: {max})")]
+    FrameTooLarge { len: usize, max: usize },
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
@@ -89,49 +141,207 @@ impl TryReadBincodeError {
 
 #[derive(Debug, serde::Deserialize)]
 pub struct ReceivedSingle<T> {
+    _codec: u8,
     _len: u32,
     pub data: T,
 }
 
+/// How messages are serialized into the payloads of frames.
+///
+/// Both peers must use the same format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Bincode,
+    /// Human-readable, e.g. for debugging or for clients without a bincode implementation.
+    Json,
+}
+
+impl Format {
+    fn serialize<T: Serialize>(self, message: &T) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Bincode => Ok(bincode::serialize(message)?),
+            Self::Json => Ok(serde_json::to_vec(message)?),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(self, bytes: &[u8]) -> anyhow::Result<T> {
+        match self {
+            Self::Bincode => Ok(bincode::deserialize(bytes)?),
+            Self::Json => Ok(serde_json::from_slice(bytes)?),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bincode => "bincode",
+            Self::Json => "json",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bincode" => Ok(Self::Bincode),
+            "json" => Ok(Self::Json),
+            unknown => bail!("unknown format: {} (expected bincode or json)", unknown),
+        }
+    }
+}
+
+/// The length of a frame header: a codec tag followed by the payload length.
+const HEADER_LEN: usize = 5;
+
+/// Payloads shorter than this are sent uncompressed,
+/// as compression would not pay off.
+#[cfg(feature = "compression")]
+const COMPRESSION_THRESHOLD: usize = 256;
+
+/// How the payload of a frame is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Raw,
+    /// Requires the `compression` feature to decode.
+    Zstd,
+}
+
+impl Codec {
+    fn tag(self) -> u8 {
+        match self {
+            Self::Raw => 0,
+            Self::Zstd => 1,
+        }
+    }
+
+    /// Returns the bincode bytes of the payload.
+    ///
+    /// Decompressed payloads longer than `max_len` are rejected.
+    fn decode(self, payload: &[u8], max_len: usize) -> anyhow::Result<Cow<'_, [u8]>> {
+        match self {
+            Self::Raw => Ok(Cow::Borrowed(payload)),
+            #[cfg(feature = "compression")]
+            Self::Zstd => {
+                // Sized as the frame declares, instead of reserving `max_len` for every frame.
+                let len = zstd::zstd_safe::get_frame_content_size(payload)
+                    .ok()
+                    .flatten()
+                    .context("compressed frame does not declare its size")?;
+                ensure!(
+                    len <= max_len as u64,
+                    "decompressed frame too large: {} bytes (max: {})",
+                    len,
+                    max_len
+                );
+
+                Ok(Cow::Owned(zstd::bulk::decompress(payload, len as usize)?))
+            }
+            #[cfg(not(feature = "compression"))]
+            Self::Zstd => {
+                let _ = max_len;
+                bail!("received a compressed frame, but compression is not enabled")
+            }
+        }
+    }
+}
+
+impl TryFrom<u8> for Codec {
+    type Error = anyhow::Error;
+
+    fn try_from(tag: u8) -> Result<Self, Self::Error> {
+        match tag {
+            0 => Ok(Self::Raw),
+            1 => Ok(Self::Zstd),
+            unknown => bail!("unknown codec tag: {}", unknown),
+        }
+    }
+}
+
+fn frame(codec: Codec, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len();
+    let mut ret = vec![0; HEADER_LEN + len];
+    ret[0] = codec.tag();
+    ret[1..HEADER_LEN].copy_from_slice(&(len as u32).to_be_bytes());
+    ret[HEADER_LEN..].copy_from_slice(payload);
+
+    ret
+}
+
 pub trait SerializeBincodeExt: Serialize {
     fn to_bincode(&self) -> bincode::Result<Vec<u8>> {
         let data = bincode::serialize(self)?;
+        Ok(frame(Codec::Raw, &data))
+    }
 
-        let len = data.len();
-        let mut ret = vec![0; 4 + len];
-        ret[0..4].copy_from_slice(&(len as u32).to_be_bytes());
-        ret[4..].copy_from_slice(&data);
-
-        Ok(ret)
+    /// Same as [`to_bincode`], but compresses the payload unless it is small.
+    ///
+    /// [`to_bincode`]: `SerializeBincodeExt::to_bincode`
+    #[cfg(feature = "compression")]
+    fn to_bincode_compressed(&self) -> bincode::Result<Vec<u8>> {
+        let data = bincode::serialize(self)?;
+        Ok(frame_compressed(&data).map_err(bincode::ErrorKind::Io)?)
     }
 }
 
 impl<T: Serialize> SerializeBincodeExt for T {}
 
+/// Frames the payload, compressing it unless it is small.
+#[cfg(feature = "compression")]
+fn frame_compressed(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    if data.len() < COMPRESSION_THRESHOLD {
+        return Ok(frame(Codec::Raw, data));
+    }
+
+    let compressed = zstd::bulk::compress(data, 0)?;
+    Ok(frame(Codec::Zstd, &compressed))
+}
+
+/// Serializes the message to be sent, compressing it if enabled.
+fn encode<T: Serialize>(message: &T, format: Format) -> anyhow::Result<Vec<u8>> {
+    let data = format.serialize(message)?;
+
+    #[cfg(feature = "compression")]
+    return Ok(frame_compressed(&data)?);
+
+    #[cfg(not(feature = "compression"))]
+    Ok(frame(Codec::Raw, &data))
+}
+
 pub struct TcpStreamWrapper<I: DeserializeOwned, O: Serialize> {
     stream: TcpStream,
     reader: BincodeReader<I>,
+    /// The tail of a frame that the socket has not accepted yet.
+    pending_write: Vec<u8>,
     _marker: PhantomData<fn(&O)>,
 }
 
 impl<I: DeserializeOwned, O: Serialize> TcpStreamWrapper<I, O> {
-    pub fn new(stream: TcpStream, buf_size: usize) -> Self {
+    pub fn new(stream: TcpStream, buf_size: usize, max_frame_len: usize, format: Format) -> Self {
         Self {
             stream,
-            reader: BincodeReader::new(buf_size),
+            reader: BincodeReader::new(buf_size, max_frame_len, format),
+            pending_write: Vec::new(),
             _marker: PhantomData,
         }
     }
 
     pub async fn read(&mut self) -> anyhow::Result<Option<I>> {
-        if let Some(data) = self.reader.data.pop_front() {
-            return Ok(Some(data));
-        }
-
-        let n = self.stream.read(&mut self.reader.bytes).await?;
-        self.reader.process_read_bytes(n)?;
+        loop {
+            if let Some(data) = self.reader.data.pop_front() {
+                return Ok(Some(data));
+            }
 
-        Ok(self.reader.data.pop_front())
+            let reader = &mut self.reader;
+            let n = self.stream.read(&mut reader.bytes[reader.filled..]).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            reader.process_read_bytes(n)?;
+        }
     }
 
     pub async fn readable(&self) -> Result<(), std::io::Error> {
@@ -148,11 +358,21 @@ impl<I: DeserializeOwned, O: Serialize> TcpStreamWrapper<I, O> {
         }
 
         self.stream.try_read_bincode(&mut self.reader)?;
-        Ok(self.reader.data.pop_front().unwrap())
+
+        // Only a part of a frame may have arrived.
+        self.reader
+            .data
+            .pop_front()
+            .ok_or(TryReadBincodeError::WouldBlock)
     }
 
     pub async fn write(&mut self, message: &O) -> anyhow::Result<()> {
-        let msg = message.to_bincode()?;
+        let msg = encode(message, self.reader.format)?;
+
+        if !self.pending_write.is_empty() {
+            self.stream.write_all(&self.pending_write).await?;
+            self.pending_write.clear();
+        }
 
         self.stream.write_all(&msg).await?;
         Ok(())
@@ -162,20 +382,61 @@ impl<I: DeserializeOwned, O: Serialize> TcpStreamWrapper<I, O> {
         self.stream.writable()
     }
 
-    pub fn try_write(&self, message: &O) -> Result<(), TryWriteBincodeError> {
-        let msg = message
-            .to_bincode()
-            .map_err(|e| TryWriteBincodeError::Other(e.into()))?;
+    /// Tries to write the message without waiting.
+    ///
+    /// Returns `Err(WouldBlock)` if the message is not taken,
+    /// either because the socket is not ready or a previous frame is still pending.
+    /// The caller should retry with the same message once the stream is writable.
+    ///
+    /// If the socket accepts only part of the frame, the message is taken
+    /// and the rest is buffered, to be sent before the next message.
+    /// Use [`try_flush`] to send it without writing another message.
+    ///
+    /// [`try_flush`]: `TcpStreamWrapper::try_flush`
+    pub fn try_write(&mut self, message: &O) -> Result<(), TryWriteBincodeError> {
+        self.try_flush()?;
+
+        let msg = encode(message, self.reader.format)?;
+
+        let n = self.try_write_bytes(&msg)?;
+        if n < msg.len() {
+            debug!("partially written: {}/{} bytes", n, msg.len());
+            self.pending_write.extend_from_slice(&msg[n..]);
+        }
+
+        Ok(())
+    }
+
+    /// Tries to send the buffered tail of a partially written frame.
+    ///
+    /// Returns `Err(WouldBlock)` if some bytes are still pending.
+    pub fn try_flush(&mut self) -> Result<(), TryWriteBincodeError> {
+        if self.pending_write.is_empty() {
+            return Ok(());
+        }
+
+        let n = self.try_write_bytes(&self.pending_write)?;
+        self.pending_write.drain(..n);
+
+        if self.pending_write.is_empty() {
+            Ok(())
+        } else {
+            Err(TryWriteBincodeError::WouldBlock)
+        }
+    }
+
+    /// Returns `true` if a partially written frame is waiting to be flushed.
+    pub fn has_pending_write(&self) -> bool {
+        !self.pending_write.is_empty()
+    }
 
-        match self.stream.try_write(&msg) {
+    fn try_write_bytes(&self, bytes: &[u8]) -> Result<usize, TryWriteBincodeError> {
+        match self.stream.try_write(bytes) {
             Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                 Err(TryWriteBincodeError::WouldBlock)
             }
             Err(e) => Err(TryWriteBincodeError::Other(e.into())),
-            Ok(n) => {
-                debug_assert_eq!(n, msg.len());
-                Ok(())
-            }
+            Ok(n) => Ok(n),
         }
     }
 }
@@ -187,3 +448,177 @@ pub enum TryWriteBincodeError {
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+
+impl TryWriteBincodeError {
+    pub fn would_block(&self) -> bool {
+        matches!(self, Self::WouldBlock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpSocket;
+
+    /// Long enough to overflow the socket buffers at once.
+    const LARGE_LEN: usize = 1 << 20;
+
+    /// Returns bytes that do not compress, so that the frame is as long as the data.
+    fn noise(len: usize) -> Vec<u8> {
+        let mut x = 0x2545_f491_4f6c_dd1d_u64;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    }
+
+    /// Connects two streams over the loopback interface, with small socket buffers.
+    async fn stream_pair() -> (TcpStream, TcpStream) {
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_recv_buffer_size(4096).unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(1).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_send_buffer_size(4096).unwrap();
+        let (sender, receiver) = tokio::join!(socket.connect(addr), listener.accept());
+
+        (sender.unwrap(), receiver.unwrap().0)
+    }
+
+    /// Retries the write until it is done, waiting for the stream to be writable in between.
+    async fn until_written<O: Serialize>(
+        stream: &mut TcpStreamWrapper<(), O>,
+        mut write: impl FnMut(&mut TcpStreamWrapper<(), O>) -> Result<(), TryWriteBincodeError>,
+    ) {
+        loop {
+            match write(stream) {
+                Ok(()) => return,
+                Err(e) if e.would_block() => stream.writable().await.await.unwrap(),
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+
+    /// Hands the bytes to the reader in the chunks a stream would read into its buffer.
+    fn feed<T: DeserializeOwned>(
+        reader: &mut BincodeReader<T>,
+        mut bytes: &[u8],
+    ) -> Result<(), TryReadBincodeError> {
+        while !bytes.is_empty() {
+            let space = &mut reader.bytes[reader.filled..];
+            let n = space.len().min(bytes.len());
+            space[..n].copy_from_slice(&bytes[..n]);
+            bytes = &bytes[n..];
+
+            reader.process_read_bytes(n)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_buffer_grows_to_fit_large_frame() {
+        const BUF_SIZE: usize = 16;
+
+        let large = noise(4096);
+        let small = vec![1, 2, 3];
+        let mut bytes = encode(&large, Format::Bincode).unwrap();
+        bytes.extend(encode(&small, Format::Bincode).unwrap());
+
+        let mut reader = BincodeReader::<Vec<u8>>::new(BUF_SIZE, 8192, Format::Bincode);
+        feed(&mut reader, &bytes).unwrap();
+
+        assert!(reader.bytes.len() > BUF_SIZE);
+        assert_eq!(reader.data.pop_front(), Some(large));
+        assert_eq!(reader.data.pop_front(), Some(small));
+        assert_eq!(reader.filled, 0, "no bytes should be left over");
+    }
+
+    #[test]
+    fn oversized_header_is_rejected_before_allocating() {
+        let mut reader = BincodeReader::<Vec<u8>>::new(64, 1024, Format::Bincode);
+
+        // A header declaring a payload of about 2 GB, with no payload following it.
+        let mut header = vec![Codec::Raw.tag()];
+        header.extend_from_slice(&0x8000_0000_u32.to_be_bytes());
+
+        let err = feed(&mut reader, &header).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                TryReadBincodeError::FrameTooLarge {
+                    len: 0x8000_0000,
+                    max: 1024
+                }
+            ),
+            "{:?}",
+            err
+        );
+        assert_eq!(reader.bytes.len(), 64, "the buffer should not grow");
+        assert!(reader.bytes.capacity() < 1024);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_frame_round_trip() {
+        let message = vec![7_u32; 10_000];
+        let raw_len = bincode::serialized_size(&message).unwrap() as usize;
+
+        let bytes = encode(&message, Format::Bincode).unwrap();
+        assert_eq!(bytes[0], Codec::Zstd.tag());
+        assert!(bytes.len() < raw_len);
+
+        let mut reader = BincodeReader::<Vec<u32>>::new(64, raw_len, Format::Bincode);
+        feed(&mut reader, &bytes).unwrap();
+        assert_eq!(reader.data.pop_front(), Some(message));
+        assert!(reader.data.is_empty());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decompressed_size_is_capped() {
+        let compressed = zstd::bulk::compress(&[0; 4096], 0).unwrap();
+
+        assert!(Codec::Zstd.decode(&compressed, 4096).is_ok());
+        assert!(Codec::Zstd.decode(&compressed, 4095).is_err());
+    }
+
+    #[tokio::test]
+    async fn try_write_resumes_partial_frame() {
+        let (sender, receiver) = stream_pair().await;
+        let mut sender =
+            TcpStreamWrapper::<(), Vec<u8>>::new(sender, 64, 2 * LARGE_LEN, Format::Bincode);
+        let mut receiver =
+            TcpStreamWrapper::<Vec<u8>, ()>::new(receiver, 64, 2 * LARGE_LEN, Format::Bincode);
+
+        let large = noise(LARGE_LEN);
+        let small = vec![1, 2, 3];
+
+        until_written(&mut sender, |s| s.try_write(&large)).await;
+        assert!(
+            sender.has_pending_write(),
+            "the socket should take only part of the frame"
+        );
+
+        let received = tokio::spawn(async move {
+            let first = receiver.read().await.unwrap();
+            let second = receiver.read().await.unwrap();
+            (first, second)
+        });
+
+        // The next message waits for the rest of the frame.
+        until_written(&mut sender, |s| s.try_write(&small)).await;
+        until_written(&mut sender, TcpStreamWrapper::try_flush).await;
+        assert!(!sender.has_pending_write());
+
+        let (first, second) = received.await.unwrap();
+        assert_eq!(first, Some(large));
+        assert_eq!(second, Some(small));
+    }
+}